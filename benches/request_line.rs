@@ -0,0 +1,40 @@
+//! Timing of Request-Line parsing on the fast path for common GET requests, compared
+//! against requests that take the general parser.
+//!
+//! Run with `cargo bench`. Stable Rust has no benchmark harness, so this is a plain
+//! timing loop over `RequestLine::new`.
+
+extern crate uhttp_request;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use uhttp_request::RequestLine;
+
+/// Number of parses timed for each input.
+const ITERS: u32 = 2_000_000;
+
+/// Inputs to time, along with a label for each.
+const INPUTS: &[(&str, &[u8])] = &[
+    ("fast GET", b"GET /assets/app.js?v=1234567890 HTTP/1.1\r\n"),
+    ("general POST", b"POST /assets/app.js?v=123456789 HTTP/1.1\r\n"),
+    ("general HTTP/1.0", b"GET /assets/app.js?v=1234567890 HTTP/1.0\r\n"),
+];
+
+fn main() {
+    for &(label, input) in INPUTS {
+        // Warm up caches and branch predictors before timing.
+        for _ in 0..ITERS / 10 {
+            black_box(RequestLine::new(black_box(input)).unwrap());
+        }
+
+        let start = Instant::now();
+
+        for _ in 0..ITERS {
+            black_box(RequestLine::new(black_box(input)).unwrap());
+        }
+
+        let ns = start.elapsed().as_nanos() as f64 / ITERS as f64;
+        println!("{:<18} {:>6.1} ns/parse", label, ns);
+    }
+}
@@ -0,0 +1,750 @@
+//! Content negotiation over the Accept-* header fields [RFC7231§5.3].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::accept::{negotiate_charset, negotiate_encoding};
+//! use uhttp_request::accept::{negotiate_coding, negotiate_media_type, ContentCoding};
+//! use uhttp_request::accept::rank_languages;
+//!
+//! let prefs = [("br", 1.0), ("gzip", 0.9), ("identity", 0.1)];
+//!
+//! assert_eq!(negotiate_encoding(b"gzip, br;q=0.5", &prefs), Some("gzip"));
+//! assert_eq!(negotiate_encoding(b"deflate", &prefs), Some("identity"));
+//! assert_eq!(negotiate_encoding(b"*;q=0", &prefs), None);
+//!
+//! let offered = [ContentCoding::Br, ContentCoding::Gzip, ContentCoding::Identity];
+//!
+//! assert_eq!(negotiate_coding(b"x-gzip, br;q=0.5", &offered), Some(ContentCoding::Gzip));
+//! assert_eq!(negotiate_coding(b"identity;q=0", &offered), None);
+//!
+//! let offered = ["utf-8", "iso-8859-1"];
+//!
+//! assert_eq!(negotiate_charset(b"utf-8;q=0.8, *", &offered), Some("iso-8859-1"));
+//! assert_eq!(negotiate_charset(b"utf-16", &offered), None);
+//!
+//! let offered = ["application/json", "text/html"];
+//! let accept = b"text/*;q=0.8, text/html;q=0.9, */*;q=0.1";
+//!
+//! assert_eq!(negotiate_media_type(accept, &offered), Some("text/html"));
+//! assert_eq!(negotiate_media_type(b"image/*", &offered), None);
+//!
+//! let mut buf = [("", 0); 4];
+//! let ranked = rank_languages(b"fr;q=0.5, en-US, *;q=0.1", &mut buf).unwrap();
+//!
+//! assert_eq!(ranked, [("en-US", 1000), ("fr", 500), ("*", 100)]);
+//! ```
+
+use grammar::{is_token, trim_ows};
+use list::{self, ListValues};
+use params::{self, split_unquoted, Params};
+use {Error, Result};
+
+/// Choose the content-coding to apply to a response, given the client's Accept-Encoding
+/// header value [RFC7231§5.3.4] and the server's supported codings, each with a
+/// preference weight from 0 to 1.
+///
+/// Each supported coding is scored by the product of its server weight and its client
+/// qvalue, taken from the entry naming it or else from any `*` entry, compared
+/// case-insensitively, and the highest-scoring coding is chosen, with ties going to the
+/// earliest supported coding. If no coding scores above zero, `identity` is chosen as a
+/// fallback when it's supported and the client hasn't explicitly excluded it, such as
+/// with `identity;q=0` or `*;q=0`. Malformed entries are ignored.
+///
+/// Return `None` if no supported coding is acceptable, in which case a server may send a
+/// 406 Not Acceptable response or fall back to an unencoded response anyway. If the
+/// request has no Accept-Encoding header, any coding is acceptable [RFC7231§5.3.4], so
+/// this shouldn't be called.
+pub fn negotiate_encoding<'s>(header: &[u8], server_prefs: &[(&'s str, f32)])
+    -> Option<&'s str>
+{
+    let mut best: Option<(&str, f32)> = None;
+    let mut fallback = None;
+
+    for &(coding, pref) in server_prefs {
+        if pref.is_nan() || pref <= 0.0 {
+            continue;
+        }
+
+        match qvalue(header, coding) {
+            Some(0) => {},
+            Some(q) => {
+                let score = q as f32 / 1000.0 * pref;
+
+                if best.is_none_or(|b| score > b.1) {
+                    best = Some((coding, score));
+                }
+            },
+            None if coding.eq_ignore_ascii_case("identity") => {
+                fallback = fallback.or(Some(coding));
+            },
+            None => {},
+        }
+    }
+
+    best.map(|b| b.0).or(fallback)
+}
+
+/// A content-coding from an Accept-Encoding header value [RFC7231§3.1.2.1].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ContentCoding<'a> {
+    /// No encoding.
+    Identity,
+    /// The Brotli coding [RFC7932].
+    Br,
+    /// The compress coding [RFC7230§4.2.1].
+    Compress,
+    /// The deflate coding [RFC7230§4.2.2].
+    Deflate,
+    /// The gzip coding [RFC7230§4.2.3].
+    Gzip,
+    /// The Zstandard coding [RFC8878§7.2].
+    Zstd,
+    /// The `*` wildcard, matching any coding not otherwise listed.
+    Any,
+    /// Any other coding, with its name as given.
+    Unknown(&'a str),
+}
+
+impl<'a> ContentCoding<'a> {
+    /// Create a new `ContentCoding` from the given name, compared case-insensitively.
+    pub fn new(name: &'a str) -> Self {
+        let is = |s: &str| name.eq_ignore_ascii_case(s);
+
+        if is("identity") {
+            ContentCoding::Identity
+        } else if is("br") {
+            ContentCoding::Br
+        } else if is("compress") || is("x-compress") {
+            ContentCoding::Compress
+        } else if is("deflate") {
+            ContentCoding::Deflate
+        } else if is("gzip") || is("x-gzip") {
+            ContentCoding::Gzip
+        } else if is("zstd") {
+            ContentCoding::Zstd
+        } else if name == "*" {
+            ContentCoding::Any
+        } else {
+            ContentCoding::Unknown(name)
+        }
+    }
+
+    /// Check if this is the same coding as the given one, comparing unknown codings
+    /// case-insensitively.
+    fn is(&self, other: ContentCoding) -> bool {
+        match (*self, other) {
+            (ContentCoding::Unknown(a), ContentCoding::Unknown(b)) =>
+                a.eq_ignore_ascii_case(b),
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Create an iterator over the codings in the given Accept-Encoding header value, each
+/// with its qvalue in thousandths, defaulting to 1000.
+pub fn encodings<'a>(header: &'a [u8]) -> Encodings<'a> {
+    Encodings(list::values(header))
+}
+
+/// Iterator over the codings in an Accept-Encoding header value.
+///
+/// Empty list elements are skipped, and a malformed entry, such as one with an invalid
+/// qvalue or a parameter other than `q`, yields `Error::Syntax`.
+#[derive(Clone, Debug)]
+pub struct Encodings<'a>(ListValues<'a>);
+
+impl<'a> Iterator for Encodings<'a> {
+    type Item = Result<(ContentCoding<'a>, u16)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_weighted)
+            .map(|(name, q)| (ContentCoding::new(name), q)))
+    }
+}
+
+/// Choose the content-coding to apply to a response, given the client's Accept-Encoding
+/// header value [RFC7231§5.3.4] and the codings the server supports, in order of
+/// preference.
+///
+/// The supported coding with the highest client qvalue, taken from the entry naming it
+/// or else from any `*` entry, is chosen, with ties going to the earliest supported
+/// coding. `identity` is acceptable even when unlisted, as a fallback, unless it's
+/// explicitly excluded with `identity;q=0` or `*;q=0`. Malformed entries are ignored.
+///
+/// This is like `negotiate_encoding`, but with typed codings, so aliases like `x-gzip`
+/// are recognized.
+pub fn negotiate_coding<'s>(header: &[u8], offered: &[ContentCoding<'s>])
+    -> Option<ContentCoding<'s>>
+{
+    let mut best: Option<(ContentCoding, u16)> = None;
+    let mut fallback = None;
+
+    for &coding in offered {
+        match coding_qvalue(header, coding) {
+            Some(q) if q > 0 && best.is_none_or(|b| q > b.1) => {
+                best = Some((coding, q));
+            },
+            Some(_) => {},
+            None if coding == ContentCoding::Identity => {
+                fallback = fallback.or(Some(coding));
+            },
+            None => {},
+        }
+    }
+
+    best.map(|b| b.0).or(fallback)
+}
+
+/// Find the qvalue, in thousandths, that the given Accept-Encoding header value assigns
+/// to the given coding, from the entry naming it or else from any `*` entry.
+fn coding_qvalue(header: &[u8], coding: ContentCoding) -> Option<u16> {
+    let mut wildcard = None;
+
+    for (c, q) in encodings(header).filter_map(|e| e.ok()) {
+        if c.is(coding) {
+            return Some(q);
+        }
+
+        if c == ContentCoding::Any {
+            wildcard = Some(q);
+        }
+    }
+
+    wildcard
+}
+
+/// Choose the charset to use for a response, given the client's Accept-Charset header
+/// value [RFC7231§5.3.3] and the charsets the server can offer.
+///
+/// The offered charset with the highest client qvalue, taken from the entry naming it or
+/// else from any `*` entry, compared case-insensitively, is chosen, with ties going to
+/// the earliest offered charset. Charsets the client doesn't mention when there's no `*`
+/// entry, or those with a qvalue of 0, aren't acceptable. Malformed entries are ignored.
+///
+/// Return `None` if no offered charset is acceptable, in which case a server may send a
+/// 406 Not Acceptable response or disregard the header field. If the request has no
+/// Accept-Charset header, any charset is acceptable, so this shouldn't be called.
+pub fn negotiate_charset<'s>(header: &[u8], offered: &[&'s str]) -> Option<&'s str> {
+    let mut best: Option<(&str, u16)> = None;
+
+    for &charset in offered {
+        match qvalue(header, charset) {
+            Some(0) | None => {},
+            Some(q) => if best.is_none_or(|b| q > b.1) {
+                best = Some((charset, q));
+            },
+        }
+    }
+
+    best.map(|b| b.0)
+}
+
+/// Create an iterator over the language ranges [RFC4647§2.1] in the given
+/// Accept-Language header value [RFC7231§5.3.5], in the order given, each with its
+/// qvalue in thousandths, defaulting to 1000.
+pub fn language_ranges<'a>(header: &'a [u8]) -> LanguageRanges<'a> {
+    LanguageRanges(list::values(header))
+}
+
+/// Iterator over the language ranges in an Accept-Language header value.
+///
+/// Empty list elements are skipped, and a malformed entry, such as one with an invalid
+/// qvalue or a subtag longer than eight characters, yields `Error::Syntax`.
+#[derive(Clone, Debug)]
+pub struct LanguageRanges<'a>(ListValues<'a>);
+
+impl<'a> Iterator for LanguageRanges<'a> {
+    type Item = Result<(&'a str, u16)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(|entry| {
+            let p = params::parameterized(entry)?;
+
+            if !is_language_range(p.base) {
+                return Err(Error::Syntax);
+            }
+
+            let mut q = 1000;
+
+            for param in p.params {
+                let param = param?;
+
+                if !param.name.eq_ignore_ascii_case("q") {
+                    return Err(Error::Syntax);
+                }
+
+                q = param.val.and_then(parse_qvalue).ok_or(Error::Syntax)?;
+            }
+
+            Ok((p.base, q))
+        }))
+    }
+}
+
+/// Rank the language ranges in the given Accept-Language header value by qvalue,
+/// writing them into the given buffer from most to least preferred.
+///
+/// Ranges with equal qvalues keep the order they were given in, and ranges with a
+/// qvalue of 0, which mark languages as unacceptable, are left out. Malformed entries
+/// are ignored. On success, return the ranked prefix of the buffer. If the buffer can't
+/// hold every range, return `Error::Capacity`.
+pub fn rank_languages<'a, 'b>(header: &'a [u8], out: &'b mut [(&'a str, u16)])
+    -> Result<&'b [(&'a str, u16)]>
+{
+    let mut len = 0;
+
+    for (range, q) in language_ranges(header).filter_map(|r| r.ok()) {
+        if q == 0 {
+            continue;
+        }
+
+        if len == out.len() {
+            return Err(Error::Capacity);
+        }
+
+        // Insert after every range at least as preferred, keeping the sort stable.
+        let idx = out[..len].iter().position(|r| r.1 < q).unwrap_or(len);
+
+        out[idx..=len].rotate_right(1);
+        out[idx] = (range, q);
+        len += 1;
+    }
+
+    Ok(&out[..len])
+}
+
+/// Check if the given string is a language range [RFC4647§2.1], either `*` or a primary
+/// subtag of letters followed by alphanumeric subtags, each with 1 to 8 characters.
+fn is_language_range(range: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+
+    range.split('-').enumerate().all(|(i, tag)| {
+        (1..=8).contains(&tag.len()) && tag.bytes().all(|b| {
+            if i == 0 { b.is_ascii_alphabetic() } else { b.is_ascii_alphanumeric() }
+        })
+    })
+}
+
+/// A media range from an Accept header value [RFC7231§5.3.2].
+#[derive(Clone, Debug)]
+pub struct MediaRange<'a> {
+    /// Type, which may be `*`, such as `text`.
+    pub ty: &'a str,
+
+    /// Subtype, which may be `*`, such as `html`.
+    pub subtype: &'a str,
+
+    /// Iterator over the media type parameters, which are those before the `q` parameter.
+    pub params: Params<'a>,
+
+    /// Qvalue in thousandths, defaulting to 1000.
+    pub q: u16,
+}
+
+impl<'a> MediaRange<'a> {
+    /// Check if the range matches the given media type, returning how specific the match
+    /// is, from `*/*` being least specific up to a full type with parameters.
+    ///
+    /// Every parameter of the range must be given by the media type, with names compared
+    /// case-insensitively and values compared after their quotes are stripped.
+    fn matches(&self, other: &MediaRange) -> Option<(u8, usize)> {
+        let level = if self.ty == "*" {
+            0
+        } else if !self.ty.eq_ignore_ascii_case(other.ty) {
+            return None;
+        } else if self.subtype == "*" {
+            1
+        } else if !self.subtype.eq_ignore_ascii_case(other.subtype) {
+            return None;
+        } else {
+            2
+        };
+
+        let mut count = 0;
+
+        for p in self.params.clone() {
+            let p = p.ok()?;
+            let val = p.val.map(params::unquote);
+
+            let found = other.params.clone().filter_map(|o| o.ok()).any(|o| {
+                o.name.eq_ignore_ascii_case(p.name) && o.val.map(params::unquote) == val
+            });
+
+            if !found {
+                return None;
+            }
+
+            count += 1;
+        }
+
+        Some((level, count))
+    }
+}
+
+/// Create an iterator over the media ranges in the given Accept header value.
+pub fn media_ranges<'a>(header: &'a [u8]) -> MediaRanges<'a> {
+    MediaRanges(list::values(header))
+}
+
+/// Iterator over the media ranges in an Accept header value.
+///
+/// Empty list elements are skipped, and a malformed range, such as one with an invalid
+/// qvalue or a `*` type with a specific subtype, yields `Error::Syntax`.
+#[derive(Clone, Debug)]
+pub struct MediaRanges<'a>(ListValues<'a>);
+
+impl<'a> Iterator for MediaRanges<'a> {
+    type Item = Result<MediaRange<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_media_range))
+    }
+}
+
+/// Choose the media type to use for a response, given the client's Accept header value
+/// [RFC7231§5.3.2] and the media types the server can offer, such as `text/html` or
+/// `text/html;level=1`.
+///
+/// Each offered type gets the qvalue of the most specific range matching it, so
+/// `text/html;level=1` takes precedence over `text/html`, which takes precedence over
+/// `text/*` and then `*/*`. The offered type with the highest qvalue is chosen, with ties
+/// going to the earliest offered type. Types matching no range, or whose qvalue is 0,
+/// aren't acceptable. Malformed ranges and offered types are ignored.
+///
+/// Return `None` if no offered type is acceptable, in which case a server may send a 406
+/// Not Acceptable response or disregard the header field. If the request has no Accept
+/// header, any media type is acceptable, so this shouldn't be called.
+pub fn negotiate_media_type<'s>(header: &[u8], offered: &[&'s str]) -> Option<&'s str> {
+    let mut best: Option<(&str, u16)> = None;
+
+    for &ty in offered {
+        let other = match parse_media_range(ty.as_bytes()) {
+            Ok(other) => other,
+            Err(_) => continue,
+        };
+
+        let q = media_ranges(header)
+            .filter_map(|r| r.ok())
+            .filter_map(|r| Some((r.matches(&other)?, r.q)))
+            .max_by_key(|&(spec, _)| spec)
+            .map(|(_, q)| q);
+
+        match q {
+            Some(0) | None => {},
+            Some(q) => if best.is_none_or(|b| q > b.1) {
+                best = Some((ty, q));
+            },
+        }
+    }
+
+    best.map(|b| b.0)
+}
+
+/// Parse the given media range and its qvalue, ignoring any accept-ext parameters after
+/// the qvalue.
+fn parse_media_range(range: &[u8]) -> Result<MediaRange<'_>> {
+    let p = params::parameterized(range)?;
+    let (ty, subtype) = p.media_type()?;
+
+    if ty == "*" && subtype != "*" {
+        return Err(Error::Syntax);
+    }
+
+    let (_, mut rest) = split_unquoted(range, b';')?;
+    let start = range.len() - rest.map_or(0, |r| r.len());
+    let mut end = range.len();
+    let mut q = 1000;
+
+    // The `q` parameter divides media type parameters from extensions.
+    while let Some(r) = rest {
+        let (param, next) = split_unquoted(r, b';')?;
+
+        if let Some(p) = params::iter(param).next() {
+            let p = p?;
+
+            if p.name.eq_ignore_ascii_case("q") {
+                q = p.val.and_then(parse_qvalue).ok_or(Error::Syntax)?;
+                end = range.len() - r.len();
+                break;
+            }
+        }
+
+        rest = next;
+    }
+
+    Ok(MediaRange { ty, subtype, params: params::iter(&range[start..end]), q })
+}
+
+/// Find the qvalue, in thousandths, that the given Accept-* header value assigns to the
+/// given name, from the entry naming it or else from any `*` entry.
+fn qvalue(header: &[u8], name: &str) -> Option<u16> {
+    let mut wildcard = None;
+
+    for (n, q) in weighted(header) {
+        if n.eq_ignore_ascii_case(name) {
+            return Some(q);
+        }
+
+        if n == "*" {
+            wildcard = Some(q);
+        }
+    }
+
+    wildcard
+}
+
+/// Create an iterator over the well-formed `(name, qvalue)` entries in the given Accept-*
+/// header value, with qvalues in thousandths and defaulting to 1000.
+///
+/// Entries are validated as with `Encodings`, and malformed ones are skipped.
+fn weighted<'a>(header: &'a [u8]) -> impl Iterator<Item = (&'a str, u16)> + 'a {
+    list::values(header).filter_map(|entry| entry.and_then(parse_weighted).ok())
+}
+
+/// Parse the given list element into a token name and its qvalue in thousandths,
+/// allowing no parameter other than `q`.
+fn parse_weighted(entry: &[u8]) -> Result<(&str, u16)> {
+    let p = params::parameterized(entry)?;
+
+    if !is_token(p.base.as_bytes()) {
+        return Err(Error::Syntax);
+    }
+
+    let mut q = 1000;
+
+    for param in p.params {
+        let param = param?;
+
+        if !param.name.eq_ignore_ascii_case("q") {
+            return Err(Error::Syntax);
+        }
+
+        q = param.val.and_then(parse_qvalue).ok_or(Error::Syntax)?;
+    }
+
+    Ok((p.base, q))
+}
+
+/// Parse the given qvalue [RFC7231§5.3.1] into thousandths.
+fn parse_qvalue(val: &[u8]) -> Option<u16> {
+    let val = trim_ows(val);
+
+    let (int, frac) = match val.iter().position(|&b| b == b'.') {
+        Some(idx) => (&val[..idx], &val[idx + 1..]),
+        None => (val, &b""[..]),
+    };
+
+    if frac.len() > 3 || !frac.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let thousandths = frac.iter().chain(b"000".iter()).take(3)
+        .fold(0, |n, &b| n * 10 + (b - b'0') as u16);
+
+    match int {
+        b"0" => Some(thousandths),
+        b"1" if thousandths == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_qvalue() {
+        assert_eq!(parse_qvalue(b"0"), Some(0));
+        assert_eq!(parse_qvalue(b"0."), Some(0));
+        assert_eq!(parse_qvalue(b"0.5"), Some(500));
+        assert_eq!(parse_qvalue(b"0.123"), Some(123));
+        assert_eq!(parse_qvalue(b"1"), Some(1000));
+        assert_eq!(parse_qvalue(b"1.000"), Some(1000));
+        assert_eq!(parse_qvalue(b"1.001"), None);
+        assert_eq!(parse_qvalue(b"0.1234"), None);
+        assert_eq!(parse_qvalue(b"2"), None);
+        assert_eq!(parse_qvalue(b".5"), None);
+        assert_eq!(parse_qvalue(b"0.a"), None);
+        assert_eq!(parse_qvalue(b""), None);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let w: Vec<_> = weighted(
+            b"gzip, br;q=0.8 , ,x;Q=0, z;level=1, bad coding, y;q=3, \"a,b\", *;q=0.1"
+        ).collect();
+        assert_eq!(w, vec![("gzip", 1000), ("br", 800), ("x", 0), ("*", 100)]);
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        let prefs = [("br", 1.0), ("gzip", 0.9), ("identity", 0.1)];
+
+        assert_eq!(negotiate_encoding(b"gzip, br", &prefs), Some("br"));
+        assert_eq!(negotiate_encoding(b"gzip, BR;q=0.5", &prefs), Some("gzip"));
+        assert_eq!(negotiate_encoding(b"*", &prefs), Some("br"));
+        assert_eq!(negotiate_encoding(b"gzip;q=0.1, identity", &prefs), Some("identity"));
+
+        // Identity is acceptable unless excluded.
+        assert_eq!(negotiate_encoding(b"", &prefs), Some("identity"));
+        assert_eq!(negotiate_encoding(b"deflate", &prefs), Some("identity"));
+        assert_eq!(negotiate_encoding(b"br;q=0, gzip;q=0", &prefs), Some("identity"));
+        assert_eq!(negotiate_encoding(b"br;q=0.5, identity;q=0", &prefs), Some("br"));
+        assert_eq!(negotiate_encoding(b"deflate, identity;q=0", &prefs), None);
+        assert_eq!(negotiate_encoding(b"deflate, *;q=0", &prefs), None);
+        assert_eq!(negotiate_encoding(b"gzip;level=1", &prefs), Some("identity"));
+        assert_eq!(negotiate_encoding(b"br", &[("gzip", 1.0)]), None);
+
+        // Ties go to the earliest server preference.
+        assert_eq!(negotiate_encoding(b"a, b", &[("b", 1.0), ("a", 1.0)]), Some("b"));
+
+        // Disabled server codings are never chosen.
+        assert_eq!(negotiate_encoding(b"br", &[("br", 0.0), ("gzip", 1.0)]), None);
+        assert_eq!(negotiate_encoding(b"br", &[("br", f32::NAN)]), None);
+    }
+
+    #[test]
+    fn test_encodings() {
+        let e: Vec<_> = encodings(b"GZIP, br;q=0.8, , X-Foo;Q=0, *;q=0.1").collect();
+        assert_eq!(e, [
+            Ok((ContentCoding::Gzip, 1000)),
+            Ok((ContentCoding::Br, 800)),
+            Ok((ContentCoding::Unknown("X-Foo"), 0)),
+            Ok((ContentCoding::Any, 100)),
+        ]);
+
+        assert_eq!(encodings(b"gzip;level=1").next(), Some(Err(Error::Syntax)));
+        assert_eq!(encodings(b"gzip;q=1.5").next(), Some(Err(Error::Syntax)));
+        assert_eq!(encodings(b"gzip;q").next(), Some(Err(Error::Syntax)));
+        assert_eq!(encodings(b"g zip").next(), Some(Err(Error::Syntax)));
+    }
+
+    #[test]
+    fn test_negotiate_coding() {
+        use self::ContentCoding::*;
+
+        let offered = [Br, Gzip, Identity];
+
+        assert_eq!(negotiate_coding(b"gzip, br", &offered), Some(Br));
+        assert_eq!(negotiate_coding(b"x-gzip, br;q=0.5", &offered), Some(Gzip));
+        assert_eq!(negotiate_coding(b"*", &offered), Some(Br));
+        assert_eq!(negotiate_coding(b"*, br;q=0", &offered), Some(Gzip));
+        assert_eq!(negotiate_coding(b"gzip;q=0.1, identity", &offered), Some(Identity));
+        assert_eq!(negotiate_coding(b"x-foo, X-FOO;q=0", &[Unknown("x-Foo")]),
+            Some(Unknown("x-Foo")));
+
+        // Identity is acceptable unless excluded.
+        assert_eq!(negotiate_coding(b"", &offered), Some(Identity));
+        assert_eq!(negotiate_coding(b"deflate", &offered), Some(Identity));
+        assert_eq!(negotiate_coding(b"br;q=0.5, identity;q=0", &offered), Some(Br));
+        assert_eq!(negotiate_coding(b"deflate, identity;q=0", &offered), None);
+        assert_eq!(negotiate_coding(b"deflate, *;q=0", &offered), None);
+        assert_eq!(negotiate_coding(b"br", &[Gzip]), None);
+        assert_eq!(negotiate_coding(b"br", &[]), None);
+    }
+
+    #[test]
+    fn test_negotiate_charset() {
+        let offered = ["utf-8", "iso-8859-1", "us-ascii"];
+
+        assert_eq!(negotiate_charset(b"UTF-8", &offered), Some("utf-8"));
+        assert_eq!(negotiate_charset(b"utf-8;q=0.5, iso-8859-1", &offered),
+            Some("iso-8859-1"));
+        assert_eq!(negotiate_charset(b"*", &offered), Some("utf-8"));
+        assert_eq!(negotiate_charset(b"utf-8;q=0, *;q=0.1", &offered), Some("iso-8859-1"));
+        assert_eq!(negotiate_charset(b"us-ascii;q=0.2, *;q=0.1", &offered),
+            Some("us-ascii"));
+
+        // Ties go to the earliest offered charset.
+        assert_eq!(negotiate_charset(b"us-ascii, utf-8", &offered), Some("utf-8"));
+
+        // Unlisted charsets are unacceptable without a wildcard.
+        assert_eq!(negotiate_charset(b"utf-16", &offered), None);
+        assert_eq!(negotiate_charset(b"", &offered), None);
+        assert_eq!(negotiate_charset(b"*;q=0", &offered), None);
+        assert_eq!(negotiate_charset(b"utf-8", &[]), None);
+    }
+
+    #[test]
+    fn test_language_ranges() {
+        let l: Vec<_> = language_ranges(b"en-US, fr;q=0.5, , zh-Hant-TW;Q=0, *;q=0.1")
+            .collect();
+        assert_eq!(l, [Ok(("en-US", 1000)), Ok(("fr", 500)), Ok(("zh-Hant-TW", 0)),
+            Ok(("*", 100))]);
+
+        assert_eq!(language_ranges(b"de-1996").next(), Some(Ok(("de-1996", 1000))));
+        assert_eq!(language_ranges(b"1de").next(), Some(Err(Error::Syntax)));
+        assert_eq!(language_ranges(b"en-").next(), Some(Err(Error::Syntax)));
+        assert_eq!(language_ranges(b"en-*").next(), Some(Err(Error::Syntax)));
+        assert_eq!(language_ranges(b"abcdefghi").next(), Some(Err(Error::Syntax)));
+        assert_eq!(language_ranges(b"en;q=2").next(), Some(Err(Error::Syntax)));
+        assert_eq!(language_ranges(b"en;x=1").next(), Some(Err(Error::Syntax)));
+    }
+
+    #[test]
+    fn test_rank_languages() {
+        let mut buf = [("", 0); 4];
+
+        assert_eq!(rank_languages(b"a;q=0.2, b, c;q=0.2, d;q=0.9", &mut buf),
+            Ok(&[("b", 1000), ("d", 900), ("a", 200), ("c", 200)][..]));
+        assert_eq!(rank_languages(b"a;q=0, b;q=0.5, bad!, *;q=0.5", &mut buf),
+            Ok(&[("b", 500), ("*", 500)][..]));
+        assert_eq!(rank_languages(b"", &mut buf), Ok(&[][..]));
+        assert_eq!(rank_languages(b"a, b, c, d, e", &mut buf), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_media_ranges() {
+        let mut r = media_ranges(b"text/html;level=1;q=0.5;ext=\"a,b\", ,*/*, text/*;Q=0");
+
+        let m = r.next().unwrap().unwrap();
+        assert_eq!((m.ty, m.subtype, m.q), ("text", "html", 500));
+        let p: Vec<_> = m.params.map(|p| p.unwrap()).collect();
+        assert_eq!(p.len(), 1);
+        assert_eq!((p[0].name, p[0].val), ("level", Some(&b"1"[..])));
+
+        let m = r.next().unwrap().unwrap();
+        assert_eq!((m.ty, m.subtype, m.q), ("*", "*", 1000));
+        assert_eq!(m.params.count(), 0);
+
+        let m = r.next().unwrap().unwrap();
+        assert_eq!((m.ty, m.subtype, m.q), ("text", "*", 0));
+        assert!(r.next().is_none());
+
+        assert!(media_ranges(b"*/html").next().unwrap().is_err());
+        assert!(media_ranges(b"text").next().unwrap().is_err());
+        assert!(media_ranges(b"text/html;q=2").next().unwrap().is_err());
+        assert!(media_ranges(b"text/html;q").next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_negotiate_media_type() {
+        // Example from [RFC7231§5.3.2].
+        let accept = b"text/*;q=0.3, text/html;q=0.7, text/html;level=1, \
+                       text/html;level=2;q=0.4, */*;q=0.5";
+
+        let q = |ty| negotiate_media_type(accept, &[ty]);
+        assert_eq!(q("text/html;level=1"), Some("text/html;level=1"));
+        assert_eq!(negotiate_media_type(accept, &["text/html;level=2", "text/html"]),
+            Some("text/html"));
+        assert_eq!(negotiate_media_type(accept, &["text/plain", "image/jpeg"]),
+            Some("image/jpeg"));
+        assert_eq!(negotiate_media_type(accept, &["text/plain", "text/html;level=3"]),
+            Some("text/html;level=3"));
+
+        let offered = ["application/json", "text/html"];
+
+        assert_eq!(negotiate_media_type(b"*/*", &offered), Some("application/json"));
+        assert_eq!(negotiate_media_type(b"TEXT/HTML", &offered), Some("text/html"));
+        assert_eq!(negotiate_media_type(b"*/*, application/json;q=0", &offered),
+            Some("text/html"));
+        assert_eq!(negotiate_media_type(b"text/plain", &offered), None);
+        assert_eq!(negotiate_media_type(b"", &offered), None);
+        assert_eq!(negotiate_media_type(b"*/*", &["bad"]), None);
+        assert_eq!(negotiate_media_type(b"text/html;charset=\"utf-8\"",
+            &["text/html;charset=utf-8"]), Some("text/html;charset=utf-8"));
+    }
+}
@@ -0,0 +1,412 @@
+//! Parsing of Authorization and Proxy-Authorization header values [RFC7235§4.2] and
+//! the challenges in WWW-Authenticate and Proxy-Authenticate values [RFC7235§4.1].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::auth::{challenges, parse_authorization, Credentials};
+//!
+//! let a = parse_authorization(b"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==").unwrap();
+//! assert_eq!(a.scheme, "Basic");
+//!
+//! let mut buf = [0; 32];
+//! assert_eq!(a.basic(&mut buf), Ok(("Aladdin", "open sesame")));
+//!
+//! let a = parse_authorization(b"Digest username=\"a\", qop=auth").unwrap();
+//!
+//! let mut p = match a.credentials {
+//!     Credentials::Params(p) => p,
+//!     _ => unreachable!(),
+//! };
+//!
+//! assert_eq!(p.next(), Some(Ok(("username", &b"\"a\""[..]))));
+//! assert_eq!(p.next(), Some(Ok(("qop", &b"auth"[..]))));
+//! assert_eq!(p.next(), None);
+//!
+//! let mut c = challenges(b"Basic realm=\"a\", Bearer realm=\"b\", error=\"x\"");
+//! assert_eq!(c.next().unwrap().unwrap().scheme, "Basic");
+//! assert_eq!(c.next().unwrap().unwrap().scheme, "Bearer");
+//! assert!(c.next().is_none());
+//! ```
+
+use grammar::{is_tchar, is_token, trim_ows};
+use list::{self, ListValues};
+use params::split_unquoted;
+use writer::offset;
+use {Error, Result};
+
+/// An Authorization header value, split into its scheme and credentials.
+#[derive(Clone, Debug)]
+pub struct Authorization<'a> {
+    /// Authentication scheme, such as `Basic` or `Bearer`, which is compared
+    /// case-insensitively [RFC7235§2.1].
+    pub scheme: &'a str,
+
+    /// Credentials following the scheme.
+    pub credentials: Credentials<'a>,
+}
+
+/// Credentials following the authentication scheme.
+#[derive(Clone, Debug)]
+pub enum Credentials<'a> {
+    /// No credentials were given.
+    Empty,
+    /// A single token68, as used by the Basic and Bearer schemes.
+    Token68(&'a [u8]),
+    /// A list of authentication parameters.
+    Params(AuthParams<'a>),
+}
+
+/// Try to parse the given Authorization header value, with surrounding whitespace
+/// trimmed.
+///
+/// The credentials are a token68 if they can be, and otherwise they're parsed as
+/// parameters as they're iterated.
+pub fn parse_authorization(val: &[u8]) -> Result<Authorization<'_>> {
+    let (scheme, rest) = split_scheme(trim_ows(val))?;
+
+    let credentials = if rest.is_empty() {
+        Credentials::Empty
+    } else if is_token68(rest) {
+        Credentials::Token68(rest)
+    } else {
+        Credentials::Params(AuthParams(list::values(rest)))
+    };
+
+    Ok(Authorization { scheme, credentials })
+}
+
+impl<'a> Authorization<'a> {
+    /// Try to decode the user-id and password of Basic credentials [RFC7617§2] into the
+    /// given buffer.
+    ///
+    /// Any other scheme or credentials that aren't valid base64 of a UTF-8 user-id and
+    /// password separated by a colon cause `Error::Syntax`.
+    pub fn basic<'b>(&self, buf: &'b mut [u8]) -> Result<(&'b str, &'b str)> {
+        if !self.scheme.eq_ignore_ascii_case("Basic") {
+            return Err(Error::Syntax);
+        }
+
+        let token = match self.credentials {
+            Credentials::Token68(t) => t,
+            _ => return Err(Error::Syntax),
+        };
+
+        let len = decode_base64(token, buf)?;
+        let pair = std::str::from_utf8(&buf[..len]).map_err(|_| Error::Syntax)?;
+
+        pair.split_once(':').ok_or(Error::Syntax)
+    }
+}
+
+/// A single challenge from a WWW-Authenticate or Proxy-Authenticate value.
+#[derive(Clone, Debug)]
+pub struct Challenge<'a> {
+    /// Authentication scheme, which is compared case-insensitively.
+    pub scheme: &'a str,
+
+    /// Token68 or parameters of the challenge, such as its realm.
+    pub credentials: Credentials<'a>,
+}
+
+/// Create an iterator over the challenges in the given WWW-Authenticate or
+/// Proxy-Authenticate value.
+pub fn challenges<'a>(val: &'a [u8]) -> Challenges<'a> {
+    Challenges(Some(val))
+}
+
+/// Iterator over the challenges in a WWW-Authenticate or Proxy-Authenticate value.
+///
+/// Both challenges and their parameters are separated by commas [RFC7235§4.1], so each
+/// list element is looked at ahead of time: an element starting with a token followed by
+/// `=` continues the parameters of the current challenge, and any other element starts
+/// a new challenge. If an error is yielded, iteration stops.
+#[derive(Clone, Debug)]
+pub struct Challenges<'a>(Option<&'a [u8]>);
+
+impl<'a> Challenges<'a> {
+    /// Parse the next challenge, if there is one.
+    fn parse(&mut self) -> Result<Option<Challenge<'a>>> {
+        let (rest, elem, mut next) = loop {
+            let rest = match self.0 {
+                Some(rest) => rest,
+                None => return Ok(None),
+            };
+
+            let (elem, next) = split_unquoted(rest, b',')?;
+            let elem = trim_ows(elem);
+
+            if !elem.is_empty() {
+                break (rest, elem, next);
+            }
+
+            self.0 = next;
+        };
+
+        // A parameter can't precede the first challenge.
+        if is_param(elem) {
+            return Err(Error::Syntax);
+        }
+
+        let (scheme, first) = split_scheme(elem)?;
+
+        let credentials = if first.is_empty() {
+            Credentials::Empty
+        } else if is_token68(first) {
+            Credentials::Token68(first)
+        } else {
+            let start = offset(rest, first).ok_or(Error::Syntax)?;
+            let mut end = start + first.len();
+
+            // Extend the parameters over every following element that's a parameter.
+            while let Some(after) = next {
+                let (elem, n) = split_unquoted(after, b',')?;
+                let elem = trim_ows(elem);
+
+                if !elem.is_empty() {
+                    if !is_param(elem) {
+                        break;
+                    }
+
+                    end = offset(rest, elem).ok_or(Error::Syntax)? + elem.len();
+                }
+
+                next = n;
+            }
+
+            Credentials::Params(AuthParams(list::values(&rest[start..end])))
+        };
+
+        self.0 = next;
+
+        Ok(Some(Challenge { scheme, credentials }))
+    }
+}
+
+impl<'a> Iterator for Challenges<'a> {
+    type Item = Result<Challenge<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parse() {
+            Ok(c) => c.map(Ok),
+            Err(e) => {
+                self.0 = None;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Iterator over the authentication parameters in credentials.
+///
+/// Each parameter is yielded as its name and raw value, which keeps the quotes of a
+/// quoted-string so `params::unquote` can strip them. Empty list elements are skipped.
+#[derive(Clone, Debug)]
+pub struct AuthParams<'a>(ListValues<'a>);
+
+impl<'a> Iterator for AuthParams<'a> {
+    type Item = Result<(&'a str, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(|param| {
+            let eq = param.iter().position(|&b| b == b'=').ok_or(Error::Syntax)?;
+            let (name, val) = (trim_ows(&param[..eq]), trim_ows(&param[eq + 1..]));
+
+            if !is_token(name) || !(is_token(val) || is_quoted(val)) {
+                return Err(Error::Syntax);
+            }
+
+            // Tokens are ASCII, so this can't fail.
+            Ok((std::str::from_utf8(name).map_err(|_| Error::Syntax)?, val))
+        }))
+    }
+}
+
+/// Split the auth-scheme from the start of the given bytes, which must be followed by
+/// either nothing or at least one space [RFC7235§2.1].
+fn split_scheme(bytes: &[u8]) -> Result<(&str, &[u8])> {
+    let (scheme, rest) = match bytes.iter().position(|&b| b == b' ') {
+        Some(idx) => (&bytes[..idx], trim_ows(&bytes[idx + 1..])),
+        None => (bytes, &b""[..]),
+    };
+
+    if !is_token(scheme) {
+        return Err(Error::Syntax);
+    }
+
+    // Tokens are ASCII, so this can't fail.
+    Ok((std::str::from_utf8(scheme).map_err(|_| Error::Syntax)?, rest))
+}
+
+/// Check if the given list element starts with a token followed by `=`, making it an
+/// auth-param rather than the start of a challenge.
+fn is_param(elem: &[u8]) -> bool {
+    let end = elem.iter().position(|&b| !is_tchar(b)).unwrap_or(elem.len());
+
+    end > 0 && trim_ows(&elem[end..]).starts_with(b"=")
+}
+
+/// Check if the given bytes form a token68 [RFC7235§2.1].
+fn is_token68(bytes: &[u8]) -> bool {
+    let end = bytes.iter().rposition(|&b| b != b'=').map_or(0, |idx| idx + 1);
+
+    end > 0 && bytes[..end].iter().all(|&b| {
+        b.is_ascii_alphanumeric() || b"-._~+/".contains(&b)
+    })
+}
+
+/// Check if the given bytes form a quoted-string, without checking their contents.
+fn is_quoted(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes.starts_with(b"\"") && bytes.ends_with(b"\"")
+}
+
+/// Try to decode the given base64 [RFC4648§4], with optional padding, into the given
+/// buffer, returning the number of bytes written.
+fn decode_base64(src: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let end = src.iter().rposition(|&b| b != b'=').map_or(0, |idx| idx + 1);
+
+    let padded = src.len() > end;
+
+    if src.len() - end > 2 || padded && !src.len().is_multiple_of(4) || end % 4 == 1 {
+        return Err(Error::Syntax);
+    }
+
+    let mut pos = 0;
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for &b in &src[..end] {
+        let v = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(Error::Syntax),
+        };
+
+        acc = acc << 6 | v as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            *buf.get_mut(pos).ok_or(Error::Capacity)? = (acc >> bits) as u8;
+            pos += 1;
+        }
+    }
+
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_authorization() {
+        let a = parse_authorization(b" Bearer  mF_9.B5f-4.1JqM== ").unwrap();
+        assert_eq!(a.scheme, "Bearer");
+        assert!(matches!(a.credentials, Credentials::Token68(b"mF_9.B5f-4.1JqM==")));
+
+        let a = parse_authorization(b"Negotiate").unwrap();
+        assert!(matches!(a.credentials, Credentials::Empty));
+
+        let a = parse_authorization(b"X a = 1 ,, b=\"c, d\" ").unwrap();
+        let p = match a.credentials {
+            Credentials::Params(p) => p,
+            _ => panic!(),
+        };
+        assert_eq!(p.collect::<Vec<_>>(),
+            [Ok(("a", &b"1"[..])), Ok(("b", &b"\"c, d\""[..]))]);
+
+        let a = parse_authorization(b"X a=1, b").unwrap();
+        let mut p = match a.credentials {
+            Credentials::Params(p) => p,
+            _ => panic!(),
+        };
+        assert_eq!(p.next(), Some(Ok(("a", &b"1"[..]))));
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+
+        assert!(parse_authorization(b"").is_err());
+        assert!(parse_authorization(b"B@sic x").is_err());
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut buf = [0; 16];
+
+        let a = parse_authorization(b"basic dXNlcjpwYTpzcw").unwrap();
+        assert_eq!(a.basic(&mut buf), Ok(("user", "pa:ss")));
+
+        let a = parse_authorization(b"Basic Og==").unwrap();
+        assert_eq!(a.basic(&mut buf), Ok(("", "")));
+
+        let a = parse_authorization(b"Basic dXNlcg==").unwrap();
+        assert_eq!(a.basic(&mut buf), Err(Error::Syntax));
+
+        let a = parse_authorization(b"Bearer dXNlcjpwYTpzcw").unwrap();
+        assert_eq!(a.basic(&mut buf), Err(Error::Syntax));
+
+        let a = parse_authorization(b"Basic dXNlcjpwYTpzcw").unwrap();
+        assert_eq!(a.basic(&mut buf[..4]), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_challenges() {
+        fn params(c: Challenge<'_>) -> Vec<(&str, &[u8])> {
+            match c.credentials {
+                Credentials::Params(p) => p.map(|p| p.unwrap()).collect(),
+                _ => panic!(),
+            }
+        }
+
+        let mut c = challenges(b"Basic realm=\"a, b\", Bearer realm=\"b\" , error = x");
+        let b = c.next().unwrap().unwrap();
+        assert_eq!(b.scheme, "Basic");
+        assert_eq!(params(b), [("realm", &b"\"a, b\""[..])]);
+        let b = c.next().unwrap().unwrap();
+        assert_eq!(b.scheme, "Bearer");
+        assert_eq!(params(b), [("realm", &b"\"b\""[..]), ("error", &b"x"[..])]);
+        assert!(c.next().is_none());
+
+        let mut c = challenges(b", Newauth,, Negotiate abc==,Digest a=1,,b=2, ");
+        let n = c.next().unwrap().unwrap();
+        assert_eq!(n.scheme, "Newauth");
+        assert!(matches!(n.credentials, Credentials::Empty));
+        let n = c.next().unwrap().unwrap();
+        assert_eq!(n.scheme, "Negotiate");
+        assert!(matches!(n.credentials, Credentials::Token68(b"abc==")));
+        let d = c.next().unwrap().unwrap();
+        assert_eq!(d.scheme, "Digest");
+        assert_eq!(params(d), [("a", &b"1"[..]), ("b", &b"2"[..])]);
+        assert!(c.next().is_none());
+
+        let mut c = challenges(b"realm=a, Basic");
+        assert!(matches!(c.next(), Some(Err(Error::Syntax))));
+        assert!(c.next().is_none());
+
+        let mut c = challenges(b"Basic realm=\"a");
+        assert!(matches!(c.next(), Some(Err(Error::Syntax))));
+
+        assert!(challenges(b"").next().is_none());
+        assert!(challenges(b" , ").next().is_none());
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        let mut buf = [0; 8];
+
+        let mut dec = |src: &[u8]| decode_base64(src, &mut buf).map(|n| buf[..n].to_vec());
+
+        assert_eq!(dec(b""), Ok(b"".to_vec()));
+        assert_eq!(dec(b"Zg=="), Ok(b"f".to_vec()));
+        assert_eq!(dec(b"Zm8="), Ok(b"fo".to_vec()));
+        assert_eq!(dec(b"Zm9v"), Ok(b"foo".to_vec()));
+        assert_eq!(dec(b"Zm9vYg"), Ok(b"foob".to_vec()));
+        assert_eq!(dec(b"Zm9vYmE="), Ok(b"fooba".to_vec()));
+        assert_eq!(dec(b"Zm9vY"), Err(Error::Syntax));
+        assert_eq!(dec(b"Zg="), Err(Error::Syntax));
+        assert_eq!(dec(b"Z==="), Err(Error::Syntax));
+        assert_eq!(dec(b"Zm-v"), Err(Error::Syntax));
+    }
+}
@@ -0,0 +1,560 @@
+//! Verification that a request body matches its framing, so keep-alive connections
+//! don't desynchronize.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::body::BodyVerifier;
+//! use uhttp_request::{Error, Request};
+//!
+//! let req = Request::new(b"PUT / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhel").unwrap();
+//! let mut v = BodyVerifier::new(&req).unwrap();
+//!
+//! let (body, rest) = v.split(req.body);
+//! assert_eq!(body, b"hel");
+//! assert_eq!(rest, b"");
+//! assert_eq!(v.remaining(), Some(2));
+//!
+//! let (body, rest) = v.split(b"loGET /next");
+//! assert_eq!(body, b"lo");
+//! assert_eq!(rest, b"GET /next");
+//! assert_eq!(v.finish(), Ok(5));
+//! ```
+
+use grammar::{is_token, trim_ows};
+use list::{self, ListValues};
+use params::split_unquoted;
+use {Error, Header, Request, Result};
+
+/// Try to parse the given Content-Length header value [RFC7230§3.3.2].
+///
+/// Surrounding whitespace is trimmed, and the rest must be decimal digits, so signs,
+/// lists, and values that overflow a `u64` cause `Error::Syntax`.
+pub fn parse_content_length(val: &[u8]) -> Result<u64> {
+    let val = trim_ows(val);
+
+    if val.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    val.iter().try_fold(0u64, |len, &b| {
+        if !b.is_ascii_digit() {
+            return Err(Error::Syntax);
+        }
+
+        len.checked_mul(10)
+            .and_then(|l| l.checked_add((b - b'0') as u64))
+            .ok_or(Error::Syntax)
+    })
+}
+
+/// Try to determine the Content-Length from every occurrence of the header among the
+/// given fields, or `None` if it isn't present.
+///
+/// Multiple occurrences must all agree [RFC7230§3.3.2], since recipients disagreeing on
+/// which one to use is a common request smuggling vector, so conflicting or invalid
+/// values cause `Error::Syntax`.
+pub fn content_length<'a, I>(headers: I) -> Result<Option<u64>>
+    where I: IntoIterator<Item = Header<'a>>
+{
+    let mut len = None;
+
+    for h in headers {
+        if !h.name.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+
+        let next = parse_content_length(h.val)?;
+
+        if len.is_some_and(|l| l != next) {
+            return Err(Error::Syntax);
+        }
+
+        len = Some(next);
+    }
+
+    Ok(len)
+}
+
+/// A transfer coding applied to a message body [RFC7230§4].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum TransferCoding<'a> {
+    /// The chunked transfer coding [RFC7230§4.1].
+    Chunked,
+    /// The compress coding [RFC7230§4.2.1].
+    Compress,
+    /// The deflate coding [RFC7230§4.2.2].
+    Deflate,
+    /// The gzip coding [RFC7230§4.2.3].
+    Gzip,
+    /// Any other coding, with its name as given.
+    Unknown(&'a str),
+}
+
+impl<'a> TransferCoding<'a> {
+    /// Try to parse the given transfer coding, ignoring any parameters.
+    pub fn new(coding: &'a [u8]) -> Result<Self> {
+        let (name, _) = split_unquoted(coding, b';')?;
+        let name = trim_ows(name);
+
+        if !is_token(name) {
+            return Err(Error::Syntax);
+        }
+
+        // Tokens are ASCII, so this can't fail.
+        let name = std::str::from_utf8(name).map_err(|_| Error::Syntax)?;
+
+        let is = |s: &str| name.eq_ignore_ascii_case(s);
+
+        Ok(if is("chunked") {
+            TransferCoding::Chunked
+        } else if is("compress") || is("x-compress") {
+            TransferCoding::Compress
+        } else if is("deflate") {
+            TransferCoding::Deflate
+        } else if is("gzip") || is("x-gzip") {
+            TransferCoding::Gzip
+        } else {
+            TransferCoding::Unknown(name)
+        })
+    }
+}
+
+/// Create an iterator over the transfer codings in the given Transfer-Encoding header
+/// value [RFC7230§3.3.1], in the order they were applied.
+pub fn transfer_encoding<'a>(val: &'a [u8]) -> TransferEncoding<'a> {
+    TransferEncoding(list::values(val))
+}
+
+/// Iterator over the transfer codings in a Transfer-Encoding header value.
+#[derive(Clone, Debug)]
+pub struct TransferEncoding<'a>(ListValues<'a>);
+
+impl<'a> TransferEncoding<'a> {
+    /// Check if the final coding is `chunked`, which is the only way a request with a
+    /// Transfer-Encoding header has reliable framing [RFC7230§3.3.3].
+    ///
+    /// Since `chunked` must not be applied more than once, a repeated occurrence causes
+    /// `Error::Syntax`, as does an invalid coding.
+    pub fn is_chunked(&self) -> Result<bool> {
+        let mut chunked = false;
+        let mut last = None;
+
+        for coding in self.clone() {
+            let coding = coding?;
+
+            if coding == TransferCoding::Chunked {
+                if chunked {
+                    return Err(Error::Syntax);
+                }
+
+                chunked = true;
+            }
+
+            last = Some(coding);
+        }
+
+        Ok(last == Some(TransferCoding::Chunked))
+    }
+
+    /// Try to find the first coding that isn't one of the registered codings, which a
+    /// server should answer with 501 Not Implemented [RFC7230§3.3.1].
+    pub fn unknown(&self) -> Result<Option<&'a str>> {
+        for coding in self.clone() {
+            if let TransferCoding::Unknown(name) = coding? {
+                return Ok(Some(name));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'a> Iterator for TransferEncoding<'a> {
+    type Item = Result<TransferCoding<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(TransferCoding::new))
+    }
+}
+
+/// Bytes remaining after a header block, which begin the body only if the block was
+/// terminated.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Body<'a> {
+    buf: &'a [u8],
+    terminated: bool,
+}
+
+impl<'a> Body<'a> {
+    /// Create a new `Body` over the given bytes, which begin the body only if
+    /// `terminated` is set.
+    pub(crate) fn new(buf: &'a [u8], terminated: bool) -> Self {
+        Body { buf, terminated }
+    }
+
+    /// Check if the header block ended with its terminating empty line, so the bytes
+    /// begin the body.
+    pub fn is_terminated(&self) -> bool { self.terminated }
+
+    /// Retrieve the raw bytes, which may be leftover header lines if the header block
+    /// wasn't terminated.
+    pub fn as_slice(&self) -> &'a [u8] { self.buf }
+
+    /// Try to create a reader over the body with the framing tracked by the given
+    /// verifier.
+    ///
+    /// Return `Error::Partial` if the header block wasn't terminated.
+    pub fn reader(self, verifier: BodyVerifier) -> Result<BodyReader<'a>> {
+        if self.terminated {
+            Ok(BodyReader { buf: self.buf, verifier })
+        } else {
+            Err(Error::Partial)
+        }
+    }
+}
+
+/// Reader separating the body bytes in a buffer from any bytes following the body.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct BodyReader<'a> {
+    buf: &'a [u8],
+    verifier: BodyVerifier,
+}
+
+impl<'a> BodyReader<'a> {
+    /// Take the available body bytes from the buffer, recording them as consumed.
+    ///
+    /// Subsequent calls return an empty slice until `feed` provides more bytes.
+    pub fn read(&mut self) -> &'a [u8] {
+        let (body, rest) = self.verifier.split(self.buf);
+        self.buf = rest;
+        body
+    }
+
+    /// Replace the buffer with newly received bytes, which must directly follow the
+    /// previous buffer on the connection.
+    ///
+    /// Any bytes not yet taken from the previous buffer by `read` are discarded, so
+    /// `read` should be called until it returns an empty slice first.
+    pub fn feed(&mut self, buf: &'a [u8]) { self.buf = buf; }
+
+    /// Retrieve the bytes of the buffer not taken by `read`, which follow the body once
+    /// it's complete, such as a pipelined request.
+    pub fn rest(&self) -> &'a [u8] { self.buf }
+
+    /// Retrieve the verifier tracking the consumed body bytes.
+    pub fn verifier(&mut self) -> &mut BodyVerifier { &mut self.verifier }
+}
+
+/// How the end of a body is determined.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+enum Framing {
+    /// Body has exactly the given number of bytes.
+    Length(u64),
+    /// Body uses the chunked transfer coding and ends with a zero-size chunk.
+    Chunked {
+        /// Whether the terminating chunk has been seen.
+        done: bool,
+    },
+}
+
+/// Tracks the bytes consumed from a request body and confirms they agree with the
+/// advertised framing [RFC7230§3.3.3].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct BodyVerifier {
+    framing: Framing,
+    read: u64,
+}
+
+impl BodyVerifier {
+    /// Try to create a verifier for the body of the given request.
+    ///
+    /// Multiple Transfer-Encoding fields are combined in order into a single list of
+    /// codings [RFC7230§3.2.2], and the final coding across all of them must be
+    /// `chunked`. Any other Transfer-Encoding gives no reliable framing for a request, so
+    /// it causes `Error::Syntax`, as does a repeated `chunked`, an invalid coding, or an
+    /// invalid Content-Length. A request with neither header has an empty body.
+    ///
+    /// A chunked Transfer-Encoding overrides any Content-Length rather than causing an
+    /// error, as required of a recipient by [RFC7230§3.3.3]. Since the body is then
+    /// framed only by its chunks, the Content-Length is never consulted, though a proxy
+    /// should still remove it before forwarding the request.
+    pub fn new(req: &Request) -> Result<Self> {
+        let mut present = false;
+        let mut chunked = false;
+        let mut last = None;
+
+        for h in req.headers() {
+            let h = h?;
+
+            if !h.name.eq_ignore_ascii_case("Transfer-Encoding") {
+                continue;
+            }
+
+            present = true;
+
+            for coding in transfer_encoding(h.val) {
+                let coding = coding?;
+
+                if coding == TransferCoding::Chunked {
+                    if chunked {
+                        return Err(Error::Syntax);
+                    }
+
+                    chunked = true;
+                }
+
+                last = Some(coding);
+            }
+        }
+
+        if !present {
+            return Ok(BodyVerifier::length(req.content_length()?.unwrap_or(0)));
+        }
+
+        if last == Some(TransferCoding::Chunked) {
+            Ok(BodyVerifier::chunked())
+        } else {
+            Err(Error::Syntax)
+        }
+    }
+
+    /// Create a verifier for a body with the given exact length.
+    pub const fn length(len: u64) -> Self {
+        BodyVerifier { framing: Framing::Length(len), read: 0 }
+    }
+
+    /// Create a verifier for a chunked body.
+    pub const fn chunked() -> Self {
+        BodyVerifier { framing: Framing::Chunked { done: false }, read: 0 }
+    }
+
+    /// Retrieve the number of body bytes consumed so far.
+    pub fn read(&self) -> u64 { self.read }
+
+    /// Retrieve the number of body bytes still expected, or `None` if the body is
+    /// chunked.
+    pub fn remaining(&self) -> Option<u64> {
+        match self.framing {
+            Framing::Length(len) => Some(len - self.read),
+            Framing::Chunked { .. } => None,
+        }
+    }
+
+    /// Record that the given number of body bytes were consumed.
+    ///
+    /// For a chunked body, this counts the raw encoded bytes. Return
+    /// `Error::BodyTooLong` if this passes the advertised length or follows the
+    /// terminating chunk, in which case nothing is recorded.
+    pub fn consume(&mut self, n: usize) -> Result<()> {
+        let read = self.read.checked_add(n as u64).ok_or(Error::BodyTooLong)?;
+
+        match self.framing {
+            Framing::Length(len) if read > len => return Err(Error::BodyTooLong),
+            Framing::Chunked { done: true } if n > 0 => return Err(Error::BodyTooLong),
+            _ => {},
+        }
+
+        self.read = read;
+
+        Ok(())
+    }
+
+    /// Split the given bytes into the part belonging to the body and the part following
+    /// it, such as a pipelined request, and record the body part as consumed.
+    ///
+    /// For a chunked body, the end isn't known here, so every byte is treated as part of
+    /// the body until `terminated` is called.
+    pub fn split<'a>(&mut self, buf: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        let n = match self.framing {
+            Framing::Length(len) => std::cmp::min(len - self.read, buf.len() as u64) as usize,
+            Framing::Chunked { done: false } => buf.len(),
+            Framing::Chunked { done: true } => 0,
+        };
+
+        self.read += n as u64;
+
+        buf.split_at(n)
+    }
+
+    /// Record that the terminating chunk and trailer of a chunked body were consumed.
+    ///
+    /// Return `Error::Syntax` if the body isn't chunked.
+    pub fn terminated(&mut self) -> Result<()> {
+        match self.framing {
+            Framing::Chunked { ref mut done } => {
+                *done = true;
+                Ok(())
+            },
+            Framing::Length(_) => Err(Error::Syntax),
+        }
+    }
+
+    /// Check if the whole body has been consumed.
+    pub fn is_complete(&self) -> bool {
+        match self.framing {
+            Framing::Length(len) => self.read == len,
+            Framing::Chunked { done } => done,
+        }
+    }
+
+    /// Confirm the whole body was consumed once the connection has no more body bytes
+    /// to offer, returning the number of bytes consumed.
+    ///
+    /// Return `Error::BodyTruncated` if fewer bytes than advertised were consumed or the
+    /// terminating chunk wasn't seen.
+    pub fn finish(self) -> Result<u64> {
+        if self.is_complete() { Ok(self.read) } else { Err(Error::BodyTruncated) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Headers;
+
+    #[test]
+    fn test_content_length() {
+        let len = |buf| content_length(Headers::new(buf).map(|h| h.unwrap()));
+
+        assert_eq!(len(b"A: 1\r\n\r\n"), Ok(None));
+        assert_eq!(len(b"Content-Length: 007\r\n\r\n"), Ok(Some(7)));
+        assert_eq!(len(b"Content-Length: 7\r\nCONTENT-LENGTH: 7 \r\n\r\n"), Ok(Some(7)));
+        assert_eq!(len(b"Content-Length: 7\r\nContent-Length: 8\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(len(b"Content-Length: 7\r\nContent-Length: x\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(len(b"Content-Length: 7, 7\r\n\r\n"), Err(Error::Syntax));
+
+        assert_eq!(parse_content_length(b"-1"), Err(Error::Syntax));
+        assert_eq!(parse_content_length(b"0x10"), Err(Error::Syntax));
+        assert_eq!(parse_content_length(b"\t42\t"), Ok(42));
+    }
+
+    #[test]
+    fn test_body() {
+        use Headers;
+
+        let mut h = Headers::new(b"A: 1\r\n\r\nbodyGET");
+        assert!(h.next().is_some());
+
+        let b = h.into_body();
+        assert!(!b.is_terminated());
+        assert_eq!(b.as_slice(), b"\r\nbodyGET");
+        assert_eq!(b.reader(BodyVerifier::length(4)), Err(Error::Partial));
+
+        let mut h = Headers::new(b"A: 1\r\n\r\nbodyGET");
+        assert_eq!(h.by_ref().count(), 1);
+
+        let b = h.into_body();
+        assert!(b.is_terminated());
+        assert_eq!(b.as_slice(), b"bodyGET");
+
+        let mut r = b.reader(BodyVerifier::length(6)).unwrap();
+        assert_eq!(r.read(), b"bodyGE");
+        assert_eq!(r.read(), b"");
+        assert_eq!(r.rest(), b"T");
+        assert_eq!(r.verifier().finish(), Ok(6));
+
+        let mut r = b.reader(BodyVerifier::length(9)).unwrap();
+        assert_eq!(r.read(), b"bodyGET");
+        r.feed(b"xyz");
+        assert_eq!(r.read(), b"xy");
+        assert_eq!(r.rest(), b"z");
+        assert!(r.verifier().is_complete());
+    }
+
+    #[test]
+    fn test_new() {
+        let v = |buf: &[u8]| BodyVerifier::new(&Request::new(buf).unwrap());
+
+        assert_eq!(v(b"GET / HTTP/1.1\r\n\r\n"), Ok(BodyVerifier::length(0)));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nContent-Length: 7\r\n\r\n"),
+            Ok(BodyVerifier::length(7)));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nContent-Length: 7\r\n\
+                       Transfer-Encoding: gzip, Chunked\r\n\r\n"),
+            Ok(BodyVerifier::chunked()));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nContent-Length: x\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked, chunked\r\n\r\n"),
+            Err(Error::Syntax));
+
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\
+                       Transfer-Encoding: gzip\r\n\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\
+                       transfer-encoding: chunked\r\n\r\n"),
+            Ok(BodyVerifier::chunked()));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\
+                       Transfer-Encoding: chunked\r\n\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\
+                       Transfer-Encoding: \r\n\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: \r\n\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(v(b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\
+                       Content-Length: x\r\n\r\n"),
+            Ok(BodyVerifier::chunked()));
+    }
+
+    #[test]
+    fn test_transfer_encoding() {
+        let mut te = transfer_encoding(b"gzip;q=1, X-Foo , ,Chunked");
+        assert_eq!(te.is_chunked(), Ok(true));
+        assert_eq!(te.unknown(), Ok(Some("X-Foo")));
+        assert_eq!(te.next(), Some(Ok(TransferCoding::Gzip)));
+        assert_eq!(te.next(), Some(Ok(TransferCoding::Unknown("X-Foo"))));
+        assert_eq!(te.next(), Some(Ok(TransferCoding::Chunked)));
+        assert_eq!(te.next(), None);
+
+        let te = transfer_encoding(b"deflate, x-compress, x-gzip");
+        assert_eq!(te.is_chunked(), Ok(false));
+        assert_eq!(te.unknown(), Ok(None));
+        assert_eq!(te.collect::<Vec<_>>(), [
+            Ok(TransferCoding::Deflate),
+            Ok(TransferCoding::Compress),
+            Ok(TransferCoding::Gzip),
+        ]);
+
+        assert_eq!(transfer_encoding(b"chunked, gzip").is_chunked(), Ok(false));
+        assert_eq!(transfer_encoding(b"chunked, chunked").is_chunked(), Err(Error::Syntax));
+        assert_eq!(transfer_encoding(b"chunked, a b").is_chunked(), Err(Error::Syntax));
+        assert_eq!(transfer_encoding(b"").is_chunked(), Ok(false));
+        assert_eq!(TransferCoding::new(b"\"chunked\""), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_length() {
+        let mut v = BodyVerifier::length(4);
+        assert!(!v.is_complete());
+        assert_eq!(v.consume(3), Ok(()));
+        assert_eq!(v.consume(2), Err(Error::BodyTooLong));
+        assert_eq!(v.read(), 3);
+        assert_eq!(v.finish(), Err(Error::BodyTruncated));
+        assert_eq!(v.consume(1), Ok(()));
+        assert!(v.is_complete());
+        assert_eq!(v.split(b"next"), (&b""[..], &b"next"[..]));
+        assert_eq!(v.terminated(), Err(Error::Syntax));
+        assert_eq!(v.finish(), Ok(4));
+
+        let mut v = BodyVerifier::length(0);
+        assert_eq!(v.consume(0), Ok(()));
+        assert_eq!(v.consume(1), Err(Error::BodyTooLong));
+        assert_eq!(v.finish(), Ok(0));
+    }
+
+    #[test]
+    fn test_chunked() {
+        let mut v = BodyVerifier::chunked();
+        assert_eq!(v.remaining(), None);
+        assert_eq!(v.split(b"3\r\nabc\r\n"), (&b"3\r\nabc\r\n"[..], &b""[..]));
+        assert_eq!(v.consume(5), Ok(()));
+        assert_eq!(v.finish(), Err(Error::BodyTruncated));
+
+        assert_eq!(v.terminated(), Ok(()));
+        assert_eq!(v.consume(0), Ok(()));
+        assert_eq!(v.consume(1), Err(Error::BodyTooLong));
+        assert_eq!(v.split(b"GET"), (&b""[..], &b"GET"[..]));
+        assert_eq!(v.finish(), Ok(13));
+    }
+}
@@ -0,0 +1,182 @@
+//! Cache keys for building in-memory response caches.
+
+use grammar::trim_ows;
+use hash::Fnv;
+use percent::hex;
+use Head;
+
+/// Compute a stable 64-bit key identifying the given request for caching purposes.
+///
+/// The key covers the method, the normalized request target, and the values of the
+/// header fields named in `vary` (typically taken from the cached response's Vary
+/// header), so requests that differ only in unlisted headers share a key. Targets are
+/// normalized by decoding percent-escapes of unreserved characters and uppercasing the
+/// hex digits of all other escapes [RFC3986§6.2.2]. Header names are compared
+/// case-insensitively, and values have surrounding whitespace trimmed. The headers are
+/// scanned once per 16 names in `vary`, so typical Vary lists take a single pass.
+///
+/// Keys are computed with FNV-1a, so they're suitable for lookups but shouldn't be
+/// relied on where untrusted parties could engineer collisions.
+pub fn cache_key(head: &Head, vary: &[&str]) -> u64 {
+    let mut h = Fnv::new();
+
+    write_len(&mut h, head.line.method.len());
+    h.write(head.line.method.as_bytes());
+
+    let target = head.line.target.as_bytes();
+    write_len(&mut h, normalized(target).count());
+    normalized(target).for_each(|b| h.write_u8(b));
+
+    // Each chunk of names gets its own state per name, filled in a single pass over the
+    // headers, so values are grouped by name regardless of how the fields interleave.
+    for names in vary.chunks(VARY_CHUNK) {
+        let mut vals = [Fnv::new(); VARY_CHUNK];
+
+        for f in head.headers().filter_map(|f| f.ok()) {
+            let val = trim_ows(f.val);
+
+            for (name, v) in names.iter().zip(vals.iter_mut()) {
+                // Each value is marked so the number of occurrences is significant.
+                if f.name.eq_ignore_ascii_case(name) {
+                    v.write_u8(1);
+                    write_len(v, val.len());
+                    v.write(val);
+                }
+            }
+        }
+
+        for (name, v) in names.iter().zip(vals.iter()) {
+            write_len(&mut h, name.len());
+            name.bytes().for_each(|b| h.write_u8(b.to_ascii_lowercase()));
+            h.write(&v.finish().to_le_bytes());
+        }
+    }
+
+    h.finish()
+}
+
+/// Number of Vary names whose values are collected in each pass over the headers.
+const VARY_CHUNK: usize = 16;
+
+/// Mix the given length into the hash, so adjacent components can't run together.
+fn write_len(h: &mut Fnv, len: usize) {
+    h.write(&(len as u64).to_le_bytes());
+}
+
+/// Create an iterator over the given target with percent-encoding normalized.
+fn normalized<'a>(target: &'a [u8]) -> Normalized<'a> {
+    Normalized {
+        target,
+        digits: [0; 2],
+        pending: 0,
+    }
+}
+
+/// Iterator over a target with percent-encoding normalized.
+struct Normalized<'a> {
+    target: &'a [u8],
+    /// Hex digits of the escape currently being emitted.
+    digits: [u8; 2],
+    /// Number of digits remaining to be emitted.
+    pending: usize,
+}
+
+impl<'a> Iterator for Normalized<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.pending > 0 {
+            self.pending -= 1;
+            return Some(self.digits[1 - self.pending]);
+        }
+
+        let (&b, rest) = self.target.split_first()?;
+        self.target = rest;
+
+        if b != b'%' {
+            return Some(b);
+        }
+
+        let (hi, lo) = match (rest.first().and_then(|&b| hex(b)),
+                              rest.get(1).and_then(|&b| hex(b))) {
+            (Some(hi), Some(lo)) => (hi, lo),
+            // Pass through malformed escapes.
+            _ => return Some(b),
+        };
+
+        self.target = &rest[2..];
+
+        let byte = hi << 4 | lo;
+
+        if is_unreserved(byte) {
+            return Some(byte);
+        }
+
+        self.digits = [rest[0].to_ascii_uppercase(), rest[1].to_ascii_uppercase()];
+        self.pending = 2;
+
+        Some(b'%')
+    }
+}
+
+/// Check if the given byte is in the unreserved set [RFC3986§2.3].
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~".contains(&b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(buf: &[u8], vary: &[&str]) -> u64 {
+        cache_key(&Head::new(buf).unwrap().0, vary)
+    }
+
+    #[test]
+    fn test_normalized() {
+        let n = |t: &[u8]| normalized(t).collect::<Vec<u8>>();
+
+        assert_eq!(n(b"/a/b?c=d"), b"/a/b?c=d");
+        assert_eq!(n(b"/%7e%41%2f%2F%c3%A9"), b"/~A%2F%2F%C3%A9");
+        assert_eq!(n(b"/%zz%4"), b"/%zz%4");
+        assert_eq!(n(b"%"), b"%");
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let base = key(b"GET /a HTTP/1.1\r\nAccept: */*\r\n\r\n", &["accept"]);
+
+        assert_eq!(base, key(b"GET /%61 HTTP/1.1\r\naccept:  */* \r\nX: y\r\n\r\n",
+            &["Accept"]));
+        assert_ne!(base, key(b"HEAD /a HTTP/1.1\r\nAccept: */*\r\n\r\n", &["accept"]));
+        assert_ne!(base, key(b"GET /b HTTP/1.1\r\nAccept: */*\r\n\r\n", &["accept"]));
+        assert_ne!(base, key(b"GET /a HTTP/1.1\r\nAccept: text/*\r\n\r\n", &["accept"]));
+        assert_ne!(base, key(b"GET /a HTTP/1.1\r\n\r\n", &["accept"]));
+        assert_ne!(base, key(b"GET /a HTTP/1.1\r\nAccept: */*\r\nAccept: */*\r\n\r\n",
+            &["accept"]));
+        assert_ne!(base, key(b"GET /a HTTP/1.1\r\nAccept: */*\r\n\r\n", &[]));
+
+        let vary = &["accept", "accept-language"];
+        let both = key(b"GET / HTTP/1.1\r\nAccept: a\r\nAccept-Language: b\r\n\r\n", vary);
+        assert_eq!(both,
+            key(b"GET / HTTP/1.1\r\nAccept-Language: b\r\nAccept: a\r\n\r\n", vary));
+        assert_ne!(both,
+            key(b"GET / HTTP/1.1\r\nAccept: b\r\nAccept-Language: a\r\n\r\n", vary));
+        assert_ne!(both, key(b"GET / HTTP/1.1\r\nAccept: a\r\nAccept-Language: b\r\n\r\n",
+            &["accept-language", "accept"]));
+        assert_ne!(key(b"GET / HTTP/1.1\r\nA: 1\r\nA: 2\r\n\r\n", &["a"]),
+            key(b"GET / HTTP/1.1\r\nA: 2\r\nA: 1\r\n\r\n", &["a"]));
+
+        let mut names = ["x"; 20];
+        names[19] = "accept";
+        assert_ne!(key(b"GET / HTTP/1.1\r\nAccept: a\r\n\r\n", &names),
+            key(b"GET / HTTP/1.1\r\nAccept: b\r\n\r\n", &names));
+        assert_eq!(key(b"GET / HTTP/1.1\r\nAccept: a\r\nX: 1\r\n\r\n", &names),
+            key(b"GET / HTTP/1.1\r\nX: 1\r\nAccept: a\r\n\r\n", &names));
+
+        assert_eq!(key(b"GET /%2f HTTP/1.1\r\n\r\n", &[]),
+            key(b"GET /%2F HTTP/1.1\r\n\r\n", &[]));
+        assert_ne!(key(b"GET /%2f HTTP/1.1\r\n\r\n", &[]),
+            key(b"GET // HTTP/1.1\r\n\r\n", &[]));
+    }
+}
@@ -0,0 +1,267 @@
+//! Decoding of bodies sent with the chunked transfer coding [RFC7230§4.1].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::chunked;
+//!
+//! let body = b"5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\nGET /next";
+//!
+//! let mut c = chunked::chunks(body);
+//! assert_eq!(c.next(), Some(Ok(&b"hello"[..])));
+//! assert_eq!(c.next(), Some(Ok(&b" world"[..])));
+//! assert_eq!(c.next(), None);
+//! assert_eq!(c.trailer(), Some(&b"Expires: never\r\n\r\n"[..]));
+//! assert_eq!(c.into_inner(), b"GET /next");
+//!
+//! let mut buf = [0; 16];
+//! let (len, rest) = chunked::decode(body, &mut buf).unwrap();
+//! assert_eq!(&buf[..len], b"hello world");
+//! assert_eq!(rest, b"GET /next");
+//! ```
+
+use grammar::is_ows;
+use params::split_unquoted;
+use writer::Cursor;
+use {next_line, Error, Header, Headers, Result};
+
+/// Create an iterator over the data of each chunk in the chunked body at the start of
+/// the given bytes.
+pub fn chunks<'a>(buf: &'a [u8]) -> Chunks<'a> {
+    Chunks { buf, trailer: None, done: false }
+}
+
+/// Iterator over the data of each chunk in a chunked body.
+///
+/// Chunk extensions are skipped, and iteration ends after the last chunk and trailer
+/// section. If an error is yielded, including `Error::Partial` when the body is
+/// incomplete, iteration stops, and the body should be decoded from the beginning again
+/// once more bytes arrive.
+pub struct Chunks<'a> {
+    buf: &'a [u8],
+    trailer: Option<&'a [u8]>,
+    done: bool,
+}
+
+impl<'a> Chunks<'a> {
+    /// Retrieve the raw trailer section, including its terminating empty line, which can
+    /// be iterated with `Trailers::new`, once the whole body has been decoded.
+    pub fn trailer(&self) -> Option<&'a [u8]> { self.trailer }
+
+    /// Retrieve the remaining bytes that haven't been processed.
+    ///
+    /// If called after the last yielded chunk, this slice begins directly after the body.
+    pub fn into_inner(self) -> &'a [u8] { self.buf }
+
+    /// Parse the next chunk.
+    fn parse(&mut self) -> Result<Option<&'a [u8]>> {
+        let (line, rest) = next_line(self.buf)?;
+        let size = parse_size(line)?;
+
+        if size == 0 {
+            let mut trailer = Trailers::new(rest);
+
+            for h in trailer.by_ref() {
+                h?;
+            }
+
+            let after = trailer.into_inner();
+
+            self.trailer = rest.get(..rest.len().saturating_sub(after.len()));
+            self.buf = after;
+
+            return Ok(None);
+        }
+
+        if size > rest.len() as u64 {
+            return Err(Error::Partial);
+        }
+
+        // Size is now known to fit in memory.
+        let end = size as usize + 2;
+
+        let (chunk, after) = match rest.split_at_checked(end) {
+            Some(split) => split,
+            None => return Err(Error::Partial),
+        };
+
+        let chunk = match chunk.strip_suffix(b"\r\n") {
+            Some(c) => c,
+            None => return Err(Error::Syntax),
+        };
+
+        self.buf = after;
+
+        Ok(Some(chunk))
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.parse() {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Iterator over the trailer fields that follow the last chunk of a chunked body
+/// [RFC7230§4.1.2].
+///
+/// Fields are parsed the same way as header fields, and iteration ends at the empty line
+/// that terminates the trailer section.
+pub struct Trailers<'a>(Headers<'a>);
+
+impl<'a> Trailers<'a> {
+    /// Create a new `Trailers` iterator over the given bytes, which must begin directly
+    /// after the CRLF of the last chunk's size line.
+    pub const fn new(buf: &'a [u8]) -> Self { Trailers(Headers::new(buf)) }
+
+    /// Retrieve the remaining bytes that haven't been processed.
+    ///
+    /// If called after the last yielded field, this slice begins directly after the
+    /// chunked body.
+    pub fn into_inner(self) -> &'a [u8] { self.0.into_inner() }
+}
+
+impl<'a> Iterator for Trailers<'a> {
+    type Item = Result<Header<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> { self.0.next() }
+}
+
+/// Try to decode the whole chunked body at the start of the given bytes, copying the
+/// data of every chunk into the given buffer.
+///
+/// On success, return `Ok((len, rest))`, where `len` is the number of bytes written and
+/// `rest` is a slice that begins directly after the body.
+pub fn decode<'a>(buf: &'a [u8], out: &mut [u8]) -> Result<(usize, &'a [u8])> {
+    let mut out = Cursor::new(out);
+    let mut chunks = chunks(buf);
+
+    for chunk in chunks.by_ref() {
+        out.push(chunk?)?;
+    }
+
+    if chunks.trailer.is_none() {
+        return Err(Error::Partial);
+    }
+
+    Ok((out.pos(), chunks.into_inner()))
+}
+
+/// Parse the size from the given chunk-size line, ignoring any chunk extensions.
+fn parse_size(line: &[u8]) -> Result<u64> {
+    let (size, _) = split_unquoted(line, b';')?;
+
+    let ws = size.iter().position(|&b| is_ows(b));
+
+    let size = match ws.and_then(|i| size.split_at_checked(i)) {
+        // Allow whitespace before extensions, as produced by some encoders.
+        Some((size, ws)) if ws.iter().all(|&b| is_ows(b)) => size,
+        Some(_) => return Err(Error::Syntax),
+        None => size,
+    };
+
+    if size.is_empty() || size.len() > 16 {
+        return Err(Error::Syntax);
+    }
+
+    size.iter().try_fold(0, |n, &b| {
+        let d = match b {
+            b'0'..=b'9' => b - b'0',
+            b'a'..=b'f' => b - b'a' + 10,
+            b'A'..=b'F' => b - b'A' + 10,
+            _ => return Err(Error::Syntax),
+        };
+
+        Ok(n << 4 | d as u64)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size(b"0"), Ok(0));
+        assert_eq!(parse_size(b"1a"), Ok(26));
+        assert_eq!(parse_size(b"FF;name=\"a;b\""), Ok(255));
+        assert_eq!(parse_size(b"10 ;x"), Ok(16));
+        assert_eq!(parse_size(b"ffffffffffffffff"), Ok(u64::MAX));
+        assert_eq!(parse_size(b"10000000000000000"), Err(Error::Syntax));
+        assert_eq!(parse_size(b"1 0"), Err(Error::Syntax));
+        assert_eq!(parse_size(b"x"), Err(Error::Syntax));
+        assert_eq!(parse_size(b""), Err(Error::Syntax));
+        assert_eq!(parse_size(b";x"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut c = chunks(b"3\r\nabc\r\n0\r\n\r\nrest");
+        assert_eq!(c.next(), Some(Ok(&b"abc"[..])));
+        assert_eq!(c.next(), None);
+        assert_eq!(c.next(), None);
+        assert_eq!(c.trailer(), Some(&b"\r\n"[..]));
+        assert_eq!(c.into_inner(), b"rest");
+
+        let mut c = chunks(b"3\r\nab");
+        assert_eq!(c.next(), Some(Err(Error::Partial)));
+        assert_eq!(c.next(), None);
+
+        let mut c = chunks(b"3\r\nabcd\r\n");
+        assert_eq!(c.next(), Some(Err(Error::Syntax)));
+
+        let mut c = chunks(b"0\r\nA: b\r\n");
+        assert_eq!(c.next(), Some(Err(Error::Partial)));
+        assert_eq!(c.trailer(), None);
+
+        let mut c = chunks(b"0\r\nA\r\n\r\n");
+        assert_eq!(c.next(), Some(Err(Error::Syntax)));
+
+        let mut c = chunks(b"ffffffffffffffff\r\n");
+        assert!(c.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_trailers() {
+        let mut t = Trailers::new(b"Expires: never\r\nX-Sum: 1\r\n\r\nrest");
+        assert_eq!(t.next(), Some(Ok(Header { name: "Expires", val: b" never" })));
+        assert_eq!(t.next(), Some(Ok(Header { name: "X-Sum", val: b" 1" })));
+        assert_eq!(t.next(), None);
+        assert_eq!(t.into_inner(), b"rest");
+
+        let mut t = Trailers::new(b"\r\n");
+        assert_eq!(t.next(), None);
+        assert_eq!(t.into_inner(), b"");
+
+        assert_eq!(Trailers::new(b"A: 1\r\n").nth(1), Some(Err(Error::Partial)));
+        assert_eq!(Trailers::new(b"A\r\n\r\n").next(), Some(Err(Error::Syntax)));
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = [0; 8];
+
+        assert_eq!(decode(b"0\r\n\r\n", &mut buf), Ok((0, &b""[..])));
+        assert_eq!(decode(b"2\r\nab\r\n2\r\ncd\r\n0\r\n\r\n", &mut buf),
+            Ok((4, &b""[..])));
+        assert_eq!(&buf[..4], b"abcd");
+        assert_eq!(decode(b"2\r\nab\r\n", &mut buf), Err(Error::Partial));
+        assert_eq!(decode(b"9\r\nabcdefghi\r\n0\r\n\r\n", &mut buf), Err(Error::Capacity));
+    }
+}
@@ -0,0 +1,242 @@
+//! Evaluation of conditional requests [RFC7232].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::Head;
+//! use uhttp_request::conditional::{evaluate, Action};
+//!
+//! let (head, _) = Head::new(
+//!     b"GET /a HTTP/1.1\r\nIf-None-Match: W/\"1\", \"2\"\r\n\r\n"
+//! ).unwrap();
+//!
+//! assert_eq!(evaluate(&head, Some(b"\"2\""), None), Ok(Action::NotModified));
+//! assert_eq!(evaluate(&head, Some(b"\"3\""), None), Ok(Action::Proceed));
+//! ```
+
+use date;
+use etag::{tag_list, EntityTag, TagList};
+use grammar::trim_ows;
+use {Head, Result};
+
+/// Outcome of evaluating the preconditions of a request.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Action {
+    /// All preconditions passed, so the request should be handled normally, including
+    /// any Range header.
+    Proceed,
+    /// Respond with 304 Not Modified.
+    NotModified,
+    /// Respond with 412 Precondition Failed.
+    PreconditionFailed,
+    /// The If-Range condition failed, so the request should be handled normally but any
+    /// Range header should be ignored and the full representation sent.
+    IgnoreRange,
+}
+
+/// Evaluate the preconditions of the given request against the current entity tag and
+/// last modification time, in seconds since the Unix epoch, of the selected
+/// representation.
+///
+/// The conditional headers are evaluated in the precedence order given in
+/// [RFC7232§6]: If-Match, If-Unmodified-Since, If-None-Match, If-Modified-Since, and
+/// then If-Range. A date condition is ignored if its value isn't a valid HTTP-date or
+/// the corresponding validator isn't given, and the entity tag should include its
+/// quotes and any `W/` prefix, such as produced by the `etag` module. The selected
+/// representation is assumed to exist, so `If-Match: *` always passes and
+/// `If-None-Match: *` always fails.
+///
+/// Return `Error::Syntax` if the given entity tag or an entity tag list is malformed.
+pub fn evaluate(head: &Head, etag: Option<&[u8]>, last_modified: Option<u64>)
+    -> Result<Action>
+{
+    let etag = etag.map(EntityTag::new).transpose()?;
+    let method = head.line.method;
+    let safe = method == "GET" || method == "HEAD";
+
+    if let Some(matched) = list_matches(head, "If-Match", etag, true)? {
+        if !matched {
+            return Ok(Action::PreconditionFailed);
+        }
+    } else if let (Some(since), Some(lm)) = (date(head, "If-Unmodified-Since")?, last_modified) {
+        if lm > since {
+            return Ok(Action::PreconditionFailed);
+        }
+    }
+
+    if let Some(matched) = list_matches(head, "If-None-Match", etag, false)? {
+        if matched {
+            return Ok(if safe { Action::NotModified } else { Action::PreconditionFailed });
+        }
+    } else if safe {
+        if let (Some(since), Some(lm)) = (date(head, "If-Modified-Since")?, last_modified) {
+            if lm <= since {
+                return Ok(Action::NotModified);
+            }
+        }
+    }
+
+    if method != "GET" || find(head, "Range")?.is_none() {
+        return Ok(Action::Proceed);
+    }
+
+    let cond = match find(head, "If-Range")? {
+        Some(c) => trim_ows(c),
+        None => return Ok(Action::Proceed),
+    };
+
+    // An If-Range entity tag always starts with a quote or `W/`, unlike an HTTP-date.
+    let passed = if cond.starts_with(b"\"") || cond.starts_with(b"W/") {
+        EntityTag::new(cond).is_ok_and(|c| etag.is_some_and(|e| c.strong_eq(&e)))
+    } else {
+        // Only an exact match against the last modification time counts as a strong
+        // validator [RFC7232§3.2].
+        date::parse(cond).ok().is_some_and(|d| last_modified == Some(d))
+    };
+
+    Ok(if passed { Action::Proceed } else { Action::IgnoreRange })
+}
+
+/// Retrieve the value of the first header with the given name.
+fn find<'a>(head: &Head<'a>, name: &str) -> Result<Option<&'a [u8]>> {
+    for h in head.headers() {
+        let h = h?;
+
+        if h.name.eq_ignore_ascii_case(name) {
+            return Ok(Some(h.val));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parse the first header with the given name as an HTTP-date, returning `None` if the
+/// header isn't present or isn't a valid date.
+fn date(head: &Head, name: &str) -> Result<Option<u64>> {
+    Ok(find(head, name)?.and_then(|v| date::parse(v).ok()))
+}
+
+/// Check if any entity tag in the headers with the given name matches the given entity
+/// tag, using the strong or weak comparison function [RFC7232§2.3.2], returning `None`
+/// if no such header is present.
+fn list_matches(head: &Head, name: &str, etag: Option<EntityTag>, strong: bool)
+    -> Result<Option<bool>>
+{
+    let mut found = false;
+    let mut matched = false;
+
+    for h in head.headers() {
+        let h = h?;
+
+        if !h.name.eq_ignore_ascii_case(name) {
+            continue;
+        }
+
+        found = true;
+
+        let tags = match tag_list(h.val)? {
+            TagList::Any => {
+                matched = true;
+                continue;
+            },
+            TagList::Tags(tags) => tags,
+        };
+
+        for tag in tags {
+            let tag = tag?;
+
+            matched |= etag.is_some_and(|e| {
+                if strong { tag.strong_eq(&e) } else { tag.weak_eq(&e) }
+            });
+        }
+    }
+
+    Ok(if found { Some(matched) } else { None })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    fn eval(req: &[u8], etag: Option<&[u8]>, lm: Option<u64>) -> Result<Action> {
+        evaluate(&Head::new(req).unwrap().0, etag, lm)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let e = Some(&b"\"x\""[..]);
+        let w = Some(&b"W/\"x\""[..]);
+        let lm = Some(784111777);
+
+        assert_eq!(eval(b"GET / HTTP/1.1\r\n\r\n", e, lm), Ok(Action::Proceed));
+
+        // If-Match uses strong comparison and takes precedence over
+        // If-Unmodified-Since.
+        let r = b"PUT / HTTP/1.1\r\nIf-Match: \"y\", \"x\"\r\n\
+                  If-Unmodified-Since: Thu, 01 Jan 1970 00:00:00 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+        assert_eq!(eval(r, w, lm), Ok(Action::PreconditionFailed));
+        assert_eq!(eval(r, None, lm), Ok(Action::PreconditionFailed));
+        assert_eq!(eval(b"PUT / HTTP/1.1\r\nIf-Match: *\r\n\r\n", None, None),
+            Ok(Action::Proceed));
+
+        let r = b"PUT / HTTP/1.1\r\nIf-Unmodified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+        assert_eq!(eval(r, e, Some(784111778)), Ok(Action::PreconditionFailed));
+        assert_eq!(eval(r, e, None), Ok(Action::Proceed));
+
+        // If-None-Match uses weak comparison and takes precedence over
+        // If-Modified-Since.
+        let r = b"GET / HTTP/1.1\r\nIf-None-Match: W/\"x\"\r\n\
+                  If-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::NotModified));
+        assert_eq!(eval(r, Some(b"\"y\""), lm), Ok(Action::Proceed));
+
+        let r = b"DELETE / HTTP/1.1\r\nIf-None-Match: *\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::PreconditionFailed));
+
+        let r = b"HEAD / HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::NotModified));
+        assert_eq!(eval(r, e, Some(784111778)), Ok(Action::Proceed));
+
+        let r = b"POST / HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+
+        let r = b"GET / HTTP/1.1\r\nIf-Modified-Since: yesterday\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+
+        let r = b"GET / HTTP/1.1\r\nIf-Match: x\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Err(Error::Syntax));
+        let r = b"GET / HTTP/1.1\r\nIf-Match: \"x\" \"y\"\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Err(Error::Syntax));
+        let r = b"GET / HTTP/1.1\r\nIf-None-Match: \"y\", *\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Err(Error::Syntax));
+        let r = b"GET / HTTP/1.1\r\nIf-None-Match: \"y\" ,, W/\"x\"\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::NotModified));
+        assert_eq!(eval(b"GET / HTTP/1.1\r\n\r\n", Some(b"x"), lm), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_if_range() {
+        let e = Some(&b"\"x\""[..]);
+        let lm = Some(784111777);
+
+        let r = b"GET / HTTP/1.1\r\nRange: bytes=0-1\r\nIf-Range: \"x\"\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+        assert_eq!(eval(r, Some(b"W/\"x\""), lm), Ok(Action::IgnoreRange));
+        assert_eq!(eval(r, None, lm), Ok(Action::IgnoreRange));
+
+        let r = b"GET / HTTP/1.1\r\nRange: bytes=0-1\r\n\
+                  If-Range: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+        assert_eq!(eval(r, e, Some(784111776)), Ok(Action::IgnoreRange));
+        assert_eq!(eval(r, e, None), Ok(Action::IgnoreRange));
+
+        // If-Range is only considered for GET with a Range header.
+        let r = b"GET / HTTP/1.1\r\nIf-Range: \"y\"\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+        let r = b"HEAD / HTTP/1.1\r\nRange: bytes=0-1\r\nIf-Range: \"y\"\r\n\r\n";
+        assert_eq!(eval(r, e, lm), Ok(Action::Proceed));
+    }
+}
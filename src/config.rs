@@ -0,0 +1,244 @@
+//! Configuration of parser limits and optional behavior.
+
+use memchr::memchr;
+
+use icap;
+use {next_line, skip_empty_lines, Error, Result};
+
+/// Limits and optional behavior applied while parsing.
+///
+/// The default configuration imposes no limits and yields every header as-is.
+///
+/// Every constructor is a `const fn`, so a configuration can be built at compile time and
+/// stored in a `const` or `static`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ParserConfig {
+    /// Maximum length in bytes of any individual header value, including surrounding
+    /// whitespace, or `None` for no limit.
+    ///
+    /// Exceeding this limit causes `Error::ValueTooLong`. The limit is checked even
+    /// before a header line is complete, so an oversized value is rejected as soon as
+    /// enough of it has arrived.
+    pub max_value_len: Option<usize>,
+
+    /// How to handle the non-standard Proxy-Connection header.
+    pub proxy_connection: ProxyConnection,
+
+    /// Maximum length in bytes of the request method, or `None` for no limit.
+    ///
+    /// Exceeding this limit causes `Error::UnsupportedMethod`. Like `max_value_len`, the
+    /// limit is checked even before the Request-Line is complete.
+    pub max_method_len: Option<usize>,
+
+    /// Request methods to accept, compared case-sensitively, or `None` to accept any
+    /// method.
+    ///
+    /// Any other method causes `Error::UnsupportedMethod`.
+    pub allowed_methods: Option<&'static [&'static str]>,
+
+    /// Maximum number of bytes to scan while parsing a request head, or `None` for no
+    /// limit.
+    ///
+    /// If the head isn't complete within this many bytes, parsing stops with
+    /// `Error::Budget` rather than `Error::Partial`, which bounds the work done per parse
+    /// call even when a peer sends an oversized head.
+    pub max_scan_len: Option<usize>,
+
+    /// How to handle header lines consisting solely of whitespace before the terminating
+    /// empty line.
+    pub whitespace_lines: WhitespaceLines,
+
+    /// Which line terminators to recognize in the Request-Line and header fields.
+    pub line_endings: LineEndings,
+
+    /// How to handle header values continued onto following lines with obsolete line
+    /// folding.
+    pub obs_fold: ObsFold,
+}
+
+/// Handling of the non-standard Proxy-Connection header, which some old clients send
+/// instead of Connection when talking to a proxy.
+///
+/// Proxies that forward it untouched or ignore its keep-alive semantics can cause
+/// persistent connection bugs, so it's often best stripped or rejected.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ProxyConnection {
+    /// Yield the header as-is.
+    Keep,
+    /// Yield the header as if it were named Connection, for legacy compatibility.
+    Connection,
+    /// Silently skip over the header.
+    Strip,
+    /// Fail with `Error::Syntax` when the header is encountered.
+    Reject,
+}
+
+/// Handling of header lines consisting solely of whitespace, which some embedded clients
+/// send between fields or before the terminating empty line.
+///
+/// Such lines aren't valid field lines [RFC7230§3.2], and some parsers treat them as the
+/// end of the head, so skipping them can mask a smuggling attempt when requests pass
+/// through several parsers.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum WhitespaceLines {
+    /// Fail with `Error::Syntax` when such a line is encountered.
+    Reject,
+    /// Silently skip over the line.
+    Skip,
+}
+
+/// Handling of header values continued onto following lines that begin with whitespace,
+/// known as obsolete line folding [RFC7230§3.2.4].
+///
+/// Parsers that don't recognize folding treat each continuation line as a separate
+/// field, so accepting it when requests pass through several parsers can hide fields
+/// from some of them.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ObsFold {
+    /// Parse each continuation line as a separate field, with the whitespace before
+    /// its name trimmed.
+    Split,
+    /// Fail with `Error::ObsFold` when a line beginning with whitespace is encountered.
+    Reject,
+    /// Yield the field with its value extended over every continuation line, including
+    /// the line terminators between them, which `Header::unfold` replaces with spaces.
+    ///
+    /// A field isn't complete until the start of the following line has arrived.
+    Merge,
+}
+
+/// Line terminators recognized while parsing.
+///
+/// Many legacy clients and test tools terminate lines with a bare LF, which recipients
+/// may recognize as a line terminator [RFC7230§3.5]. A bare CR is never accepted.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum LineEndings {
+    /// Recognize only CRLF, so a line ending in a bare LF is never complete.
+    Crlf,
+    /// Recognize both CRLF and a bare LF.
+    Lenient,
+}
+
+impl LineEndings {
+    /// Retrieve the next line, up to and not including the nearest recognized line
+    /// terminator.
+    pub(crate) fn next_line(self, bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+        if self == LineEndings::Crlf {
+            return next_line(bytes);
+        }
+
+        let (line, rest) = match memchr(b'\n', bytes).and_then(|i| bytes.split_at_checked(i)) {
+            Some(x) => x,
+            // Any CR must still be followed by LF.
+            None => return next_line(bytes),
+        };
+
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if memchr(b'\r', line).is_some() {
+            return Err(Error::Syntax);
+        }
+
+        Ok((line, rest.get(1..).unwrap_or_default()))
+    }
+
+    /// Skip over any empty lines at the start of the given bytes.
+    pub(crate) fn skip_empty_lines(self, mut bytes: &[u8]) -> Result<&[u8]> {
+        if self == LineEndings::Lenient {
+            while let Some(rest) = bytes.strip_prefix(b"\n")
+                .or_else(|| bytes.strip_prefix(b"\r\n")) {
+                bytes = rest;
+            }
+        }
+
+        skip_empty_lines(bytes)
+    }
+}
+
+impl ParserConfig {
+    /// Create a new `ParserConfig` with the default configuration.
+    pub const fn new() -> Self {
+        ParserConfig {
+            max_value_len: None,
+            proxy_connection: ProxyConnection::Keep,
+            max_method_len: None,
+            allowed_methods: None,
+            max_scan_len: None,
+            whitespace_lines: WhitespaceLines::Reject,
+            line_endings: LineEndings::Crlf,
+            obs_fold: ObsFold::Split,
+        }
+    }
+
+    /// Create a new `ParserConfig` that closely follows RFC 9112 and rejects common
+    /// smuggling vectors.
+    ///
+    /// Header values are limited to 8 KiB, methods to 32 bytes, and the whole head to 64
+    /// KiB, and Proxy-Connection and folded values are rejected.
+    pub const fn strict_rfc9112() -> Self {
+        ParserConfig {
+            max_value_len: Some(8192),
+            proxy_connection: ProxyConnection::Reject,
+            max_method_len: Some(32),
+            allowed_methods: None,
+            max_scan_len: Some(65536),
+            whitespace_lines: WhitespaceLines::Reject,
+            line_endings: LineEndings::Crlf,
+            obs_fold: ObsFold::Reject,
+        }
+    }
+
+    /// Create a new `ParserConfig` that accepts what real-world browsers and older
+    /// clients send.
+    ///
+    /// Header values are limited to 64 KiB, which accommodates large cookies,
+    /// Proxy-Connection is treated as Connection, whitespace-only header lines are
+    /// skipped, bare LF line terminators are accepted, and folded values are merged.
+    pub const fn lenient_browser() -> Self {
+        ParserConfig {
+            max_value_len: Some(65536),
+            proxy_connection: ProxyConnection::Connection,
+            max_method_len: None,
+            allowed_methods: None,
+            max_scan_len: None,
+            whitespace_lines: WhitespaceLines::Skip,
+            line_endings: LineEndings::Lenient,
+            obs_fold: ObsFold::Merge,
+        }
+    }
+
+    /// Create a new `ParserConfig` suited to small embedded servers with tight memory
+    /// budgets.
+    ///
+    /// Header values are limited to 256 bytes and the whole head to 2 KiB,
+    /// Proxy-Connection is skipped, and only the `GET`, `HEAD`, and `POST` methods are
+    /// accepted.
+    pub const fn embedded_minimal() -> Self {
+        ParserConfig {
+            max_value_len: Some(256),
+            proxy_connection: ProxyConnection::Strip,
+            max_method_len: Some(4),
+            allowed_methods: Some(&["GET", "HEAD", "POST"]),
+            max_scan_len: Some(2048),
+            whitespace_lines: WhitespaceLines::Reject,
+            line_endings: LineEndings::Crlf,
+            obs_fold: ObsFold::Reject,
+        }
+    }
+
+    /// Create a new `ParserConfig` for ICAP requests [RFC3507], which accepts only the
+    /// ICAP methods.
+    ///
+    /// Use `icap::is_icap` to check the protocol version of a parsed request.
+    pub const fn icap() -> Self {
+        ParserConfig {
+            max_method_len: Some(7),
+            allowed_methods: Some(icap::METHODS),
+            ..ParserConfig::new()
+        }
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self { ParserConfig::new() }
+}
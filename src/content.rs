@@ -0,0 +1,203 @@
+//! Parsers for representation metadata header fields [RFC7231§3.1].
+
+use grammar::trim_ows;
+use list::{self, ListValues};
+use {Error, Result};
+
+/// Create an iterator over the language tags in the given Content-Language header value
+/// [RFC7231§3.1.3.2].
+///
+/// Empty list elements are skipped [RFC7230§7]. Each tag is checked to be a sequence of
+/// alphanumeric subtags of 1 to 8 characters separated by hyphens, with an alphabetic
+/// primary subtag [RFC5646§2.1], but isn't checked against any registry.
+pub fn languages<'a>(val: &'a [u8]) -> ContentLanguages<'a> {
+    ContentLanguages(list::values(val))
+}
+
+/// Iterator over the language tags in a Content-Language header value.
+#[derive(Clone, Debug)]
+pub struct ContentLanguages<'a>(ListValues<'a>);
+
+impl<'a> Iterator for ContentLanguages<'a> {
+    type Item = Result<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(|tag| if is_language_tag(tag) {
+            // Tag is ASCII, so this can't fail.
+            std::str::from_utf8(tag).map_err(|_| Error::Syntax)
+        } else {
+            Err(Error::Syntax)
+        }))
+    }
+}
+
+/// Check if the given bytes have the general shape of a language tag.
+fn is_language_tag(tag: &[u8]) -> bool {
+    let mut subtags = tag.split(|&b| b == b'-');
+
+    let primary = match subtags.next() {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if !primary.iter().all(u8::is_ascii_alphabetic) {
+        return false;
+    }
+
+    std::iter::once(primary).chain(subtags).all(|s| {
+        !s.is_empty() && s.len() <= 8 && s.iter().all(u8::is_ascii_alphanumeric)
+    })
+}
+
+/// Components of a Content-Location header value [RFC7231§3.1.4.2].
+///
+/// The value is split along the delimiters of a URI reference [RFC3986§4.1], but
+/// components aren't further validated or percent-decoded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ContentLocation<'a> {
+    /// Scheme, without the trailing `:`, if the value is an absolute URI.
+    pub scheme: Option<&'a str>,
+
+    /// Authority, without the leading `//`, if present.
+    pub authority: Option<&'a str>,
+
+    /// Path, which may be empty.
+    pub path: &'a str,
+
+    /// Query, without the leading `?`, if present.
+    pub query: Option<&'a str>,
+}
+
+impl<'a> ContentLocation<'a> {
+    /// Try to parse the given Content-Location header value, with surrounding optional
+    /// whitespace trimmed.
+    ///
+    /// The value must consist of visible ASCII characters and must not contain a fragment.
+    pub fn new(val: &'a [u8]) -> Result<Self> {
+        let val = trim_ows(val);
+
+        if val.is_empty() || !val.iter().all(|&b| b > b' ' && b < 0x7F) || val.contains(&b'#')
+        {
+            return Err(Error::Syntax);
+        }
+
+        // Value is ASCII, so this can't fail.
+        let mut rest = std::str::from_utf8(val).map_err(|_| Error::Syntax)?;
+
+        let scheme = match rest.find([':', '/', '?']) {
+            Some(idx) if idx > 0 && rest.as_bytes()[idx] == b':' => {
+                let (scheme, after) = rest.split_at(idx);
+
+                if !is_scheme(scheme) {
+                    return Err(Error::Syntax);
+                }
+
+                rest = &after[1..];
+                Some(scheme)
+            },
+            _ => None,
+        };
+
+        let authority = if rest.starts_with("//") {
+            let after = &rest[2..];
+            let end = after.find(['/', '?']).unwrap_or(after.len());
+            let (authority, after) = after.split_at(end);
+
+            rest = after;
+            Some(authority)
+        } else {
+            None
+        };
+
+        let (path, query) = match rest.find('?') {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+            None => (rest, None),
+        };
+
+        Ok(ContentLocation { scheme, authority, path, query })
+    }
+}
+
+/// Check if the given string is a valid URI scheme [RFC3986§3.1].
+fn is_scheme(s: &str) -> bool {
+    let mut bytes = s.bytes();
+
+    bytes.next().is_some_and(|b| b.is_ascii_alphabetic()) &&
+        bytes.all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_languages() {
+        let mut l = languages(b" en-US, , mi,\tx-klingon");
+        assert_eq!(l.next(), Some(Ok("en-US")));
+        assert_eq!(l.next(), Some(Ok("mi")));
+        assert_eq!(l.next(), Some(Ok("x-klingon")));
+        assert_eq!(l.next(), None);
+
+        let mut l = languages(b"\"en, fr\", de");
+        assert_eq!(l.next(), Some(Err(Error::Syntax)));
+        assert_eq!(l.next(), Some(Ok("de")));
+        assert_eq!(l.next(), None);
+
+        let mut l = languages(b"de-1996, 1de, en--US, en-abcdefghi, e n");
+        assert_eq!(l.next(), Some(Ok("de-1996")));
+        assert_eq!(l.next(), Some(Err(Error::Syntax)));
+        assert_eq!(l.next(), Some(Err(Error::Syntax)));
+        assert_eq!(l.next(), Some(Err(Error::Syntax)));
+        assert_eq!(l.next(), Some(Err(Error::Syntax)));
+        assert_eq!(l.next(), None);
+
+        assert_eq!(languages(b"").next(), None);
+    }
+
+    #[test]
+    fn test_content_location() {
+        assert_eq!(ContentLocation::new(b" http://example.com/a/b?c=d ").unwrap(),
+            ContentLocation {
+                scheme: Some("http"),
+                authority: Some("example.com"),
+                path: "/a/b",
+                query: Some("c=d"),
+            });
+
+        assert_eq!(ContentLocation::new(b"/docs/x.json").unwrap(), ContentLocation {
+            scheme: None,
+            authority: None,
+            path: "/docs/x.json",
+            query: None,
+        });
+
+        assert_eq!(ContentLocation::new(b"x.json?").unwrap(), ContentLocation {
+            scheme: None,
+            authority: None,
+            path: "x.json",
+            query: Some(""),
+        });
+
+        assert_eq!(ContentLocation::new(b"//cdn.example.com").unwrap(), ContentLocation {
+            scheme: None,
+            authority: Some("cdn.example.com"),
+            path: "",
+            query: None,
+        });
+
+        assert_eq!(ContentLocation::new(b"urn:isbn:123").unwrap(), ContentLocation {
+            scheme: Some("urn"),
+            authority: None,
+            path: "isbn:123",
+            query: None,
+        });
+
+        assert_eq!(ContentLocation::new(b"a/b:c").unwrap().path, "a/b:c");
+
+        assert_eq!(ContentLocation::new(b""), Err(Error::Syntax));
+        assert_eq!(ContentLocation::new(b"/a b"), Err(Error::Syntax));
+        assert_eq!(ContentLocation::new(b"/a#b"), Err(Error::Syntax));
+        assert_eq!(ContentLocation::new(b"1http://a"), Err(Error::Syntax));
+        assert_eq!(ContentLocation::new(b"/\xff"), Err(Error::Syntax));
+    }
+}
@@ -0,0 +1,240 @@
+//! Parsing of Set-Cookie response header values [RFC6265§5.2], for clients that keep a
+//! cookie jar.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::cookie::{parse_set_cookie, SameSite};
+//!
+//! let c = parse_set_cookie(b"id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+//!                            Path=/; Secure; HttpOnly; SameSite=Lax").unwrap();
+//!
+//! assert_eq!(c.name, "id");
+//! assert_eq!(c.value, b"a3fWa");
+//! assert_eq!(c.expires, Some(1445412480));
+//! assert_eq!(c.path, Some("/"));
+//! assert!(c.secure && c.http_only);
+//! assert_eq!(c.same_site, Some(SameSite::Lax));
+//! ```
+
+use date::{self, FIXDATE_LEN};
+use grammar::trim_ows;
+use {Error, Result};
+
+/// Value of the SameSite attribute.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum SameSite {
+    /// Cookie is sent only with same-site requests.
+    Strict,
+    /// Cookie is also sent with top-level cross-site navigations.
+    Lax,
+    /// Cookie is sent with all requests.
+    None,
+}
+
+/// A cookie set by a Set-Cookie header value, along with its typed attributes.
+///
+/// Attributes that are absent or ignored are `None` or `false`. When an attribute
+/// appears more than once, the last valid occurrence is used.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct SetCookie<'a> {
+    /// Cookie name, with surrounding whitespace trimmed.
+    pub name: &'a str,
+
+    /// Raw cookie value, with surrounding whitespace trimmed, which keeps any
+    /// surrounding quotes.
+    pub value: &'a [u8],
+
+    /// Expiry time in seconds since the Unix epoch.
+    pub expires: Option<u64>,
+
+    /// Number of seconds until expiry, which takes precedence over `expires`. Zero and
+    /// negative values expire the cookie immediately.
+    pub max_age: Option<i64>,
+
+    /// Domain the cookie is sent to, without any leading dot, compared
+    /// case-insensitively.
+    pub domain: Option<&'a str>,
+
+    /// Path the cookie is sent to, which always begins with `/`.
+    pub path: Option<&'a str>,
+
+    /// Whether the cookie is sent only over secure channels.
+    pub secure: bool,
+
+    /// Whether the cookie is hidden from non-HTTP APIs.
+    pub http_only: bool,
+
+    /// Cross-site sending restriction.
+    pub same_site: Option<SameSite>,
+}
+
+/// Try to parse the given Set-Cookie header value.
+///
+/// Following user agent behavior [RFC6265§5.2], malformed attributes are ignored rather
+/// than rejected, and Expires also accepts the dashed and `UTC` variants some servers
+/// send. Cookies without a name or `=` cause `Error::Syntax`, as user agents ignore them.
+pub fn parse_set_cookie(val: &[u8]) -> Result<SetCookie<'_>> {
+    let mut attrs = val.split(|&b| b == b';');
+
+    let pair = attrs.next().unwrap_or_default();
+    let eq = pair.iter().position(|&b| b == b'=').ok_or(Error::Syntax)?;
+
+    let name = std::str::from_utf8(trim_ows(&pair[..eq])).map_err(|_| Error::Syntax)?;
+
+    if name.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    let mut cookie = SetCookie {
+        name,
+        value: trim_ows(&pair[eq + 1..]),
+        expires: None,
+        max_age: None,
+        domain: None,
+        path: None,
+        secure: false,
+        http_only: false,
+        same_site: None,
+    };
+
+    for attr in attrs {
+        let (name, val) = match attr.iter().position(|&b| b == b'=') {
+            Some(idx) => (trim_ows(&attr[..idx]), trim_ows(&attr[idx + 1..])),
+            None => (trim_ows(attr), &b""[..]),
+        };
+
+        let is = |s: &str| name.eq_ignore_ascii_case(s.as_bytes());
+        let text = std::str::from_utf8(val).ok();
+
+        if is("Expires") {
+            cookie.expires = parse_expires(val).or(cookie.expires);
+        } else if is("Max-Age") {
+            cookie.max_age = parse_max_age(val).or(cookie.max_age);
+        } else if is("Domain") {
+            match text.map(|d| d.strip_prefix('.').unwrap_or(d)) {
+                Some(d) if !d.is_empty() => cookie.domain = Some(d),
+                _ => {},
+            }
+        } else if is("Path") {
+            match text {
+                Some(p) if p.starts_with('/') => cookie.path = Some(p),
+                _ => {},
+            }
+        } else if is("Secure") {
+            cookie.secure = true;
+        } else if is("HttpOnly") {
+            cookie.http_only = true;
+        } else if is("SameSite") {
+            let is = |s: &str| val.eq_ignore_ascii_case(s.as_bytes());
+
+            if is("Strict") {
+                cookie.same_site = Some(SameSite::Strict);
+            } else if is("Lax") {
+                cookie.same_site = Some(SameSite::Lax);
+            } else if is("None") {
+                cookie.same_site = Some(SameSite::None);
+            }
+        }
+    }
+
+    Ok(cookie)
+}
+
+/// Parse the given Expires attribute value, or `None` if it's invalid.
+fn parse_expires(val: &[u8]) -> Option<u64> {
+    if let Ok(t) = date::parse(val) {
+        return Some(t);
+    }
+
+    if val.len() != FIXDATE_LEN {
+        return None;
+    }
+
+    // Wed, 21-Oct-2015 07:28:00 UTC
+    let mut buf = [0; FIXDATE_LEN];
+    buf.copy_from_slice(val);
+
+    for idx in [7, 11] {
+        if buf[idx] == b'-' {
+            buf[idx] = b' ';
+        }
+    }
+
+    if buf.ends_with(b" UTC") {
+        buf[FIXDATE_LEN - 3..].copy_from_slice(b"GMT");
+    }
+
+    date::parse(&buf).ok()
+}
+
+/// Parse the given Max-Age attribute value, or `None` if it's invalid.
+fn parse_max_age(val: &[u8]) -> Option<i64> {
+    let (neg, digits) = match val.strip_prefix(b"-") {
+        Some(rest) => (true, rest),
+        None => (false, val),
+    };
+
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    // Saturate rather than ignore absurdly large values.
+    let n = digits.iter().fold(0i64, |n, &b| {
+        n.saturating_mul(10).saturating_add((b - b'0') as i64)
+    });
+
+    Some(if neg { -n } else { n })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let c = parse_set_cookie(b" sid = \"abc\" ;max-age=60; domain=.Example.com;path=/a;\
+                                  secure;HTTPONLY;samesite=strict; Foo=bar").unwrap();
+        assert_eq!(c, SetCookie {
+            name: "sid",
+            value: b"\"abc\"",
+            expires: None,
+            max_age: Some(60),
+            domain: Some("Example.com"),
+            path: Some("/a"),
+            secure: true,
+            http_only: true,
+            same_site: Some(SameSite::Strict),
+        });
+
+        let c = parse_set_cookie(b"a=; Max-Age=-5; Max-Age=x; Domain=; Path=rel; \
+                                   SameSite=Bogus; Expires=never").unwrap();
+        assert_eq!(c.value, b"");
+        assert_eq!(c.max_age, Some(-5));
+        assert_eq!(c.domain, None);
+        assert_eq!(c.path, None);
+        assert_eq!(c.same_site, None);
+        assert_eq!(c.expires, None);
+
+        let c = parse_set_cookie(b"a=b; Max-Age=99999999999999999999; SameSite=None").unwrap();
+        assert_eq!(c.max_age, Some(i64::MAX));
+        assert_eq!(c.same_site, Some(SameSite::None));
+
+        assert_eq!(parse_set_cookie(b"novalue"), Err(Error::Syntax));
+        assert_eq!(parse_set_cookie(b" =v"), Err(Error::Syntax));
+        assert_eq!(parse_set_cookie(b""), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_parse_expires() {
+        let t = Some(1445412480);
+
+        assert_eq!(parse_expires(b"Wed, 21 Oct 2015 07:28:00 GMT"), t);
+        assert_eq!(parse_expires(b"Wed, 21-Oct-2015 07:28:00 GMT"), t);
+        assert_eq!(parse_expires(b"Wed, 21-Oct-2015 07:28:00 UTC"), t);
+        assert_eq!(parse_expires(b"Wednesday, 21-Oct-15 07:28:00 GMT"), t);
+        assert_eq!(parse_expires(b"Wed Oct 21 07:28:00 2015"), t);
+        assert_eq!(parse_expires(b"Wed, 21+Oct+2015 07:28:00 GMT"), None);
+        assert_eq!(parse_expires(b"0"), None);
+    }
+}
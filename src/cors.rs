@@ -0,0 +1,41 @@
+//! Helpers for Cross-Origin Resource Sharing preflight requests [FETCH§3.2].
+
+use grammar::{is_token, trim_ows};
+
+/// Check the given comma-separated list of header names, such as an
+/// Access-Control-Request-Headers value, against the given allow-list.
+///
+/// Names are compared case-insensitively, and empty list elements are skipped
+/// [RFC7230§7]. This can back the decision of which names to echo in
+/// Access-Control-Allow-Headers, such as for gRPC-Web clients requesting
+/// `x-grpc-web` and `x-user-agent`.
+///
+/// Return the first name that isn't a valid token or isn't allowed, with surrounding
+/// whitespace trimmed, or `None` if every name is allowed.
+pub fn first_disallowed<'a>(val: &'a [u8], allowed: &[&str]) -> Option<&'a [u8]> {
+    val.split(|&b| b == b',')
+        .map(trim_ows)
+        .filter(|n| !n.is_empty())
+        .find(|n| {
+            !is_token(n) || !allowed.iter().any(|a| a.as_bytes().eq_ignore_ascii_case(n))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_first_disallowed() {
+        let allowed = ["Content-Type", "x-grpc-web", "X-User-Agent"];
+
+        assert_eq!(first_disallowed(b"content-type,x-grpc-web , x-user-agent", &allowed),
+            None);
+        assert_eq!(first_disallowed(b" , X-GRPC-WEB,,", &allowed), None);
+        assert_eq!(first_disallowed(b"", &allowed), None);
+        assert_eq!(first_disallowed(b"x-grpc-web, authorization, x-foo", &allowed),
+            Some(&b"authorization"[..]));
+        assert_eq!(first_disallowed(b"content type", &allowed), Some(&b"content type"[..]));
+        assert_eq!(first_disallowed(b"x-grpc-web", &[]), Some(&b"x-grpc-web"[..]));
+    }
+}
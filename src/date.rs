@@ -0,0 +1,207 @@
+//! HTTP-date handling [RFC7231§7.1.1.1].
+//!
+//! Times are represented as whole seconds since the Unix epoch.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use grammar::trim_ows;
+use {Error, Result};
+
+/// Abbreviated day names, starting with Sunday.
+static DAYS: [&[u8; 3]; 7] = [b"Sun", b"Mon", b"Tue", b"Wed", b"Thu", b"Fri", b"Sat"];
+
+/// Full day names, starting with Sunday.
+static LONG_DAYS: [&[u8]; 7] = [
+    b"Sunday", b"Monday", b"Tuesday", b"Wednesday", b"Thursday", b"Friday", b"Saturday",
+];
+
+/// Abbreviated month names, starting with January.
+static MONTHS: [&[u8; 3]; 12] = [
+    b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun",
+    b"Jul", b"Aug", b"Sep", b"Oct", b"Nov", b"Dec",
+];
+
+/// Length of a formatted IMF-fixdate.
+pub const FIXDATE_LEN: usize = 29;
+
+/// Retrieve the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Format the given time as an IMF-fixdate, such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub fn format(secs: u64) -> [u8; FIXDATE_LEN] {
+    let days = secs / 86400;
+    let rem = secs % 86400;
+    let (year, month, day) = civil(days);
+
+    let mut buf = *b"Thu, 01 Jan 1970 00:00:00 GMT";
+
+    buf[..3].copy_from_slice(DAYS[((days + 4) % 7) as usize]);
+    digits(&mut buf[5..7], day as u64);
+    buf[8..11].copy_from_slice(MONTHS[month as usize - 1]);
+    digits(&mut buf[12..16], year);
+    digits(&mut buf[17..19], rem / 3600);
+    digits(&mut buf[20..22], rem / 60 % 60);
+    digits(&mut buf[23..25], rem % 60);
+
+    buf
+}
+
+/// Try to parse the given HTTP-date, with surrounding whitespace trimmed, into seconds
+/// since the Unix epoch.
+///
+/// Along with the IMF-fixdate format, the obsolete RFC 850 and asctime formats are
+/// accepted [RFC7231§7.1.1.1], and two-digit RFC 850 years are taken to be in the range
+/// 1970 through 2069. Dates before the Unix epoch are rejected, and the day name isn't
+/// checked against the date.
+pub fn parse(val: &[u8]) -> Result<u64> {
+    let val = trim_ows(val);
+
+    let (year, month, day, time) = if val.len() == FIXDATE_LEN && val.get(3) == Some(&b',') {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        expect(DAYS.iter().any(|d| d[..] == val[..3]))?;
+        expect(val[4] == b' ' && val[7] == b' ' && val[11] == b' ' && val[16] == b' ')?;
+        expect(&val[25..] == b" GMT")?;
+
+        (number(&val[12..16])?, month(&val[8..11])?, number(&val[5..7])?, &val[17..25])
+    } else if val.len() == 24 && val[3] == b' ' {
+        // Sun Nov  6 08:49:37 1994
+        expect(DAYS.iter().any(|d| d[..] == val[..3]))?;
+        expect(val[7] == b' ' && val[10] == b' ' && val[19] == b' ')?;
+
+        let day = if val[8] == b' ' { &val[9..10] } else { &val[8..10] };
+
+        (number(&val[20..24])?, month(&val[4..7])?, number(day)?, &val[11..19])
+    } else {
+        // Sunday, 06-Nov-94 08:49:37 GMT
+        let comma = val.iter().position(|&b| b == b',').ok_or(Error::Syntax)?;
+        expect(LONG_DAYS.contains(&&val[..comma]))?;
+
+        let rest = &val[comma + 1..];
+        expect(rest.len() == 23 && rest[0] == b' ' && rest[3] == b'-' && rest[7] == b'-')?;
+        expect(rest[10] == b' ' && &rest[19..] == b" GMT")?;
+
+        let year = number(&rest[8..10])?;
+        let year = if year < 70 { 2000 + year } else { 1900 + year };
+
+        (year, month(&rest[4..7])?, number(&rest[1..3])?, &rest[11..19])
+    };
+
+    expect(time[2] == b':' && time[5] == b':')?;
+
+    let hour = number(&time[..2])?;
+    let min = number(&time[3..5])?;
+    let sec = number(&time[6..])?;
+
+    // Allow for a leap second.
+    expect(hour < 24 && min < 60 && sec <= 60)?;
+
+    Ok(days(year, month, day)? * 86400 + hour * 3600 + min * 60 + sec)
+}
+
+/// Return `Error::Syntax` unless the given condition holds.
+fn expect(cond: bool) -> Result<()> {
+    if cond { Ok(()) } else { Err(Error::Syntax) }
+}
+
+/// Parse the given bytes as a decimal number.
+fn number(bytes: &[u8]) -> Result<u64> {
+    expect(!bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit))?;
+    Ok(bytes.iter().fold(0, |n, &b| n * 10 + (b - b'0') as u64))
+}
+
+/// Parse the given abbreviated month name into a month number starting at 1.
+fn month(name: &[u8]) -> Result<u32> {
+    MONTHS.iter().position(|m| m[..] == *name)
+        .map(|m| m as u32 + 1)
+        .ok_or(Error::Syntax)
+}
+
+/// Convert the given proleptic Gregorian date into the number of days since the Unix
+/// epoch, checking that the date exists.
+fn days(year: u64, month: u32, day: u64) -> Result<u64> {
+    expect(year >= 1970 && (1..=31).contains(&day))?;
+
+    // Same shifted epoch as `civil`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y % 400;
+    let mp = (month as u64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    // Invalid days such as Feb 30 roll over into the next month.
+    expect(civil(days) == (year, month, day as u32))?;
+
+    Ok(days)
+}
+
+/// Write the given number as zero-padded decimal digits filling the given buffer.
+fn digits(buf: &mut [u8], mut n: u64) {
+    for b in buf.iter_mut().rev() {
+        *b = b'0' + (n % 10) as u8;
+        n /= 10;
+    }
+}
+
+/// Convert the given number of days since the Unix epoch into a proleptic Gregorian
+/// `(year, month, day)`.
+pub(crate) fn civil(days: u64) -> (u64, u32, u32) {
+    // Shift the epoch to 0000-03-01 so leap days fall at the end of each year.
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(&format(0)[..], b"Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(&format(784111777)[..], b"Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(&format(951782400)[..], b"Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(&format(1485129599)[..], b"Sun, 22 Jan 2017 23:59:59 GMT");
+        assert_eq!(&format(253402300799)[..], b"Fri, 31 Dec 9999 23:59:59 GMT");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(b"Sun, 06 Nov 1994 08:49:37 GMT"), Ok(784111777));
+        assert_eq!(parse(b" Sunday, 06-Nov-94 08:49:37 GMT "), Ok(784111777));
+        assert_eq!(parse(b"Sun Nov  6 08:49:37 1994"), Ok(784111777));
+        assert_eq!(parse(b"Thu, 01 Jan 1970 00:00:00 GMT"), Ok(0));
+        assert_eq!(parse(b"Tue, 29 Feb 2000 00:00:00 GMT"), Ok(951782400));
+        assert_eq!(parse(b"Monday, 01-Jan-69 00:00:00 GMT"), Ok(3124224000));
+        assert_eq!(parse(b"Wed Dec 31 23:59:60 2036"), Ok(2114380800));
+
+        for &d in &[
+            &b""[..],
+            b"Sun, 06 Nov 1994 08:49:37 UTC",
+            b"Xyz, 06 Nov 1994 08:49:37 GMT",
+            b"Sun, 06 nov 1994 08:49:37 GMT",
+            b"Sun, 30 Feb 1994 08:49:37 GMT",
+            b"Sun, 06 Nov 1994 24:00:00 GMT",
+            b"Sun, 06 Nov 1969 08:49:37 GMT",
+            b"Sun, 6 Nov 1994 08:49:37 GMT",
+            b"Sun,  6 Nov 1994 08:49:37 GMT",
+            b"Sun, 06 Nov 1994 08-49-37 GMT",
+            b"Sun, 06-Nov-94 08:49:37 GMT",
+            b"Sunday, 06-Nov-1994 08:49:37 GMT",
+            b"Sun Nov 06 08:49:37 94",
+        ] {
+            assert_eq!(parse(d), Err(Error::Syntax));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Locating where parsing of a request head failed.
+
+use memchr::memchr;
+
+use grammar::is_ows;
+use {Error, Headers, LineEndings, ParserConfig, RequestLine, WhitespaceLines};
+
+/// Component of a request head that was being parsed when an error occurred.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Component {
+    /// Request method.
+    Method,
+    /// Request target.
+    Target,
+    /// HTTP version of the Request-Line.
+    Version,
+    /// Name of a header field, including the colon that ends it.
+    HeaderName,
+    /// Value of a header field.
+    HeaderValue,
+    /// Terminator of a line.
+    LineEnding,
+}
+
+/// An error along with where in the input it occurred.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct DetailedError {
+    /// Underlying error.
+    pub error: Error,
+
+    /// Component being parsed.
+    pub component: Component,
+
+    /// Offset of the byte within the input where parsing failed, which is the length of
+    /// the input, or of the scanned part of it, for incomplete input.
+    pub offset: usize,
+}
+
+impl DetailedError {
+    /// Locate the given error that occurred while parsing the given request head with
+    /// the given configuration.
+    pub(crate) fn locate(buf: &[u8], config: ParserConfig, error: Error) -> Self {
+        // Budget errors occur like incomplete input within the scanned bytes.
+        let (buf, kind) = match (error, config.max_scan_len) {
+            (Error::Budget, Some(max)) => (buf.get(..max).unwrap_or(buf), Error::Partial),
+            _ => (buf, error),
+        };
+
+        let (component, offset) = locate(buf, config, kind);
+
+        DetailedError { error, component, offset }
+    }
+}
+
+/// Find the component and offset of the given error in the given request head.
+fn locate(buf: &[u8], config: ParserConfig, error: Error) -> (Component, usize) {
+    let start = match config.line_endings.skip_empty_lines(buf) {
+        Ok(rest) => buf.len() - rest.len(),
+        Err(_) => return (Component::Method, buf.len()),
+    };
+
+    let block = match RequestLine::with_config(buf, config) {
+        Ok((_, block)) => block,
+        Err(Error::UnsupportedMethod) => return (Component::Method, start),
+        Err(_) => {
+            let (component, idx) = locate_line(&buf[start..], config.line_endings, error);
+            return (component, start + idx);
+        },
+    };
+
+    let mut headers = Headers::with_config(block, config);
+
+    // Some errors occur after the failing line was consumed, so start from the bytes
+    // before the failing call.
+    let mut rest = loop {
+        let before = headers.buf;
+
+        match headers.next() {
+            Some(Ok(_)) => {},
+            _ => break before,
+        }
+    };
+
+    // Skip any lines that were skipped before reaching the failing line.
+    if config.whitespace_lines == WhitespaceLines::Skip {
+        while let Ok((line, next)) = config.line_endings.next_line(rest) {
+            if line.is_empty() || !line.iter().all(|&b| is_ows(b)) {
+                break;
+            }
+
+            rest = next;
+        }
+    }
+
+    let line_start = buf.len() - rest.len();
+    let (component, idx) = locate_field(rest, config, error);
+
+    (component, line_start + idx)
+}
+
+/// Find the component and offset of the given error in the Request-Line at the start of
+/// the given bytes.
+fn locate_line(line: &[u8], endings: LineEndings, error: Error) -> (Component, usize) {
+    let (text, bad) = split_line(line, endings);
+
+    if let Some(idx) = bad {
+        return (Component::LineEnding, idx);
+    }
+
+    // Component containing the given offset, by counting preceding spaces.
+    let component = |idx: usize| match text[..idx].iter().filter(|&&b| b == b' ').count() {
+        0 => Component::Method,
+        1 => Component::Target,
+        _ => Component::Version,
+    };
+
+    if error == Error::Partial {
+        return match text.len() < line.len() {
+            true => (Component::LineEnding, line.len()),
+            false => (component(text.len()), line.len()),
+        };
+    }
+
+    if let Err(e) = std::str::from_utf8(text) {
+        return (component(e.valid_up_to()), e.valid_up_to());
+    }
+
+    // Otherwise, there must be exactly two spaces.
+    match text.iter().enumerate().filter(|&(_, &b)| b == b' ').nth(2) {
+        Some((idx, _)) => (Component::Version, idx),
+        None => (match component(text.len()) {
+            Component::Method => Component::Target,
+            _ => Component::Version,
+        }, text.len()),
+    }
+}
+
+/// Find the component and offset of the given error in the header field line at the
+/// start of the given bytes.
+fn locate_field(line: &[u8], config: ParserConfig, error: Error) -> (Component, usize) {
+    let (text, bad) = split_line(line, config.line_endings);
+    let colon = memchr(b':', text);
+
+    if let (Error::ValueTooLong, Some(idx), Some(max)) = (error, colon, config.max_value_len) {
+        return (Component::HeaderValue, idx + 1 + max);
+    }
+
+    if let Some(idx) = bad {
+        return (Component::LineEnding, idx);
+    }
+
+    // Folded lines continue the previous value.
+    if error == Error::ObsFold {
+        return (Component::HeaderValue, 0);
+    }
+
+    if error == Error::Partial {
+        return match (text.len() < line.len(), colon) {
+            (true, _) => (Component::LineEnding, line.len()),
+            (false, Some(_)) => (Component::HeaderValue, line.len()),
+            (false, None) => (Component::HeaderName, line.len()),
+        };
+    }
+
+    let idx = match colon {
+        Some(idx) => idx,
+        None => return (Component::HeaderName, text.len()),
+    };
+
+    match std::str::from_utf8(&text[..idx]) {
+        Ok(_) => (Component::HeaderName, 0),
+        Err(e) => (Component::HeaderName, e.valid_up_to()),
+    }
+}
+
+/// Split the given bytes into the line up to any CR or LF and, if the line terminator
+/// is malformed or a bare LF that isn't recognized, its offset.
+fn split_line(bytes: &[u8], endings: LineEndings) -> (&[u8], Option<usize>) {
+    let end = bytes.iter().position(|&b| b == b'\r' || b == b'\n').unwrap_or(bytes.len());
+
+    let bad = match (bytes.get(end), bytes.get(end + 1)) {
+        (Some(b'\r'), Some(&b)) if b != b'\n' => Some(end),
+        (Some(b'\n'), _) if endings == LineEndings::Crlf => Some(end),
+        _ => None,
+    };
+
+    (&bytes[..end], bad)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use {Head, LineEndings, ProxyConnection};
+
+    #[test]
+    fn test_with_details() {
+        let d = |buf: &[u8], config| {
+            let e = Head::with_details(buf, config).unwrap_err();
+            (e.error, e.component, e.offset)
+        };
+
+        let c = ParserConfig::new();
+
+        assert_eq!(d(b"", c), (Error::Partial, Component::Method, 0));
+        assert_eq!(d(b"\r\nGE", c), (Error::Partial, Component::Method, 4));
+        assert_eq!(d(b"GET /a", c), (Error::Partial, Component::Target, 6));
+        assert_eq!(d(b"GET / HTTP/1.1\r", c), (Error::Partial, Component::LineEnding, 15));
+        assert_eq!(d(b"GET / HTTP/1.1\nHost: a\n", c),
+            (Error::Partial, Component::LineEnding, 14));
+        assert_eq!(d(b"GET / HTTP/1.1\rX\r\n", c), (Error::Syntax, Component::LineEnding, 14));
+        assert_eq!(d(b"\r\nGET /\r\n", c), (Error::Syntax, Component::Version, 7));
+        assert_eq!(d(b"GET\r\n", c), (Error::Syntax, Component::Target, 3));
+        assert_eq!(d(b"GET / HTTP/1.1 x\r\n", c), (Error::Syntax, Component::Version, 14));
+        assert_eq!(d(b"GET  / HTTP/1.1\r\n", c), (Error::Syntax, Component::Version, 6));
+        assert_eq!(d(b"GET /\xff HTTP/1.1\r\n", c), (Error::Syntax, Component::Target, 5));
+
+        let req = b"GET / HTTP/1.1\r\nHost: a\r\n";
+        assert_eq!(d(req, c), (Error::Partial, Component::HeaderName, 25));
+        assert_eq!(d(b"GET / HTTP/1.1\r\nHost: a", c),
+            (Error::Partial, Component::HeaderValue, 23));
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA: b\r\nHost\r\n\r\n", c),
+            (Error::Syntax, Component::HeaderName, 26));
+        assert_eq!(d(b"GET / HTTP/1.1\r\n : b\r\n\r\n", c),
+            (Error::Syntax, Component::HeaderName, 16));
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA\xff: b\r\n\r\n", c),
+            (Error::Syntax, Component::HeaderName, 17));
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA: b\rc\r\n\r\n", c),
+            (Error::Syntax, Component::LineEnding, 20));
+
+        let config = ParserConfig { max_value_len: Some(3), ..c };
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA: bcdef", config),
+            (Error::ValueTooLong, Component::HeaderValue, 21));
+
+        let config = ParserConfig { max_scan_len: Some(20), ..c };
+        assert_eq!(d(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n", config),
+            (Error::Budget, Component::HeaderName, 20));
+
+        let config = ParserConfig { allowed_methods: Some(&["GET"]), ..c };
+        assert_eq!(d(b"\r\nPUT / HTTP/1.1\r\n\r\n", config),
+            (Error::UnsupportedMethod, Component::Method, 2));
+
+        let config = ParserConfig::strict_rfc9112();
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA: b\r\n c\r\n\r\n", config),
+            (Error::ObsFold, Component::HeaderValue, 22));
+
+        let config = ParserConfig { proxy_connection: ProxyConnection::Reject, ..c };
+        assert_eq!(d(b"GET / HTTP/1.1\r\nA: b\r\nProxy-Connection: c\r\n\r\n", config),
+            (Error::Syntax, Component::HeaderName, 22));
+
+        let config = ParserConfig { line_endings: LineEndings::Lenient, ..c };
+        assert_eq!(d(b"GET / HTTP/1.1\nA\n\n", config),
+            (Error::Syntax, Component::HeaderName, 16));
+
+        let config = ParserConfig { whitespace_lines: WhitespaceLines::Skip, ..c };
+        assert_eq!(d(b"GET / HTTP/1.1\r\n \r\n\t\r\nA\r\n\r\n", config),
+            (Error::Syntax, Component::HeaderName, 23));
+
+        assert!(Head::with_details(b"GET / HTTP/1.1\r\n\r\n", c).is_ok());
+    }
+}
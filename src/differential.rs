@@ -0,0 +1,111 @@
+//! Harness comparing request head parsing against httparse on tricky inputs.
+//!
+//! Cases are loaded from `tests/corpus/differential`, one raw request head per `.http`
+//! file, drawn from the httparse test suite and request smuggling research. Each case
+//! is also parsed by httparse 1.x, a dev-dependency, with its default configuration,
+//! and the two verdicts must agree unless the case is listed in `DIVERGENCES` along
+//! with a description of the deliberate or known difference. The test fails both when an undocumented divergence appears and when a documented one
+//! goes away, so the list stays an accurate record of where the two parsers differ.
+
+use std::fs;
+use std::path::PathBuf;
+
+use {Error, Head, ParserConfig};
+
+/// Outcome of parsing a request head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Verdict {
+    /// Complete head with the given number of header fields.
+    Complete(usize),
+    /// Incomplete head.
+    Partial,
+    /// Malformed head.
+    Invalid,
+}
+
+/// Corpus cases, by file name without extension, where this crate reaches a different
+/// verdict than httparse, along with the reason.
+const DIVERGENCES: &[(&str, &str)] = &[
+    ("bare-lf", "bare LF isn't accepted as a line terminator"),
+    ("empty-method", "an empty method isn't rejected by the Request-Line parser"),
+    ("method-del", "the method isn't validated as a token"),
+    ("target-del", "the target is only checked for whitespace"),
+    ("lowercase-version", "the version is left to RequestLine::version_parsed"),
+    ("version-2", "the version is left to RequestLine::version_parsed"),
+    ("space-before-colon", "whitespace before the colon is tolerated"),
+    ("name-control", "field names aren't validated as tokens"),
+    ("value-nul", "field values aren't checked for control characters"),
+];
+
+/// Load every case in the corpus directory as its name and contents, sorted by name.
+fn corpus() -> Vec<(String, Vec<u8>)> {
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "corpus", "differential"]
+        .iter().collect();
+
+    let mut cases: Vec<_> = fs::read_dir(&dir).expect("corpus directory")
+        .map(|entry| entry.expect("corpus entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "http"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let input = fs::read(&path).expect("corpus file");
+            (name, input)
+        })
+        .collect();
+
+    cases.sort();
+    assert!(!cases.is_empty(), "empty corpus in {:?}", dir);
+
+    cases
+}
+
+/// Parse the given head with the given configuration and reduce the result to a verdict.
+fn verdict(buf: &[u8], config: ParserConfig) -> Verdict {
+    match Head::with_config(buf, config) {
+        Ok((head, _)) => Verdict::Complete(head.stats.headers),
+        Err(Error::Partial) => Verdict::Partial,
+        Err(_) => Verdict::Invalid,
+    }
+}
+
+/// Parse the given head with httparse and reduce the result to a verdict.
+fn httparse_verdict(buf: &[u8]) -> Verdict {
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut req = httparse::Request::new(&mut headers);
+
+    match req.parse(buf) {
+        Ok(httparse::Status::Complete(_)) => Verdict::Complete(req.headers.len()),
+        Ok(httparse::Status::Partial) => Verdict::Partial,
+        Err(_) => Verdict::Invalid,
+    }
+}
+
+#[test]
+fn test_differential() {
+    let cases = corpus();
+
+    for &(name, _) in DIVERGENCES {
+        assert!(cases.iter().any(|(n, _)| n == name), "no corpus case {:?}", name);
+    }
+
+    for (name, input) in &cases {
+        let ours = verdict(input, ParserConfig::new());
+        let theirs = httparse_verdict(input);
+
+        match DIVERGENCES.iter().find(|&&(n, _)| n == name) {
+            None => assert_eq!(ours, theirs, "unexpected divergence on {:?}", name),
+            Some((_, why)) => assert!(ours != theirs, "{:?} no longer diverges: {}",
+                name, why),
+        }
+    }
+}
+
+#[test]
+fn test_strict_rejects() {
+    // Strict mode must reject at least everything the default configuration does.
+    for (name, input) in corpus() {
+        if verdict(&input, ParserConfig::new()) == Verdict::Invalid {
+            assert_eq!(verdict(&input, ParserConfig::strict_rfc9112()), Verdict::Invalid,
+                "{:?} accepted in strict mode", name);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Entity tags [RFC7232§2.3].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::etag::{tag_list, EntityTag, TagList};
+//!
+//! let e = EntityTag::new(b"W/\"abc\"").unwrap();
+//! assert!(e.weak);
+//! assert_eq!(e.tag, b"abc");
+//!
+//! let mut tags = match tag_list(b"\"abc\", W/\"def\"").unwrap() {
+//!     TagList::Tags(tags) => tags,
+//!     TagList::Any => unreachable!(),
+//! };
+//!
+//! assert!(tags.next().unwrap().unwrap().weak_eq(&e));
+//! assert_eq!(tags.next().unwrap().unwrap().tag, b"def");
+//! assert!(tags.next().is_none());
+//!
+//! assert!(matches!(tag_list(b" * "), Ok(TagList::Any)));
+//! ```
+
+use grammar::trim_ows;
+use writer::Cursor;
+use {Error, Result};
+
+/// A parsed entity tag.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct EntityTag<'a> {
+    /// Whether the tag has the `W/` weakness indicator.
+    pub weak: bool,
+
+    /// Opaque tag, without its quotes.
+    pub tag: &'a [u8],
+}
+
+impl<'a> EntityTag<'a> {
+    /// Try to parse the given entity tag, such as an ETag header value, with surrounding
+    /// whitespace trimmed.
+    pub fn new(val: &'a [u8]) -> Result<Self> {
+        match split_tag(trim_ows(val))? {
+            (tag, b"") => Ok(tag),
+            _ => Err(Error::Syntax),
+        }
+    }
+
+    /// Compare with the given entity tag using the strong comparison function
+    /// [RFC7232§2.3.2], where both must be strong and have the same opaque tag.
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Compare with the given entity tag using the weak comparison function
+    /// [RFC7232§2.3.2], where only the opaque tags must be the same.
+    pub fn weak_eq(&self, other: &EntityTag) -> bool { self.tag == other.tag }
+}
+
+/// An If-Match or If-None-Match header value [RFC7232§3.1].
+#[derive(Clone, Debug)]
+pub enum TagList<'a> {
+    /// The `*` value, matching any current representation.
+    Any,
+    /// A list of entity tags.
+    Tags(EntityTags<'a>),
+}
+
+/// Try to parse the given If-Match or If-None-Match header value, with surrounding
+/// whitespace trimmed, as `*` or a list of entity tags.
+///
+/// The tags are only parsed as they're iterated.
+pub fn tag_list(val: &[u8]) -> Result<TagList<'_>> {
+    match trim_ows(val) {
+        b"*" => Ok(TagList::Any),
+        val => Ok(TagList::Tags(EntityTags(val))),
+    }
+}
+
+/// Iterator over the entity tags in a comma-separated list.
+///
+/// Empty list elements are skipped. If an element isn't a valid entity tag, including a
+/// `*` within the list, `Error::Syntax` is yielded and iteration stops.
+#[derive(Clone, Debug)]
+pub struct EntityTags<'a>(&'a [u8]);
+
+impl<'a> Iterator for EntityTags<'a> {
+    type Item = Result<EntityTag<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.0.iter().position(|&b| b != b',' && !b" \t".contains(&b))?;
+
+        let parsed = split_tag(&self.0[start..]).and_then(|(tag, after)| {
+            let after = trim_ows(after);
+
+            if after.is_empty() || after[0] == b',' {
+                Ok((tag, after))
+            } else {
+                Err(Error::Syntax)
+            }
+        });
+
+        match parsed {
+            Ok((tag, after)) => {
+                self.0 = after;
+                Some(Ok(tag))
+            },
+            Err(e) => {
+                self.0 = &[];
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Split an entity tag from the start of the given bytes, returning it along with the
+/// bytes that follow it.
+fn split_tag(bytes: &[u8]) -> Result<(EntityTag<'_>, &[u8])> {
+    let (weak, rest) = match bytes.strip_prefix(b"W/") {
+        Some(rest) => (true, rest),
+        None => (false, bytes),
+    };
+
+    let rest = rest.strip_prefix(b"\"").ok_or(Error::Syntax)?;
+    let end = rest.iter().position(|&b| b == b'"').ok_or(Error::Syntax)?;
+    let tag = &rest[..end];
+
+    // etagc = %x21 / %x23-7E / obs-text
+    if tag.iter().any(|&b| b < 0x21 || b == 0x7F) {
+        return Err(Error::Syntax);
+    }
+
+    Ok((EntityTag { weak, tag }, &rest[end + 1..]))
+}
+
+/// Write an entity tag with the given 64-bit hash as its opaque tag into the given
+/// buffer, such as `"0123456789abcdef"` or `W/"0123456789abcdef"` if `weak`.
+///
+/// On success, return the number of bytes written.
+pub fn format64(hash: u64, weak: bool, buf: &mut [u8]) -> Result<usize> {
+    format(hash as u128, 16, weak, buf)
+}
+
+/// Write an entity tag with the given 128-bit hash as its opaque tag into the given
+/// buffer, such as `"000102030405060708090a0b0c0d0e0f"` or the same with a `W/` prefix
+/// if `weak`.
+///
+/// On success, return the number of bytes written.
+pub fn format128(hash: u128, weak: bool, buf: &mut [u8]) -> Result<usize> {
+    format(hash, 32, weak, buf)
+}
+
+/// Write an entity tag with the given number of lowercase hex digits of the given hash.
+fn format(hash: u128, digits: usize, weak: bool, buf: &mut [u8]) -> Result<usize> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    let mut out = Cursor::new(buf);
+
+    if weak {
+        out.push(b"W/")?;
+    }
+
+    out.push(b"\"")?;
+
+    for i in (0..digits).rev() {
+        out.push(&[HEX[(hash >> (i * 4) & 0xF) as usize]])?;
+    }
+
+    out.push(b"\"")?;
+
+    Ok(out.pos())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    #[test]
+    fn test_entity_tag() {
+        assert_eq!(EntityTag::new(b" \"abc\" "), Ok(EntityTag { weak: false, tag: b"abc" }));
+        assert_eq!(EntityTag::new(b"W/\"\""), Ok(EntityTag { weak: true, tag: b"" }));
+        assert_eq!(EntityTag::new(b"\"\x80!\""),
+            Ok(EntityTag { weak: false, tag: b"\x80!" }));
+        assert_eq!(EntityTag::new(b"abc"), Err(Error::Syntax));
+        assert_eq!(EntityTag::new(b"w/\"abc\""), Err(Error::Syntax));
+        assert_eq!(EntityTag::new(b"\"abc"), Err(Error::Syntax));
+        assert_eq!(EntityTag::new(b"\"a b\""), Err(Error::Syntax));
+        assert_eq!(EntityTag::new(b"\"a\"b"), Err(Error::Syntax));
+
+        let strong = EntityTag::new(b"\"1\"").unwrap();
+        let weak = EntityTag::new(b"W/\"1\"").unwrap();
+        let other = EntityTag::new(b"\"2\"").unwrap();
+
+        assert!(strong.strong_eq(&strong));
+        assert!(!strong.strong_eq(&weak));
+        assert!(!weak.strong_eq(&weak));
+        assert!(weak.weak_eq(&strong));
+        assert!(weak.weak_eq(&weak));
+        assert!(!strong.weak_eq(&other));
+    }
+
+    #[test]
+    fn test_tag_list() {
+        let tags = |val| match tag_list(val).unwrap() {
+            TagList::Tags(t) => t,
+            TagList::Any => panic!(),
+        };
+
+        let mut t = tags(b" \"a\", W/\"b,c\" ,, \"\",");
+        assert_eq!(t.next(), Some(Ok(EntityTag { weak: false, tag: b"a" })));
+        assert_eq!(t.next(), Some(Ok(EntityTag { weak: true, tag: b"b,c" })));
+        assert_eq!(t.next(), Some(Ok(EntityTag { weak: false, tag: b"" })));
+        assert_eq!(t.next(), None);
+
+        let mut t = tags(b"\"a\" \"b\"");
+        assert_eq!(t.next(), Some(Err(Error::Syntax)));
+        assert_eq!(t.next(), None);
+
+        assert_eq!(tags(b"\"a\", *").nth(1), Some(Err(Error::Syntax)));
+        assert_eq!(tags(b"a").next(), Some(Err(Error::Syntax)));
+        assert_eq!(tags(b"").next(), None);
+        assert!(matches!(tag_list(b"*"), Ok(TagList::Any)));
+    }
+
+    #[test]
+    fn test_format() {
+        let mut buf = [0; 64];
+
+        let len = format64(0x0123456789abcdef, false, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\"0123456789abcdef\"");
+
+        let len = format64(0xff, true, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"W/\"00000000000000ff\"");
+
+        let len = format128(0x000102030405060708090a0b0c0d0e0f, false, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\"000102030405060708090a0b0c0d0e0f\"");
+
+        let len = format128(u128::MAX, true, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"W/\"ffffffffffffffffffffffffffffffff\"");
+
+        assert_eq!(format64(0, false, &mut buf[..17]), Err(Error::Capacity));
+        assert_eq!(format64(0, false, &mut buf[..18]), Ok(18));
+    }
+}
@@ -0,0 +1,91 @@
+//! Tolerant extraction of the mailbox in a From header [RFC7231§5.5.1].
+
+use grammar::trim_ows;
+use {Error, Result};
+
+/// Extract the `local@domain` addr-spec from the given From header value.
+///
+/// This handles the common mailbox forms, such as a bare address, an address in angle
+/// brackets with an optional (possibly quoted) display name, and an address followed by a
+/// comment, without implementing the full RFC 5322 grammar. The extracted address must
+/// contain a single `@` with nonempty local and domain parts and no whitespace.
+pub fn addr_spec(val: &[u8]) -> Result<&str> {
+    let val = std::str::from_utf8(trim_ows(val)).map_err(|_| Error::Syntax)?;
+
+    let addr = match find_unquoted(val, '<') {
+        Some(start) => {
+            let inner = &val[start + 1..];
+            &inner[..inner.find('>').ok_or(Error::Syntax)?]
+        },
+        None => {
+            let at = find_unquoted(val, '@').ok_or(Error::Syntax)?;
+            let is_delim = |c: char| c.is_whitespace() || "()<>\",;".contains(c);
+
+            let start = val[..at].rfind(is_delim).map_or(0, |i| i + 1);
+            let end = val[at..].find(is_delim).map_or(val.len(), |i| at + i);
+
+            &val[start..end]
+        },
+    };
+
+    let addr = addr.trim();
+    let (local, domain) = addr.split_at(addr.find('@').ok_or(Error::Syntax)?);
+    let domain = &domain[1..];
+
+    if local.is_empty() || domain.is_empty() || domain.contains('@') ||
+        addr.contains(char::is_whitespace)
+    {
+        return Err(Error::Syntax);
+    }
+
+    Ok(addr)
+}
+
+/// Find the first occurrence of the given character outside of any quoted-string or
+/// comment.
+fn find_unquoted(s: &str, needle: char) -> Option<usize> {
+    let mut quoted = false;
+    let mut depth = 0usize;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' if quoted || depth > 0 => escaped = true,
+            '"' if depth == 0 => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted && depth > 0 => depth -= 1,
+            _ if c == needle && !quoted && depth == 0 => return Some(i),
+            _ => {},
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_addr_spec() {
+        assert_eq!(addr_spec(b"webmaster@example.org"), Ok("webmaster@example.org"));
+        assert_eq!(addr_spec(b" bot@example.com "), Ok("bot@example.com"));
+        assert_eq!(addr_spec(b"Crawler <crawl@example.com>"), Ok("crawl@example.com"));
+        assert_eq!(addr_spec(b"<crawl@example.com>"), Ok("crawl@example.com"));
+        assert_eq!(addr_spec(b"\"Doe, <J>\" < j@example.com >"), Ok("j@example.com"));
+        assert_eq!(addr_spec(b"bot@example.com (Example Bot)"), Ok("bot@example.com"));
+        assert_eq!(addr_spec(b"(bot <x@y>) bot@example.com"), Ok("bot@example.com"));
+        assert_eq!(addr_spec(b"googlebot(at)googlebot.com"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b"Name <crawl@example.com"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b"<@example.com>"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b"<a@>"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b"<a@b@c>"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b"<a b@c>"), Err(Error::Syntax));
+        assert_eq!(addr_spec(b""), Err(Error::Syntax));
+    }
+}
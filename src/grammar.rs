@@ -0,0 +1,144 @@
+//! Character classes and helpers from the HTTP grammar [RFC7230§1.2].
+//!
+//! The character classes are backed by a single lookup table, and they're the same
+//! definitions the parsers in this crate use, so they can be used to validate or
+//! sanitize values consistently before writing them.
+
+/// Bit set in `CLASSES` for token characters.
+const TCHAR: u8 = 1 << 0;
+/// Bit set in `CLASSES` for visible characters.
+const VCHAR: u8 = 1 << 1;
+/// Bit set in `CLASSES` for quoted-string text characters.
+const QDTEXT: u8 = 1 << 2;
+/// Bit set in `CLASSES` for obsolete text characters.
+const OBS_TEXT: u8 = 1 << 3;
+
+/// Character classes of every byte value.
+static CLASSES: [u8; 256] = classes();
+
+/// Build the character class table.
+const fn classes() -> [u8; 256] {
+    const SPECIAL: &[u8] = b"!#$%&'*+-.^_`|~";
+
+    let mut table = [0; 256];
+    let mut b = 0;
+
+    while b < 256 {
+        let c = b as u8;
+        let mut class = 0;
+
+        if c.is_ascii_alphanumeric() {
+            class |= TCHAR;
+        }
+
+        let mut i = 0;
+
+        while i < SPECIAL.len() {
+            if SPECIAL[i] == c {
+                class |= TCHAR;
+            }
+
+            i += 1;
+        }
+
+        if c >= 0x21 && c <= 0x7E {
+            class |= VCHAR;
+        }
+
+        if c >= 0x80 {
+            class |= OBS_TEXT;
+        }
+
+        // qdtext = HTAB / SP / %x21 / %x23-5B / %x5D-7E / obs-text
+        if c == b'\t' || c == b' ' || c == 0x21 || c >= 0x23 && c <= 0x5B ||
+            c >= 0x5D && c <= 0x7E || c >= 0x80
+        {
+            class |= QDTEXT;
+        }
+
+        table[b] = class;
+        b += 1;
+    }
+
+    table
+}
+
+/// Check if the given byte is optional whitespace (SP or HTAB) [RFC7230§3.2.3].
+pub fn is_ows(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+/// Check if the given byte is a token character [RFC7230§3.2.6].
+pub fn is_tchar(b: u8) -> bool { CLASSES[b as usize] & TCHAR != 0 }
+
+/// Check if the given byte is a visible ASCII character [RFC5234§B.1].
+pub fn is_vchar(b: u8) -> bool { CLASSES[b as usize] & VCHAR != 0 }
+
+/// Check if the given byte may appear unescaped in a quoted-string [RFC7230§3.2.6].
+pub fn is_qdtext(b: u8) -> bool { CLASSES[b as usize] & QDTEXT != 0 }
+
+/// Check if the given byte is obsolete non-ASCII text [RFC7230§3.2.6].
+pub fn is_obs_text(b: u8) -> bool { CLASSES[b as usize] & OBS_TEXT != 0 }
+
+/// Check if the given bytes form a nonempty token [RFC7230§3.2.6].
+pub fn is_token(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && bytes.iter().all(|&b| is_tchar(b))
+}
+
+/// Trim leading and trailing optional whitespace from the given bytes.
+pub fn trim_ows(mut bytes: &[u8]) -> &[u8] {
+    while let Some((&b, rest)) = bytes.split_first() {
+        if !is_ows(b) {
+            break;
+        }
+
+        bytes = rest;
+    }
+
+    while let Some((&b, rest)) = bytes.split_last() {
+        if !is_ows(b) {
+            break;
+        }
+
+        bytes = rest;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classes() {
+        for b in 0..=255u8 {
+            assert_eq!(is_tchar(b),
+                b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+            assert_eq!(is_vchar(b), b.is_ascii_graphic());
+            assert_eq!(is_obs_text(b), b >= 0x80);
+            assert_eq!(is_qdtext(b),
+                is_ows(b) || is_vchar(b) && b != b'"' && b != b'\\' || is_obs_text(b));
+        }
+    }
+
+    #[test]
+    fn test_is_token() {
+        assert!(is_token(b"GET"));
+        assert!(is_token(b"x-Custom_1.2~!"));
+        assert!(!is_token(b""));
+        assert!(!is_token(b"a b"));
+        assert!(!is_token(b"a:b"));
+        assert!(!is_token(b"\"a\""));
+        assert!(!is_token(b"\xff"));
+    }
+
+    #[test]
+    fn test_trim_ows() {
+        assert_eq!(trim_ows(b""), b"");
+        assert_eq!(trim_ows(b" \t "), b"");
+        assert_eq!(trim_ows(b"abc"), b"abc");
+        assert_eq!(trim_ows(b" \ta b\t "), b"a b");
+        assert_eq!(trim_ows(b"\r\nabc\r\n"), b"\r\nabc\r\n");
+    }
+}
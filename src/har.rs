@@ -0,0 +1,149 @@
+//! Conversion of parsed requests into HAR [HAR§request] entries for debugging and traffic
+//! recording.
+//!
+//! This module requires the `serde` and `alloc` features.
+
+use percent::decoded;
+use grammar::trim_ows;
+use Head;
+
+/// A name/value pair within a HAR request entry.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Serialize)]
+pub struct HarPair {
+    /// Pair name.
+    pub name: String,
+    /// Pair value.
+    pub value: String,
+}
+
+/// A HAR `request` entry.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    /// Request method.
+    pub method: String,
+    /// Absolute URL of the request.
+    pub url: String,
+    /// HTTP version.
+    pub http_version: String,
+    /// Cookies sent in Cookie headers.
+    pub cookies: Vec<HarPair>,
+    /// Header fields, in wire order, with surrounding whitespace trimmed from values.
+    pub headers: Vec<HarPair>,
+    /// Query parameters, percent-decoded.
+    pub query_string: Vec<HarPair>,
+    /// Length of the head in bytes.
+    pub headers_size: i64,
+    /// Length of the body from Content-Length, or -1 if unknown.
+    pub body_size: i64,
+}
+
+impl HarRequest {
+    /// Convert the given request head into a HAR entry.
+    ///
+    /// Origin-form targets are made absolute using the given scheme and the Host header.
+    /// Invalid UTF-8 is replaced with U+FFFD.
+    pub fn new(head: &Head, scheme: &str) -> Self {
+        let line = head.line;
+        let fields = || head.headers().filter_map(|h| h.ok());
+        let lossy = |b: &[u8]| String::from_utf8_lossy(b).into_owned();
+
+        let url = if line.target.starts_with('/') {
+            let host = fields().find(|h| h.name.eq_ignore_ascii_case("Host"))
+                .map_or(&b""[..], |h| trim_ows(h.val));
+
+            format!("{}://{}{}", scheme, lossy(host), line.target)
+        } else {
+            line.target.to_string()
+        };
+
+        let query = line.target.find('?').map_or("", |idx| &line.target[idx + 1..]);
+
+        let query_string = query.split('&').filter(|p| !p.is_empty()).map(|p| {
+            let (name, value) = match p.find('=') {
+                Some(idx) => (&p[..idx], &p[idx + 1..]),
+                None => (p, ""),
+            };
+
+            HarPair {
+                name: lossy(&decoded(name.as_bytes()).collect::<Vec<u8>>()),
+                value: lossy(&decoded(value.as_bytes()).collect::<Vec<u8>>()),
+            }
+        }).collect();
+
+        let cookies = fields()
+            .filter(|h| h.name.eq_ignore_ascii_case("Cookie"))
+            .flat_map(|h| h.val.split(|&b| b == b';'))
+            .map(trim_ows)
+            .filter(|c| !c.is_empty())
+            .map(|c| match c.iter().position(|&b| b == b'=') {
+                Some(idx) => HarPair { name: lossy(&c[..idx]), value: lossy(&c[idx + 1..]) },
+                None => HarPair { name: lossy(c), value: String::new() },
+            })
+            .collect();
+
+        let headers = fields().map(|h| HarPair {
+            name: h.name.to_string(),
+            value: lossy(trim_ows(h.val)),
+        }).collect();
+
+        let body_size = fields()
+            .find(|h| h.name.eq_ignore_ascii_case("Content-Length"))
+            .and_then(|h| std::str::from_utf8(trim_ows(h.val)).ok()?.parse().ok())
+            .unwrap_or(-1);
+
+        HarRequest {
+            method: line.method.to_string(),
+            url,
+            http_version: line.version.to_string(),
+            cookies,
+            headers,
+            query_string,
+            headers_size: head.stats.head_len as i64,
+            body_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_har_request() {
+        let (head, _) = Head::new(b"POST /a?x=1%202&y HTTP/1.1\r\nHost: example.com\r\n\
+                                    Cookie: a=b; c\r\nContent-Length: 3\r\n\r\nabc").unwrap();
+
+        let har = HarRequest::new(&head, "https");
+
+        assert_eq!(har.url, "https://example.com/a?x=1%202&y");
+        assert_eq!(har.query_string, vec![
+            HarPair { name: "x".into(), value: "1 2".into() },
+            HarPair { name: "y".into(), value: "".into() },
+        ]);
+        assert_eq!(har.cookies, vec![
+            HarPair { name: "a".into(), value: "b".into() },
+            HarPair { name: "c".into(), value: "".into() },
+        ]);
+        assert_eq!(har.headers.len(), 3);
+        assert_eq!(har.headers_size, 84);
+        assert_eq!(har.body_size, 3);
+
+        assert_eq!(serde_json::to_string(&har).unwrap(),
+            "{\"method\":\"POST\",\"url\":\"https://example.com/a?x=1%202&y\",\
+             \"httpVersion\":\"HTTP/1.1\",\
+             \"cookies\":[{\"name\":\"a\",\"value\":\"b\"},{\"name\":\"c\",\"value\":\"\"}],\
+             \"headers\":[{\"name\":\"Host\",\"value\":\"example.com\"},\
+             {\"name\":\"Cookie\",\"value\":\"a=b; c\"},\
+             {\"name\":\"Content-Length\",\"value\":\"3\"}],\
+             \"queryString\":[{\"name\":\"x\",\"value\":\"1 2\"},{\"name\":\"y\",\"value\":\"\"}],\
+             \"headersSize\":84,\"bodySize\":3}");
+
+        let (head, _) = Head::new(b"GET http://a.com/ HTTP/1.1\r\n\r\n").unwrap();
+        let har = HarRequest::new(&head, "https");
+        assert_eq!(har.url, "http://a.com/");
+        assert_eq!(har.body_size, -1);
+        assert!(har.query_string.is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+//! Stable, non-cryptographic hashing.
+
+/// 64-bit FNV-1a hasher [FNV].
+///
+/// Unlike the standard library's default hasher, results are stable across runs,
+/// platforms, and releases.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Fnv(u64);
+
+impl Fnv {
+    /// Create a new `Fnv` hasher in its initial state.
+    pub const fn new() -> Self { Fnv(0xcbf29ce484222325) }
+
+    /// Mix the given byte into the hash.
+    pub fn write_u8(&mut self, b: u8) {
+        self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+    }
+
+    /// Mix the given bytes into the hash.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u8(b);
+        }
+    }
+
+    /// Retrieve the current hash value.
+    pub fn finish(&self) -> u64 { self.0 }
+}
+
+/// Hash the given header name case-insensitively, using 64-bit FNV-1a over its
+/// ASCII-lowercased bytes.
+///
+/// The result is stable across runs, platforms, and releases, so it can be used to build
+/// precomputed routing tables keyed by header name.
+pub fn name_hash64(name: &str) -> u64 {
+    let mut h = Fnv::new();
+
+    for b in name.bytes() {
+        h.write_u8(b.to_ascii_lowercase());
+    }
+
+    h.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fnv() {
+        // Test vectors from the reference implementation.
+        assert_eq!(Fnv::new().finish(), 0xcbf29ce484222325);
+
+        let mut h = Fnv::new();
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63dc4c8601ec8c);
+
+        let mut h = Fnv::new();
+        h.write(b"foobar");
+        assert_eq!(h.finish(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_name_hash64() {
+        assert_eq!(name_hash64("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(name_hash64("FooBar"), 0x85944171f73967e8);
+        assert_eq!(name_hash64("Content-Type"), name_hash64("content-type"));
+        assert_ne!(name_hash64("Content-Type"), name_hash64("Content-Length"));
+    }
+}
@@ -0,0 +1,406 @@
+//! Combined parsing of a full request head.
+
+use writer::offset;
+use {DetailedError, Error, Headers, LineEndings, ParserConfig, RequestLine, Result};
+
+/// Size accounting collected while parsing a request head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct HeadStats {
+    /// Number of header fields in the head.
+    pub headers: usize,
+
+    /// Length of the longest header field line, excluding its terminating CRLF.
+    ///
+    /// With `ObsFold::Merge`, a folded field counts as a single line spanning all its
+    /// continuation lines.
+    pub max_header_len: usize,
+
+    /// Total length of the head, from the first byte of the buffer through the CRLF of
+    /// the terminating empty line.
+    pub head_len: usize,
+
+    /// Length of the request target.
+    pub target_len: usize,
+}
+
+/// A complete request head: the Request-Line followed by all header fields.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Head<'a> {
+    /// Request-Line that begins the head.
+    pub line: RequestLine<'a>,
+
+    /// Size accounting for the head.
+    pub stats: HeadStats,
+
+    /// Header block, beginning directly after the Request-Line CRLF and including the
+    /// terminating empty line.
+    block: &'a [u8],
+
+    /// Configuration the head was parsed with.
+    config: ParserConfig,
+}
+
+impl<'a> Head<'a> {
+    /// Try to parse the given bytes into a Request-Line and header fields, validating
+    /// every field along the way.
+    ///
+    /// On success, return `Ok((head, rest))`, where `head` is the parsed `Head` and
+    /// `rest` is a slice that begins directly after the terminating empty line.
+    pub fn new(buf: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        Head::with_config(buf, ParserConfig::new())
+    }
+
+    /// Try to parse the given bytes into a `Head`, applying the limits in the given
+    /// configuration.
+    ///
+    /// When `max_scan_len` is configured, no bytes past that limit are examined.
+    pub fn with_config(buf: &'a [u8], config: ParserConfig) -> Result<(Self, &'a [u8])> {
+        let scan = match config.max_scan_len.and_then(|max| buf.get(..max)) {
+            Some(scan) if scan.len() < buf.len() => scan,
+            _ => return Head::parse(buf, config),
+        };
+
+        match Head::parse(scan, config) {
+            Ok((head, _)) => {
+                let rest = buf.get(head.stats.head_len..).unwrap_or_default();
+                Ok((head, rest))
+            },
+            Err(Error::Partial) => Err(Error::Budget),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Try to parse the given bytes into a `Head` like `with_config`, locating any error
+    /// within the input.
+    ///
+    /// Locating the error requires parsing the input again, so this is best used to
+    /// diagnose input that has already failed to parse, or where errors are expected to
+    /// be rare.
+    pub fn with_details(buf: &'a [u8], config: ParserConfig)
+        -> std::result::Result<(Self, &'a [u8]), DetailedError>
+    {
+        Head::with_config(buf, config).map_err(|e| DetailedError::locate(buf, config, e))
+    }
+
+    /// Try to parse the given bytes into a `Head`, scanning as far as needed.
+    fn parse(buf: &'a [u8], config: ParserConfig) -> Result<(Self, &'a [u8])> {
+        let (line, block) = RequestLine::with_config(buf, config)?;
+
+        let mut stats = HeadStats {
+            target_len: line.target.len(),
+            ..HeadStats::default()
+        };
+
+        let mut headers = Headers::with_config(block, config);
+
+        loop {
+            let before = headers.buf.len();
+
+            let h = match headers.next() {
+                Some(Ok(h)) => h,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            };
+
+            // The yielded field ends the consumed bytes, which may have been preceded by
+            // lines skipped due to the configuration, and it begins on the line holding
+            // the start of its value, since a merged value spans its continuation lines.
+            let consumed = block.get(block.len() - before..)
+                .and_then(|c| c.get(..c.len().checked_sub(headers.buf.len())?))
+                .unwrap_or_default();
+            let consumed = consumed.strip_suffix(b"\n").unwrap_or(consumed);
+            let consumed = consumed.strip_suffix(b"\r").unwrap_or(consumed);
+            let before = offset(consumed, h.val).and_then(|i| consumed.get(..i))
+                .unwrap_or(consumed);
+            let start = match config.line_endings {
+                LineEndings::Crlf => before.windows(2).rposition(|w| w == b"\r\n")
+                    .map_or(0, |i| i + 2),
+                LineEndings::Lenient => before.iter().rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1),
+            };
+
+            stats.headers += 1;
+            stats.max_header_len = std::cmp::max(stats.max_header_len,
+                consumed.len().saturating_sub(start));
+        }
+
+        let rest = headers.into_inner();
+        let block = block.get(..block.len() - rest.len()).unwrap_or_default();
+
+        stats.head_len = buf.len() - rest.len();
+
+        Ok((Head { line, stats, block, config }, rest))
+    }
+
+    /// Create a new iterator over the header fields in the head, using the same
+    /// configuration the head was parsed with.
+    ///
+    /// Since all fields were validated during parsing, the iterator never yields an
+    /// error.
+    pub fn headers(&self) -> Headers<'a> { Headers::with_config(self.block, self.config) }
+
+    /// Retrieve the raw header block, including the terminating empty line.
+    pub fn header_bytes(&self) -> &'a [u8] { self.block }
+}
+
+/// Stage a request head parser has reached.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Phase {
+    /// Request-Line hasn't been fully received.
+    RequestLine,
+    /// Request-Line has been received, but the header block hasn't been terminated.
+    Headers,
+    /// Complete head has been received.
+    Complete,
+}
+
+/// Snapshot of how far a possibly incomplete request head has been received.
+///
+/// This can be recomputed each time more bytes arrive to enforce header-read timeouts
+/// based on actual parser progress, such as by resetting a deadline only when
+/// `advanced` reports that a complete line arrived, so a client trickling bytes of a
+/// single line can't keep the connection open indefinitely.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Progress {
+    /// Stage reached.
+    pub phase: Phase,
+
+    /// Number of bytes examined, which is the whole buffer if the head is incomplete,
+    /// or the length of the head if it's complete.
+    pub buffered: usize,
+
+    /// Number of bytes making up complete lines, through the CRLF of the last one.
+    pub complete: usize,
+
+    /// Number of complete header fields received.
+    pub headers: usize,
+}
+
+impl Progress {
+    /// Try to determine the progress of the head at the start of the given buffer.
+    pub fn new(buf: &[u8]) -> Result<Self> {
+        Progress::with_config(buf, ParserConfig::new())
+    }
+
+    /// Try to determine the progress of the head at the start of the given buffer,
+    /// applying the limits in the given configuration.
+    ///
+    /// Any error other than `Partial` that parsing the head would produce is returned.
+    pub fn with_config(buf: &[u8], config: ParserConfig) -> Result<Self> {
+        match Head::with_config(buf, config) {
+            Ok((head, _)) => return Ok(Progress {
+                phase: Phase::Complete,
+                buffered: head.stats.head_len,
+                complete: head.stats.head_len,
+                headers: head.stats.headers,
+            }),
+            Err(Error::Partial) => {},
+            Err(e) => return Err(e),
+        }
+
+        let block = match RequestLine::with_config(buf, config) {
+            Ok((_, block)) => block,
+            Err(Error::Partial) => return Ok(Progress {
+                phase: Phase::RequestLine,
+                buffered: buf.len(),
+                complete: 0,
+                headers: 0,
+            }),
+            Err(e) => return Err(e),
+        };
+
+        let mut headers = Headers::with_config(block, config);
+        let mut count = 0;
+
+        while let Some(Ok(_)) = headers.next() {
+            count += 1;
+        }
+
+        Ok(Progress {
+            phase: Phase::Headers,
+            buffered: buf.len(),
+            complete: buf.len() - headers.buf.len(),
+            headers: count,
+        })
+    }
+
+    /// Check if a complete line or more has been received since the given earlier
+    /// progress.
+    pub fn advanced(&self, prev: &Progress) -> bool {
+        self.phase != prev.phase || self.complete > prev.complete
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use {ObsFold, ProxyConnection};
+
+    #[test]
+    fn test_head() {
+        let (head, rest) = Head::new(
+            b"\r\nGET /abc HTTP/1.1\r\nHost: example.com\r\nA: b\r\n\r\nbody"
+        ).unwrap();
+
+        assert_eq!(head.line.method, "GET");
+        assert_eq!(head.line.target, "/abc");
+        assert_eq!(head.stats, HeadStats {
+            headers: 2,
+            max_header_len: 17,
+            head_len: 48,
+            target_len: 4,
+        });
+        assert_eq!(head.header_bytes(), &b"Host: example.com\r\nA: b\r\n\r\n"[..]);
+        assert_eq!(rest, b"body");
+
+        let mut h = head.headers();
+        assert_eq!(h.next().unwrap().unwrap().name, "Host");
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert!(h.next().is_none());
+        assert_eq!(h.into_inner(), b"");
+
+        let (head, rest) = Head::new(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(head.stats.headers, 0);
+        assert_eq!(head.stats.head_len, 18);
+        assert_eq!(rest, b"");
+
+        assert_eq!(Head::new(b"GET / HTTP/1.1\r\nHost: a\r\n"), Err(Error::Partial));
+        assert_eq!(Head::new(b"GET / HTTP/1.1\r\nHost\r\n\r\n"), Err(Error::Syntax));
+
+        let config = ParserConfig { max_value_len: Some(2), ..ParserConfig::new() };
+        assert_eq!(Head::with_config(b"GET / HTTP/1.1\r\nA: bc\r\n\r\n", config),
+            Err(Error::ValueTooLong));
+
+        let config = ParserConfig {
+            proxy_connection: ProxyConnection::Strip,
+            ..ParserConfig::new()
+        };
+        let (head, _) = Head::with_config(
+            b"GET / HTTP/1.1\r\nProxy-Connection: keep-alive\r\nA: b\r\n\r\n", config
+        ).unwrap();
+        assert_eq!(head.stats.headers, 1);
+        assert_eq!(head.stats.max_header_len, 4);
+        assert_eq!(head.headers().count(), 1);
+
+        let config = ParserConfig { allowed_methods: Some(&["GET"]), ..ParserConfig::new() };
+        assert_eq!(Head::with_config(b"PUT / HTTP/1.1\r\n\r\n", config),
+            Err(Error::UnsupportedMethod));
+    }
+
+    #[test]
+    fn test_max_scan_len() {
+        let req = b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody";
+        let config = |max| ParserConfig { max_scan_len: Some(max), ..ParserConfig::new() };
+
+        let (head, rest) = Head::with_config(req, config(27)).unwrap();
+        assert_eq!(head.stats.head_len, 27);
+        assert_eq!(rest, b"body");
+
+        let (_, rest) = Head::with_config(req, config(100)).unwrap();
+        assert_eq!(rest, b"body");
+
+        assert_eq!(Head::with_config(req, config(26)), Err(Error::Budget));
+        assert_eq!(Head::with_config(req, config(0)), Err(Error::Budget));
+        assert_eq!(Head::with_config(&req[..20], config(26)), Err(Error::Partial));
+        assert_eq!(Head::with_config(b"GET / HTTP/1.1\r\nHost\r\nxxxx", config(23)),
+            Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let config = ParserConfig { line_endings: LineEndings::Lenient, ..ParserConfig::new() };
+        let req = b"\nGET / HTTP/1.1\nHost: a\r\nLong: abcdef\n\nbody";
+
+        let (head, rest) = Head::with_config(req, config).unwrap();
+        assert_eq!(head.stats, HeadStats {
+            headers: 2,
+            max_header_len: 12,
+            head_len: 39,
+            target_len: 1,
+        });
+        assert_eq!(head.headers().count(), 2);
+        assert_eq!(rest, b"body");
+
+        assert_eq!(Head::new(req), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_obs_fold_stats() {
+        let config = ParserConfig {
+            obs_fold: ObsFold::Merge,
+            proxy_connection: ProxyConnection::Strip,
+            ..ParserConfig::new()
+        };
+
+        let (head, _) = Head::with_config(
+            b"GET / HTTP/1.1\r\nA: b\r\nProxy-Connection: x\r\nLong: abc\r\n def\r\n\tghi\r\n\
+              C: d\r\n\r\n", config
+        ).unwrap();
+        assert_eq!(head.stats.headers, 3);
+        assert_eq!(head.stats.max_header_len, 21);
+
+        let (head, _) = Head::with_config(b"GET / HTTP/1.1\r\nE:\r\n xy\r\n\r\n", config)
+            .unwrap();
+        assert_eq!(head.stats.max_header_len, 7);
+    }
+
+    #[test]
+    fn test_presets() {
+        let req = b"PATCH / HTTP/1.1\r\nProxy-Connection: close\r\n\r\n";
+
+        assert_eq!(Head::with_config(req, ParserConfig::strict_rfc9112()),
+            Err(Error::Syntax));
+        assert_eq!(Head::with_config(req, ParserConfig::embedded_minimal()),
+            Err(Error::UnsupportedMethod));
+
+        let (head, _) = Head::with_config(req, ParserConfig::lenient_browser()).unwrap();
+        assert_eq!(head.headers().next().unwrap().unwrap().name, "Connection");
+
+        let req = b"GET / HTTP/1.1\r\nProxy-Connection: close\r\n\r\n";
+        let (head, _) = Head::with_config(req, ParserConfig::embedded_minimal()).unwrap();
+        assert_eq!(head.stats.headers, 0);
+    }
+
+    #[test]
+    fn test_progress() {
+        let req = b"GET / HTTP/1.1\r\nHost: a\r\nB: c\r\n\r\nbody";
+
+        assert_eq!(Progress::new(b"").unwrap(), Progress {
+            phase: Phase::RequestLine,
+            buffered: 0,
+            complete: 0,
+            headers: 0,
+        });
+
+        assert_eq!(Progress::new(&req[..10]).unwrap().phase, Phase::RequestLine);
+
+        let p = Progress::new(&req[..20]).unwrap();
+        assert_eq!(p, Progress {
+            phase: Phase::Headers,
+            buffered: 20,
+            complete: 16,
+            headers: 0,
+        });
+
+        let q = Progress::new(&req[..29]).unwrap();
+        assert_eq!(q, Progress {
+            phase: Phase::Headers,
+            buffered: 29,
+            complete: 25,
+            headers: 1,
+        });
+        assert!(q.advanced(&p));
+        assert!(!Progress::new(&req[..22]).unwrap().advanced(&p));
+
+        let r = Progress::new(req).unwrap();
+        assert_eq!(r, Progress {
+            phase: Phase::Complete,
+            buffered: 33,
+            complete: 33,
+            headers: 2,
+        });
+        assert!(r.advanced(&q));
+
+        assert_eq!(Progress::new(b"GET / HTTP/1.1\r\nHost\r\n"), Err(Error::Syntax));
+    }
+}
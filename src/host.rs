@@ -0,0 +1,251 @@
+//! Parsing of request hosts and matching them against allow-lists, for virtual hosting
+//! and rejecting requests with unexpected Host headers.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::host::HostPattern;
+//!
+//! let p = HostPattern::new("*.example.com:8080").unwrap();
+//!
+//! assert!(p.matches("api.Example.com:8080"));
+//! assert!(!p.matches("api.example.com"));
+//! assert!(!p.matches("example.com:8080"));
+//! ```
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use grammar::trim_ows;
+use {Error, Result};
+
+/// Host component of an authority [RFC3986§3.2.2].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Host<'a> {
+    /// Registered name, such as a DNS name, as given.
+    Name(&'a str),
+    /// IPv4 address literal.
+    Ipv4(Ipv4Addr),
+    /// IPv6 address literal, given within brackets.
+    Ipv6(Ipv6Addr),
+}
+
+/// Try to parse the given Host header value [RFC7230§5.4] into its host and optional
+/// port, with surrounding whitespace trimmed.
+///
+/// Registered names may contain only the characters allowed by [RFC3986§3.2.2] and
+/// aren't otherwise validated or percent-decoded. Future IP literal formats and empty
+/// values cause `Error::Syntax`.
+pub fn parse_host(val: &[u8]) -> Result<(Host<'_>, Option<u16>)> {
+    let val = std::str::from_utf8(trim_ows(val)).map_err(|_| Error::Syntax)?;
+    let (host, port) = split(val)?;
+
+    if let Some(lit) = host.strip_prefix('[') {
+        let lit = lit.strip_suffix(']').ok_or(Error::Syntax)?;
+        return lit.parse().map(|ip| (Host::Ipv6(ip), port)).map_err(|_| Error::Syntax);
+    }
+
+    if let Ok(ip) = host.parse() {
+        return Ok((Host::Ipv4(ip), port));
+    }
+
+    let valid = |b: u8| b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=%".contains(&b);
+
+    if host.bytes().all(valid) {
+        Ok((Host::Name(host), port))
+    } else {
+        Err(Error::Syntax)
+    }
+}
+
+/// Split the given authority [RFC3986§3.2], such as a Host header value, into its host
+/// and optional port.
+///
+/// IP literals keep their surrounding brackets. An empty port, as in `example.com:`, is
+/// treated as absent [RFC3986§3.2.3].
+pub fn split(authority: &str) -> Result<(&str, Option<u16>)> {
+    let (host, port) = if authority.starts_with('[') {
+        let end = authority.find(']').ok_or(Error::Syntax)? + 1;
+        let (host, rest) = authority.split_at(end);
+
+        match rest.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if rest.is_empty() => (host, None),
+            None => return Err(Error::Syntax),
+        }
+    } else {
+        match authority.rfind(':') {
+            Some(idx) => (&authority[..idx], Some(&authority[idx + 1..])),
+            None => (authority, None),
+        }
+    };
+
+    if host.is_empty() || host.contains(['@', '/', ' ']) ||
+        !host.starts_with('[') && host.contains(['[', ']', ':'])
+    {
+        return Err(Error::Syntax);
+    }
+
+    match port {
+        Some("") | None => Ok((host, None)),
+        Some(p) if p.len() <= 5 && p.bytes().all(|b| b.is_ascii_digit()) =>
+            p.parse().map(|p| (host, Some(p))).map_err(|_| Error::Syntax),
+        Some(_) => Err(Error::Syntax),
+    }
+}
+
+/// A pattern matching a set of hosts.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct HostPattern<'a> {
+    /// Host to match, without any leading `*.`.
+    host: &'a str,
+    /// Whether only subdomains of the host are matched.
+    wildcard: bool,
+    /// Port the authority must have, if constrained.
+    port: Option<u16>,
+}
+
+impl<'a> HostPattern<'a> {
+    /// Try to parse the given pattern.
+    ///
+    /// The pattern is either an exact host, such as `example.com`, or a leading wildcard,
+    /// such as `*.example.com`, which matches any subdomain at any depth but not the
+    /// domain itself. Either may be followed by a port, in which case only authorities
+    /// with that exact port match, and otherwise authorities with any or no port match.
+    pub fn new(pattern: &'a str) -> Result<Self> {
+        let (host, port) = split(pattern)?;
+
+        let (host, wildcard) = match host.strip_prefix("*.") {
+            Some(rest) => (rest, true),
+            None => (host, false),
+        };
+
+        if host.is_empty() || host.contains('*') {
+            return Err(Error::Syntax);
+        }
+
+        Ok(HostPattern { host, wildcard, port })
+    }
+
+    /// Check if the given authority, such as a Host header value, matches the pattern.
+    ///
+    /// Hosts are compared case-insensitively, ignoring any trailing dot on the
+    /// authority's host. Malformed authorities never match.
+    pub fn matches(&self, authority: &str) -> bool {
+        let (host, port) = match split(authority) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+
+        if self.port.is_some() && self.port != port {
+            return false;
+        }
+
+        let host = host.strip_suffix('.').unwrap_or(host);
+
+        if !self.wildcard {
+            return host.eq_ignore_ascii_case(self.host);
+        }
+
+        // Require at least one label followed by a dot before the pattern host. Compare
+        // bytes so a multibyte character can't straddle the split.
+        let host = host.as_bytes();
+
+        host.len() > self.host.len() + 1 && {
+            let (sub, base) = host.split_at(host.len() - self.host.len());
+            sub.ends_with(b".") && sub.len() > 1 &&
+                base.eq_ignore_ascii_case(self.host.as_bytes())
+        }
+    }
+}
+
+/// Check if the given authority matches any of the given patterns.
+pub fn allowed(patterns: &[HostPattern], authority: &str) -> bool {
+    patterns.iter().any(|p| p.matches(authority))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("example.com"), Ok(("example.com", None)));
+        assert_eq!(split("example.com:80"), Ok(("example.com", Some(80))));
+        assert_eq!(split("example.com:"), Ok(("example.com", None)));
+        assert_eq!(split("[::1]:8080"), Ok(("[::1]", Some(8080))));
+        assert_eq!(split("[::1]"), Ok(("[::1]", None)));
+        assert_eq!(split("a:65536"), Err(Error::Syntax));
+        assert_eq!(split("a:b"), Err(Error::Syntax));
+        assert_eq!(split("a:1:2"), Err(Error::Syntax));
+        assert_eq!(split("[::1]x"), Err(Error::Syntax));
+        assert_eq!(split("[::1"), Err(Error::Syntax));
+        assert_eq!(split("user@a"), Err(Error::Syntax));
+        assert_eq!(split(":80"), Err(Error::Syntax));
+        assert_eq!(split(""), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_parse_host() {
+        assert_eq!(parse_host(b" Example.com:8080\t"),
+            Ok((Host::Name("Example.com"), Some(8080))));
+        assert_eq!(parse_host(b"example.com"), Ok((Host::Name("example.com"), None)));
+        assert_eq!(parse_host(b"127.0.0.1:80"),
+            Ok((Host::Ipv4(Ipv4Addr::new(127, 0, 0, 1)), Some(80))));
+        assert_eq!(parse_host(b"[::1]:443"), Ok((Host::Ipv6(Ipv6Addr::LOCALHOST), Some(443))));
+        assert_eq!(parse_host(b"[::1]"), Ok((Host::Ipv6(Ipv6Addr::LOCALHOST), None)));
+        assert_eq!(parse_host(b"1.2.3.256"), Ok((Host::Name("1.2.3.256"), None)));
+        assert_eq!(parse_host(b"a%2eb"), Ok((Host::Name("a%2eb"), None)));
+        assert_eq!(parse_host(b"[v1.x]"), Err(Error::Syntax));
+        assert_eq!(parse_host(b"[1.2.3.4]"), Err(Error::Syntax));
+        assert_eq!(parse_host(b"a\"b"), Err(Error::Syntax));
+        assert_eq!(parse_host(b"a:99999"), Err(Error::Syntax));
+        assert_eq!(parse_host(b"\xff"), Err(Error::Syntax));
+        assert_eq!(parse_host(b" "), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_host_pattern() {
+        let p = HostPattern::new("Example.com").unwrap();
+        assert!(p.matches("example.COM"));
+        assert!(p.matches("example.com:1234"));
+        assert!(p.matches("example.com."));
+        assert!(!p.matches("www.example.com"));
+        assert!(!p.matches("example.com.evil"));
+        assert!(!p.matches("example.com@evil"));
+
+        let p = HostPattern::new("*.example.com").unwrap();
+        assert!(p.matches("a.example.com"));
+        assert!(p.matches("a.b.example.com:443"));
+        assert!(!p.matches("example.com"));
+        assert!(!p.matches(".example.com"));
+        assert!(!p.matches("aexample.com"));
+
+        let p = HostPattern::new("*.ab").unwrap();
+        assert!(!p.matches("aéb"));
+        assert!(!p.matches("éab"));
+        assert!(p.matches("é.ab"));
+
+        let p = HostPattern::new("[::1]:8080").unwrap();
+        assert!(p.matches("[::1]:8080"));
+        assert!(!p.matches("[::1]"));
+        assert!(!p.matches("[::1]:80"));
+
+        assert_eq!(HostPattern::new("*"), Err(Error::Syntax));
+        assert_eq!(HostPattern::new("*."), Err(Error::Syntax));
+        assert_eq!(HostPattern::new("a.*.com"), Err(Error::Syntax));
+        assert_eq!(HostPattern::new("*.a:x"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_allowed() {
+        let list = [
+            HostPattern::new("example.com").unwrap(),
+            HostPattern::new("*.example.org:8443").unwrap(),
+        ];
+
+        assert!(allowed(&list, "example.com"));
+        assert!(allowed(&list, "x.example.org:8443"));
+        assert!(!allowed(&list, "x.example.org"));
+        assert!(!allowed(&[], "example.com"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Support for ICAP [RFC3507], which frames requests the same way as HTTP and so can
+//! reuse the rest of this crate.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::{Head, ParserConfig};
+//! use uhttp_request::icap;
+//!
+//! let (head, _) = Head::with_config(
+//!     b"REQMOD icap://proxy/f ICAP/1.0\r\nEncapsulated: req-hdr=0, null-body=70\r\n\r\n",
+//!     ParserConfig::icap(),
+//! ).unwrap();
+//!
+//! assert!(icap::is_icap(&head.line));
+//!
+//! let val = head.headers().next().unwrap().unwrap().val;
+//! let mut e = icap::encapsulated(val);
+//! assert_eq!(e.next(), Some(Ok(("req-hdr", 0))));
+//! assert_eq!(e.next(), Some(Ok(("null-body", 70))));
+//! assert_eq!(e.next(), None);
+//! ```
+
+use list::{self, ListValues};
+use {Error, RequestLine, Result};
+
+/// Protocol version of ICAP requests.
+pub const VERSION: &str = "ICAP/1.0";
+
+/// Methods defined by ICAP [RFC3507§4.3.2].
+pub const METHODS: &[&str] = &["REQMOD", "RESPMOD", "OPTIONS"];
+
+/// Check if the given Request-Line begins an ICAP request, by its protocol version.
+pub fn is_icap(line: &RequestLine) -> bool { line.version == VERSION }
+
+/// Create an iterator over the `(section, offset)` entries in the given Encapsulated
+/// header value [RFC3507§4.4.1], such as `("req-hdr", 0)`.
+///
+/// Empty list elements are skipped [RFC7230§7]. Each section name must be one of those
+/// defined by ICAP, and offsets must be decimal numbers, but their order isn't checked.
+pub fn encapsulated<'a>(val: &'a [u8]) -> Encapsulated<'a> {
+    Encapsulated(list::values(val))
+}
+
+/// Iterator over the entries in an Encapsulated header value.
+#[derive(Clone, Debug)]
+pub struct Encapsulated<'a>(ListValues<'a>);
+
+impl<'a> Iterator for Encapsulated<'a> {
+    type Item = Result<(&'a str, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_entry))
+    }
+}
+
+/// Parse the given `section=offset` entry.
+fn parse_entry(entry: &[u8]) -> Result<(&'static str, usize)> {
+    const SECTIONS: &[&str] = &[
+        "req-hdr", "res-hdr", "req-body", "res-body", "null-body", "opt-body",
+    ];
+
+    let (name, off) = match entry.iter().position(|&b| b == b'=')
+        .and_then(|i| entry.split_at_checked(i))
+    {
+        Some((name, off)) => (name, off.get(1..).unwrap_or_default()),
+        None => return Err(Error::Syntax),
+    };
+
+    match SECTIONS.iter().find(|s| s.as_bytes() == name) {
+        Some(name) if !off.is_empty() && off.iter().all(u8::is_ascii_digit) => {
+            off.iter().try_fold(0usize, |n, &b| {
+                n.checked_mul(10)?.checked_add((b - b'0') as usize)
+            }).map(|n| (*name, n)).ok_or(Error::Syntax)
+        },
+        _ => Err(Error::Syntax),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use {Head, ParserConfig};
+
+    #[test]
+    fn test_icap() {
+        let (head, _) = Head::with_config(
+            b"RESPMOD icap://a/b ICAP/1.0\r\nHost: a\r\n\r\n",
+            ParserConfig::icap(),
+        ).unwrap();
+        assert!(is_icap(&head.line));
+
+        let (head, _) = Head::new(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(!is_icap(&head.line));
+
+        assert_eq!(Head::with_config(b"GET / ICAP/1.0\r\n\r\n", ParserConfig::icap()),
+            Err(Error::UnsupportedMethod));
+    }
+
+    #[test]
+    fn test_encapsulated() {
+        let mut e = encapsulated(b"req-hdr=0,res-hdr=137 , res-body=296");
+        assert_eq!(e.next(), Some(Ok(("req-hdr", 0))));
+        assert_eq!(e.next(), Some(Ok(("res-hdr", 137))));
+        assert_eq!(e.next(), Some(Ok(("res-body", 296))));
+        assert_eq!(e.next(), None);
+
+        let mut e = encapsulated(
+            b"foo=1, req-hdr=, req-hdr=x, req-hdr, opt-body=99999999999999999999"
+        );
+        assert_eq!(e.next(), Some(Err(Error::Syntax)));
+        assert_eq!(e.next(), Some(Err(Error::Syntax)));
+        assert_eq!(e.next(), Some(Err(Error::Syntax)));
+        assert_eq!(e.next(), Some(Err(Error::Syntax)));
+        assert_eq!(e.next(), Some(Err(Error::Syntax)));
+        assert_eq!(e.next(), None);
+
+        let mut e = encapsulated(b" , req-hdr=0,, null-body=5 ,");
+        assert_eq!(e.next(), Some(Ok(("req-hdr", 0))));
+        assert_eq!(e.next(), Some(Ok(("null-body", 5))));
+        assert_eq!(e.next(), None);
+        assert_eq!(encapsulated(b"").next(), None);
+    }
+}
@@ -0,0 +1,105 @@
+//! Parsing of the Idempotency-Key header [IDEMPOTENCY-KEY§2], for servers that make
+//! retried POST and PATCH requests safe by remembering the key of each one handled.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::idempotency::parse_idempotency_key;
+//!
+//! let key = "8e03978e-40d5-43e8-bc93-6894a57f9324";
+//!
+//! assert_eq!(parse_idempotency_key(b"\"8e03978e-40d5-43e8-bc93-6894a57f9324\"", 64),
+//!     Ok(key));
+//! assert_eq!(parse_idempotency_key(b"8e03978e-40d5-43e8-bc93-6894a57f9324", 64),
+//!     Ok(key));
+//! assert!(parse_idempotency_key(b"\"\"", 64).is_err());
+//! ```
+
+use grammar::{is_token, trim_ows};
+use {Error, Result};
+
+/// Default maximum length of a key, which comfortably fits UUIDs and other generated
+/// keys while bounding what a server has to store.
+pub const DEFAULT_MAX_LEN: usize = 255;
+
+/// Try to parse the given Idempotency-Key header value, with surrounding whitespace
+/// trimmed, into the key.
+///
+/// The key is specified as a Structured Field string [RFC8941§3.3.3], which is returned
+/// without its quotes and with any backslash escapes intact, so keys can be compared
+/// byte for byte. A bare token, as sent by some clients, is also accepted as an opaque
+/// key. An empty key or other malformed value causes `Error::Syntax`, and a key longer
+/// than the given maximum causes `Error::ValueTooLong`.
+pub fn parse_idempotency_key(val: &[u8], max_len: usize) -> Result<&str> {
+    let val = trim_ows(val);
+
+    let key = match val.strip_prefix(b"\"") {
+        Some(rest) => {
+            let key = rest.strip_suffix(b"\"").ok_or(Error::Syntax)?;
+
+            if !is_sf_string(key) {
+                return Err(Error::Syntax);
+            }
+
+            key
+        },
+        None if is_token(val) => val,
+        None => return Err(Error::Syntax),
+    };
+
+    if key.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    if key.len() > max_len {
+        return Err(Error::ValueTooLong);
+    }
+
+    // Both forms allow only ASCII, so this can't fail.
+    std::str::from_utf8(key).map_err(|_| Error::Syntax)
+}
+
+/// Check if the given bytes are valid contents of a Structured Field string, where only
+/// `"` and `\` may be escaped.
+fn is_sf_string(bytes: &[u8]) -> bool {
+    let mut iter = bytes.iter();
+
+    while let Some(&b) = iter.next() {
+        match b {
+            b'\\' => match iter.next() {
+                Some(b'"') | Some(b'\\') => {},
+                _ => return false,
+            },
+            b'"' => return false,
+            0x20..=0x7E => {},
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_idempotency_key() {
+        assert_eq!(parse_idempotency_key(b" \"abc 123\" ", 8), Ok("abc 123"));
+        assert_eq!(parse_idempotency_key(b"\"a\\\"b\\\\\"", 8), Ok("a\\\"b\\\\"));
+        assert_eq!(parse_idempotency_key(b"abc-123", 8), Ok("abc-123"));
+        assert_eq!(parse_idempotency_key(b"\"12345678\"", 8), Ok("12345678"));
+
+        assert_eq!(parse_idempotency_key(b"\"123456789\"", 8), Err(Error::ValueTooLong));
+        assert_eq!(parse_idempotency_key(b"123456789", 8), Err(Error::ValueTooLong));
+        assert_eq!(parse_idempotency_key(b"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"abc", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"a\"b\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"a\\b\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"a\\\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"\"\xc3\xa9\"", 8), Err(Error::Syntax));
+        assert_eq!(parse_idempotency_key(b"a b", 8), Err(Error::Syntax));
+    }
+}
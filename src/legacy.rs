@@ -0,0 +1,112 @@
+//! Helpers for header fields used by legacy integrations.
+
+use grammar::trim_ows;
+use {Error, Headers, Method, Result};
+
+/// Header names used to tunnel a method through POST, in order of precedence.
+const OVERRIDE_NAMES: &[&str] = &[
+    "X-HTTP-Method-Override",
+    "X-HTTP-Method",
+    "X-Method-Override",
+];
+
+/// Find and parse the method override requested by the given headers, returning `None`
+/// if no override is present.
+///
+/// The X-HTTP-Method-Override, X-HTTP-Method, and X-Method-Override headers are checked
+/// in that order. The value must name one of the standard methods, since tunneling
+/// arbitrary extension methods past intermediaries is rarely intended. Whether the
+/// override should be honored at all (typically only for POST) is up to the caller.
+pub fn method_override<'a>(headers: Headers<'a>) -> Result<Option<Method<'a>>> {
+    let mut best: Option<(usize, &[u8])> = None;
+
+    for h in headers {
+        let h = h?;
+
+        let rank = match OVERRIDE_NAMES.iter().position(|n| n.eq_ignore_ascii_case(h.name)) {
+            Some(rank) => rank,
+            None => continue,
+        };
+
+        if best.is_none_or(|(r, _)| rank < r) {
+            best = Some((rank, h.val));
+        }
+    }
+
+    let val = match best {
+        Some((_, val)) => trim_ows(val),
+        None => return Ok(None),
+    };
+
+    let val = std::str::from_utf8(val).map_err(|_| Error::Syntax)?;
+
+    match Method::parse(val)? {
+        Method::Extension(_) => Err(Error::Syntax),
+        m => Ok(Some(m)),
+    }
+}
+
+/// Extract the URI from the given quoted SOAPAction header value, returning `None` if
+/// the value is empty [SOAP11§6.1.1].
+///
+/// The URI is returned as-is, including an empty string for `""`, and isn't further
+/// validated.
+pub fn soap_action(val: &[u8]) -> Result<Option<&str>> {
+    let val = trim_ows(val);
+
+    if val.is_empty() {
+        return Ok(None);
+    }
+
+    if val.len() < 2 || val[0] != b'"' || val[val.len() - 1] != b'"' {
+        return Err(Error::Syntax);
+    }
+
+    let uri = &val[1..val.len() - 1];
+
+    if uri.contains(&b'"') {
+        return Err(Error::Syntax);
+    }
+
+    std::str::from_utf8(uri).map(Some).map_err(|_| Error::Syntax)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_method_override() {
+        let h = Headers::new(b"Host: a\r\nx-http-method-override: DELETE\r\n\r\n");
+        assert_eq!(method_override(h), Ok(Some(Method::Delete)));
+
+        let h = Headers::new(b"X-Method-Override: PUT\r\nX-HTTP-Method: PATCH\r\n\r\n");
+        assert_eq!(method_override(h), Ok(Some(Method::Patch)));
+
+        let h = Headers::new(b"X-HTTP-Method: PUT\r\nX-HTTP-Method-Override:  GET \r\n\r\n");
+        assert_eq!(method_override(h), Ok(Some(Method::Get)));
+
+        let h = Headers::new(b"Host: a\r\n\r\n");
+        assert_eq!(method_override(h), Ok(None));
+
+        let h = Headers::new(b"X-HTTP-Method-Override: delete\r\n\r\n");
+        assert_eq!(method_override(h), Err(Error::Syntax));
+
+        let h = Headers::new(b"X-HTTP-Method-Override: \r\n\r\n");
+        assert_eq!(method_override(h), Err(Error::Syntax));
+
+        let h = Headers::new(b"X-HTTP-Method-Override: PUT\r\n");
+        assert_eq!(method_override(h), Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_soap_action() {
+        assert_eq!(soap_action(b" \"http://example.com/GetQuote\""),
+            Ok(Some("http://example.com/GetQuote")));
+        assert_eq!(soap_action(b"\"\""), Ok(Some("")));
+        assert_eq!(soap_action(b""), Ok(None));
+        assert_eq!(soap_action(b"urn:x"), Err(Error::Syntax));
+        assert_eq!(soap_action(b"\""), Err(Error::Syntax));
+        assert_eq!(soap_action(b"\"a\"b\""), Err(Error::Syntax));
+    }
+}
@@ -33,13 +33,109 @@
 //! assert_eq!(rest, b"body");
 //! ```
 
-#![feature(field_init_shorthand)]
-#![feature(conservative_impl_trait)]
-
+#[cfg(test)]
+extern crate httparse;
 extern crate memchr;
 
+#[cfg(all(feature = "serde", feature = "alloc"))]
+#[macro_use]
+extern crate serde;
+
+#[cfg(test)]
+extern crate serde_json;
+
+#[cfg(feature = "url")]
+extern crate url;
+
 use memchr::memchr;
 
+use body::Body;
+use grammar::{is_ows, is_token, is_vchar};
+use path::PathSegments;
+use query::{Query, QueryParams};
+use writer::Cursor;
+
+pub mod accept;
+pub mod auth;
+pub mod body;
+pub mod cache;
+pub mod chunked;
+pub mod conditional;
+mod config;
+pub mod content;
+pub mod cookie;
+pub mod cors;
+pub mod date;
+mod detail;
+#[cfg(test)]
+mod differential;
+pub mod etag;
+pub mod from;
+pub mod grammar;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub mod har;
+mod hash;
+mod head;
+pub mod host;
+pub mod icap;
+pub mod idempotency;
+pub mod legacy;
+pub mod lifecycle;
+pub mod list;
+pub mod location;
+pub mod log;
+mod lower;
+#[cfg(feature = "alloc")]
+mod map;
+mod method;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod multipart;
+mod name;
+#[cfg(test)]
+mod no_panic;
+pub mod params;
+pub mod path;
+pub mod percent;
+pub mod policy;
+pub mod privacy;
+pub mod proxy;
+pub mod query;
+pub mod range;
+#[cfg(feature = "alloc")]
+mod reqbuf;
+mod request;
+pub mod response;
+pub mod sigv4;
+pub mod sniff;
+mod status;
+#[cfg(feature = "alloc")]
+mod store;
+pub mod suspicion;
+pub mod transform;
+pub mod upgrade;
+mod version;
+pub mod visit;
+pub mod websocket;
+pub mod writer;
+
+pub use config::{LineEndings, ObsFold, ParserConfig, ProxyConnection, WhitespaceLines};
+pub use detail::{Component, DetailedError};
+pub use hash::name_hash64;
+pub use head::{Head, HeadStats, Phase, Progress};
+pub use lower::{LowerHeader, LowerName, Lowercase, MAX_LOWER_NAME};
+#[cfg(feature = "alloc")]
+pub use map::{DuplicatePolicy, Duplicates, HeaderMap};
+pub use method::Method;
+pub use name::HeaderName;
+#[cfg(feature = "alloc")]
+pub use reqbuf::RequestBuf;
+pub use request::Request;
+pub use status::StatusLine;
+#[cfg(feature = "alloc")]
+pub use store::HeadStore;
+pub use version::Version;
+
 /// Errors that may occur when processing request header.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Error {
@@ -47,6 +143,22 @@ pub enum Error {
     Partial,
     /// Malformed syntax.
     Syntax,
+    /// Output buffer too small.
+    Capacity,
+    /// Header value longer than the configured maximum.
+    ValueTooLong,
+    /// Request method longer than the configured maximum or not in the configured
+    /// allow-list, suited to a 501 Not Implemented response [RFC7231§6.6.2].
+    UnsupportedMethod,
+    /// Request head not complete within the configured maximum number of scanned bytes.
+    Budget,
+    /// Body ended before its advertised length or its terminating chunk.
+    BodyTruncated,
+    /// Body continued past its advertised length or its terminating chunk.
+    BodyTooLong,
+    /// Header value continued onto another line with obsolete line folding
+    /// [RFC7230§3.2.4], suited to a 400 Bad Request response.
+    ObsFold,
 }
 
 /// Specialized result using custom `Error`.
@@ -80,11 +192,95 @@ impl<'a> RequestLine<'a> {
     /// On success, return `Ok((rl, rest))`, where `rl` is the `RequestLine` and `rest` is
     /// a slice that begins directly after the Request-Line terminating CRLF.
     pub fn new(buf: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        match fast_get(buf) {
+            Some(parsed) => Ok(parsed),
+            None => RequestLine::parse(buf, LineEndings::Crlf),
+        }
+    }
+
+    /// Try to parse the given bytes into `RequestLine` components, validating each
+    /// component against the grammar [RFC7230§3.1.1].
+    ///
+    /// Unlike `new`, this requires the method to be a token, the target to be non-empty
+    /// and consist only of visible ASCII characters, and the version to have the form
+    /// `HTTP/DIGIT.DIGIT`, returning `Error::Syntax` otherwise.
+    pub fn new_strict(buf: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let (line, rest) = RequestLine::new(buf)?;
+
+        if !is_token(line.method.as_bytes()) || line.target.is_empty() ||
+           !line.target.bytes().all(is_vchar)
+        {
+            return Err(Error::Syntax);
+        }
+
+        Version::parse(line.version)?;
+
+        Ok((line, rest))
+    }
+
+    /// Try to parse the given bytes into `RequestLine` components, applying the method
+    /// restrictions in the given configuration.
+    pub fn with_config(buf: &'a [u8], config: ParserConfig) -> Result<(Self, &'a [u8])> {
+        let res = match config.line_endings {
+            LineEndings::Crlf => RequestLine::new(buf),
+            LineEndings::Lenient => match fast_get(buf) {
+                Some(parsed) => Ok(parsed),
+                None => RequestLine::parse(buf, LineEndings::Lenient),
+            },
+        };
+
+        let method = match res {
+            Ok((ref line, _)) => line.method.as_bytes(),
+            // Reject an oversized method before the rest of the line arrives.
+            Err(Error::Partial) => match config.line_endings.skip_empty_lines(buf) {
+                Ok(start) => start.split(|&b| b == b' ').next().unwrap_or(start),
+                Err(_) => return res,
+            },
+            Err(_) => return res,
+        };
+
+        if config.max_method_len.is_some_and(|max| method.len() > max) {
+            return Err(Error::UnsupportedMethod);
+        }
+
+        if let (Ok((ref line, _)), Some(allowed)) = (&res, config.allowed_methods) {
+            if !allowed.contains(&line.method) {
+                return Err(Error::UnsupportedMethod);
+            }
+        }
+
+        res
+    }
+
+    /// Try to parse the raw method into a typed `Method`.
+    ///
+    /// The raw slice remains available in the `method` field.
+    pub fn method(&self) -> Result<Method<'a>> { Method::parse(self.method) }
+
+    /// Try to parse the raw version into a typed `Version`.
+    pub fn version_parsed(&self) -> Result<Version> { Version::parse(self.version) }
+
+    /// Create an iterator over the query parameters in the target.
+    pub fn query_params(&self) -> QueryParams<'a> {
+        QueryParams::new(self.target.as_bytes())
+    }
+
+    /// Create a `Query` for looking up the query parameters in the target by name.
+    pub fn query(&self) -> Query<'a> { Query::new(self.target.as_bytes()) }
+
+    /// Create an iterator over the segments of the path in the target.
+    pub fn path_segments(&self) -> PathSegments<'a> {
+        PathSegments::new(self.target.as_bytes())
+    }
+
+    /// Try to parse the given bytes into `RequestLine` components, handling every form
+    /// accepted by `new` and recognizing the given line terminators.
+    fn parse(buf: &'a [u8], endings: LineEndings) -> Result<(Self, &'a [u8])> {
         // Ignore leading empty lines [RFC7230§3.5].
-        let start = skip_empty_lines(buf)?;
+        let start = endings.skip_empty_lines(buf)?;
 
         // Retrieve contents of initial line and split by spaces.
-        let (line, rest) = next_line(start)?;
+        let (line, rest) = endings.next_line(start)?;
         let line = std::str::from_utf8(line).map_err(|_| Error::Syntax)?;
 
         let mut chunks = line.split(' ');
@@ -106,51 +302,295 @@ pub struct Header<'a> {
     /// Header name, with surrounding whitespace trimmed.
     ///
     /// This is not guaranteed to be free of internal whitespace or otherwise
-    /// syntactically correct.
+    /// syntactically correct. Names are case-insensitive, so compare them with
+    /// `eq_ignore_ascii_case` or through `header_name`.
     pub name: &'a str,
 
     /// Raw header value.
+    ///
+    /// If `ObsFold::Merge` is configured, this may span several lines.
     pub val: &'a [u8],
 }
 
+impl<'a> Header<'a> {
+    /// Retrieve the name wrapped in a `HeaderName`, which compares and hashes
+    /// case-insensitively.
+    pub const fn header_name(&self) -> HeaderName<'a> { HeaderName::new(self.name) }
+
+    /// Try to copy the value into the given buffer with each obsolete line fold replaced
+    /// by a single space [RFC7230§3.2.4].
+    ///
+    /// On success, return the number of bytes written.
+    pub fn unfold(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut out = Cursor::new(buf);
+        let mut rest = self.val;
+
+        let split = |b: &'a [u8]| memchr(b'\n', b).and_then(|i| b.split_at_checked(i));
+
+        while let Some((line, next)) = split(rest) {
+            out.push(line.strip_suffix(b"\r").unwrap_or(line))?;
+            out.push(b" ")?;
+
+            let next = next.get(1..).unwrap_or_default();
+            let start = next.iter().position(|&b| !is_ows(b)).unwrap_or(next.len());
+
+            rest = next.get(start..).unwrap_or_default();
+        }
+
+        out.push(rest)?;
+
+        Ok(out.pos())
+    }
+}
+
 /// Iterator over all header fields in a request.
-pub struct Headers<'a>(&'a [u8]);
+pub struct Headers<'a> {
+    buf: &'a [u8],
+    config: ParserConfig,
+    /// Whether the terminating empty line has been consumed.
+    terminated: bool,
+}
 
 impl<'a> Headers<'a> {
     /// Create a new `Headers` iterator over the given bytes, which must begin directly
-    /// after the Request-Line CRLF.
-    pub fn new(s: &'a [u8]) -> Self {
-        Headers(s)
+    /// after the Request-Line or Status-Line CRLF.
+    pub const fn new(s: &'a [u8]) -> Self {
+        Headers::with_config(s, ParserConfig::new())
+    }
+
+    /// Create a new `Headers` iterator over the given bytes, applying the limits in the
+    /// given configuration to each header.
+    pub const fn with_config(s: &'a [u8], config: ParserConfig) -> Self {
+        Headers { buf: s, config, terminated: false }
+    }
+
+    /// Try to collect the remaining header fields into a `HeaderMap` for random access.
+    ///
+    /// This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn to_map(self) -> Result<HeaderMap<'a>> { HeaderMap::new(self) }
+
+    /// Try to collect the remaining header fields into a `HeaderMap`, applying the given
+    /// duplicate policy.
+    ///
+    /// This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn to_map_with(self, policy: DuplicatePolicy) -> Result<HeaderMap<'a>> {
+        HeaderMap::with_policy(self, policy)
     }
 
     /// Retrieve the remaining bytes that haven't been processed.
     ///
     /// If called after the last yielded header, this slice will contain the beginning of
     /// the request body.
-    pub fn into_inner(self) -> &'a [u8] { self.0 }
+    pub fn into_inner(self) -> &'a [u8] { self.buf }
+
+    /// Retrieve the remaining bytes that haven't been processed as a `Body`, which
+    /// records whether the terminating empty line was reached.
+    ///
+    /// Unlike `into_inner`, this makes it explicit when the remaining bytes are leftover
+    /// header lines rather than the request body.
+    pub fn into_body(self) -> Body<'a> { Body::new(self.buf, self.terminated) }
+
+    /// Check the given possibly incomplete header line against the configured limits.
+    fn check_limits(&self, line: &[u8]) -> Result<()> {
+        let max = match self.config.max_value_len {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+
+        match memchr(b':', line) {
+            Some(idx) if line.len().saturating_sub(idx + 1) > max =>
+                Err(Error::ValueTooLong),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check if any of the remaining header fields has the given name, compared
+    /// case-insensitively.
+    ///
+    /// This only examines the name before each colon and doesn't validate lines, so it's
+    /// cheaper than searching the iterator when the value isn't needed. The configured
+    /// Proxy-Connection handling is respected, but other configured limits aren't
+    /// checked.
+    pub fn contains(&self, name: &str) -> bool {
+        let mut rest = self.buf;
+
+        let lenient = self.config.line_endings == LineEndings::Lenient;
+
+        // Headers are terminated by an empty line [RFC7230§3].
+        while !rest.is_empty() && !rest.starts_with(b"\r\n") &&
+              (!lenient || !rest.starts_with(b"\n"))
+        {
+            let split = memchr(b'\n', rest).and_then(|i| rest.split_at_checked(i));
+
+            let (line, next) = match split {
+                Some((line, next)) => (line, next.get(1..).unwrap_or_default()),
+                None => (rest, &b""[..]),
+            };
+
+            // Continuation lines of folded values don't begin a field.
+            let folded = self.config.obs_fold != ObsFold::Split &&
+                line.first().is_some_and(|&b| is_ows(b));
+
+            let field = memchr(b':', line).and_then(|i| line.get(..i)).filter(|_| !folded);
+
+            if let Some(field) = field {
+                let field = grammar::trim_ows(field);
+
+                let field = if field.eq_ignore_ascii_case(b"Proxy-Connection") {
+                    match self.config.proxy_connection {
+                        ProxyConnection::Keep => field,
+                        ProxyConnection::Connection => b"Connection",
+                        ProxyConnection::Strip | ProxyConnection::Reject => b"",
+                    }
+                } else {
+                    field
+                };
+
+                if field.eq_ignore_ascii_case(name.as_bytes()) {
+                    return true;
+                }
+            }
+
+            rest = next;
+        }
+
+        false
+    }
+
+    /// Create an iterator over only the header fields whose name begins with the given
+    /// prefix, compared case-insensitively.
+    ///
+    /// Any errors encountered are passed through.
+    pub fn with_prefix<'p>(self, prefix: &'p str) -> WithPrefix<'a, 'p> {
+        WithPrefix {
+            headers: self,
+            prefix,
+        }
+    }
+
+    /// Create an iterator over the remaining header fields with their names lowercased
+    /// into an inline buffer, so they can be matched exactly against lowercase tables.
+    ///
+    /// A name longer than `MAX_LOWER_NAME` bytes yields `Error::Capacity` for that
+    /// field, and iteration can continue with the next one.
+    pub fn lowercase(self) -> Lowercase<'a> { Lowercase(self) }
+
+    /// Create an iterator over the remaining header fields as `(name, value)` string
+    /// pairs, with surrounding whitespace trimmed from each value.
+    ///
+    /// A header that is malformed or has a non-UTF-8 value yields an error for that item
+    /// and iteration continues with the next line, except that iteration stops after an
+    /// error that leaves the line unconsumed, such as `Error::Partial`.
+    pub fn as_str_pairs(self) -> StrPairs<'a> {
+        StrPairs {
+            headers: self,
+            done: false,
+        }
+    }
 }
 
 impl<'a> Iterator for Headers<'a> {
     type Item = Result<Header<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (line, rest) = match next_line(self.0) {
-            Ok(x) => x,
-            Err(e) => return Some(Err(e)),
-        };
+        loop {
+            let h = match self.next_field()? {
+                Ok(h) => h,
+                Err(e) => return Some(Err(e)),
+            };
 
-        self.0 = rest;
+            if h.name.eq_ignore_ascii_case("Proxy-Connection") {
+                match self.config.proxy_connection {
+                    ProxyConnection::Keep => {},
+                    ProxyConnection::Connection => return Some(Ok(Header {
+                        name: "Connection",
+                        val: h.val,
+                    })),
+                    ProxyConnection::Strip => continue,
+                    ProxyConnection::Reject => return Some(Err(Error::Syntax)),
+                }
+            }
 
-        // Headers are terminated by an empty line [RFC7230§3].
-        if line.is_empty() {
-            return None;
+            return Some(Ok(h));
         }
+    }
+}
+
+impl<'a> Headers<'a> {
+    /// Parse the next header field, without applying any name-based policies.
+    fn next_field(&mut self) -> Option<Result<Header<'a>>> {
+        let mut field;
+
+        let line = loop {
+            field = self.buf;
+
+            let (line, rest) = match self.config.line_endings.next_line(self.buf) {
+                Ok(x) => x,
+                Err(Error::Partial) => {
+                    // Check the incomplete line so oversized values fail early.
+                    let line = memchr(b'\r', self.buf)
+                        .and_then(|idx| self.buf.get(..idx))
+                        .unwrap_or(self.buf);
+
+                    return Some(self.check_limits(line).and(Err(Error::Partial)));
+                },
+                Err(e) => return Some(Err(e)),
+            };
+
+            if let Err(e) = self.check_limits(line) {
+                return Some(Err(e));
+            }
+
+            self.buf = rest;
+
+            // Headers are terminated by an empty line [RFC7230§3].
+            if line.is_empty() {
+                self.terminated = true;
+                return None;
+            }
+
+            // Skip whitespace-only lines if configured.
+            if self.config.whitespace_lines == WhitespaceLines::Skip &&
+                line.iter().all(|&b| is_ows(b))
+            {
+                continue;
+            }
+
+            // A continued line can't start a field, and if merging is configured any
+            // continuation lines were consumed along with the field they continue.
+            let continued = line.first().is_some_and(|&b| is_ows(b));
+
+            if continued && !line.iter().all(|&b| is_ows(b)) {
+                match self.config.obs_fold {
+                    ObsFold::Split => {},
+                    ObsFold::Reject => return Some(Err(Error::ObsFold)),
+                    ObsFold::Merge => return Some(Err(Error::Syntax)),
+                }
+            }
+
+            break line;
+        };
+
+        let line = match self.config.obs_fold {
+            ObsFold::Split | ObsFold::Reject => line,
+            ObsFold::Merge => match self.merge_folds(field, line.len()) {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            },
+        };
 
         let (name, val) = match memchr(b':', line) {
-            Some(idx) => line.split_at(idx),
+            Some(idx) => (line.get(..idx), line.get(idx + 1..)),
             None => return Some(Err(Error::Syntax)),
         };
 
+        let (name, val) = match (name, val) {
+            (Some(name), Some(val)) => (name, val),
+            _ => return Some(Err(Error::Syntax)),
+        };
+
         let name = match std::str::from_utf8(name) {
             Ok(s) => s.trim(),
             Err(_) => return Some(Err(Error::Syntax)),
@@ -161,29 +601,160 @@ impl<'a> Iterator for Headers<'a> {
             return Some(Err(Error::Syntax));
         }
 
-        // Skip past ':'.
-        let val = &val[1..];
-
         Some(Ok(Header { name, val }))
     }
+
+    /// Extend the field line of the given length at the start of the given bytes over
+    /// any following continuation lines, consuming them.
+    fn merge_folds(&mut self, field: &'a [u8], mut len: usize) -> Result<&'a [u8]> {
+        loop {
+            // If no more bytes have arrived, the next line may yet continue the field.
+            if self.buf.first().is_some_and(|&b| !is_ows(b)) {
+                return field.get(..len).ok_or(Error::Syntax);
+            }
+
+            let (line, rest) = match self.config.line_endings.next_line(self.buf) {
+                Ok(x) => x,
+                Err(Error::Partial) => {
+                    // Leave the whole field to be parsed again once more bytes arrive.
+                    self.buf = field;
+                    return self.check_limits(field).and(Err(Error::Partial));
+                },
+                Err(e) => return Err(e),
+            };
+
+            let folded = field.len().checked_sub(self.buf.len())
+                .and_then(|n| field.get(..n + line.len()))
+                .ok_or(Error::Syntax)?;
+
+            len = folded.len();
+            self.check_limits(folded)?;
+            self.buf = rest;
+        }
+    }
+}
+
+/// Iterator over header fields with a certain name prefix.
+pub struct WithPrefix<'a, 'p> {
+    headers: Headers<'a>,
+    prefix: &'p str,
+}
+
+impl<'a, 'p> Iterator for WithPrefix<'a, 'p> {
+    type Item = Result<Header<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix = self.prefix.as_bytes();
+
+        self.headers.find(|h| match *h {
+            Ok(h) => h.name.as_bytes().get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix)),
+            Err(_) => true,
+        })
+    }
+}
+
+/// Iterator over header fields as `(name, value)` string pairs.
+pub struct StrPairs<'a> {
+    headers: Headers<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for StrPairs<'a> {
+    type Item = Result<(&'a str, &'a str)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let before = self.headers.buf.len();
+
+        let h = match self.headers.next()? {
+            Ok(h) => h,
+            Err(e) => {
+                // Retrying an unconsumed line would yield the same error forever.
+                self.done = self.headers.buf.len() == before;
+                return Some(Err(e));
+            },
+        };
+
+        Some(std::str::from_utf8(grammar::trim_ows(h.val))
+            .map(|v| (h.name, v))
+            .map_err(|_| Error::Syntax))
+    }
+}
+
+/// Try to parse the given bytes as the common `GET <origin-form> HTTP/1.1` Request-Line in
+/// a single scan, returning `None` if the general parser is needed.
+///
+/// This only succeeds when the general parser would return an identical result.
+fn fast_get<'a>(buf: &'a [u8]) -> Option<(RequestLine<'a>, &'a [u8])> {
+    const SUFFIX: &[u8] = b" HTTP/1.1\r\n";
+
+    let after = buf.strip_prefix(b"GET ")?;
+
+    if after.first() != Some(&b'/') {
+        return None;
+    }
+
+    let len = visible_len(after);
+    let (target, suffix) = (after.get(..len)?, after.get(len..)?);
+    let rest = suffix.strip_prefix(SUFFIX)?;
+
+    // Components are visible ASCII, so these can't fail.
+    let method = std::str::from_utf8(buf.get(..3)?).ok()?;
+    let target = std::str::from_utf8(target).ok()?;
+    let version = std::str::from_utf8(suffix.get(1..SUFFIX.len() - 2)?).ok()?;
+
+    Some((RequestLine { method, target, version }, rest))
+}
+
+/// Count the leading visible ASCII characters in the given bytes, checking 8 bytes at a
+/// time.
+fn visible_len(bytes: &[u8]) -> usize {
+    const ONES: u64 = 0x0101010101010101;
+    const HIGHS: u64 = 0x8080808080808080;
+
+    let mut pos = 0;
+
+    while let Some(chunk) = bytes.get(pos..pos + 8) {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        let x = u64::from_le_bytes(word);
+
+        // Set the high bit of some byte if any byte is below 0x21 or above 0x7E.
+        let below = x.wrapping_sub(ONES * 0x21) & !x;
+        let above = x.wrapping_add(ONES) | x;
+
+        if (below | above) & HIGHS != 0 {
+            break;
+        }
+
+        pos += 8;
+    }
+
+    pos + bytes.iter().skip(pos).take_while(|&&b| (0x21..0x7F).contains(&b)).count()
 }
 
 /// Consume CRLFs until the first non-CRLF character, returning a slice beginning at that
 /// character.
-fn skip_empty_lines<'a>(mut bytes: &'a [u8]) -> Result<&'a [u8]> {
+fn skip_empty_lines(mut bytes: &[u8]) -> Result<&[u8]> {
     loop {
         match check_crlf(bytes) {
             Ok(rest) => bytes = rest,
-            Err(Error::Partial) => return Err(Error::Partial),
             Err(Error::Syntax) => return Ok(bytes),
+            Err(e) => return Err(e),
         }
     }
 }
 
 /// Retrieve the next chunk in the request, up to and not including the nearest CRLF.
-fn next_line<'a>(bytes: &'a [u8]) -> Result<(&'a [u8], &'a [u8])> {
-    let (line, rest) = match memchr(b'\r', bytes) {
-        Some(idx) => bytes.split_at(idx),
+fn next_line(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    let split = memchr(b'\r', bytes).and_then(|i| bytes.split_at_checked(i));
+
+    let (line, rest) = match split {
+        Some(x) => x,
         None => return Err(Error::Partial),
     };
 
@@ -194,14 +765,12 @@ fn next_line<'a>(bytes: &'a [u8]) -> Result<(&'a [u8], &'a [u8])> {
 
 /// Check if the given slice begins with CRLF and, if it does, return the slice
 /// immediately after.
-fn check_crlf<'a>(bytes: &'a [u8]) -> Result<&'a [u8]> {
+fn check_crlf(bytes: &[u8]) -> Result<&[u8]> {
     if bytes.len() < 2 {
         Err(Error::Partial)
-    } else if bytes.starts_with(&b"\r\n"[..]) {
-        // Skip over CRLF.
-        Ok(&bytes[2..])
     } else {
-        Err(Error::Syntax)
+        // Skip over CRLF.
+        bytes.strip_prefix(&b"\r\n"[..]).ok_or(Error::Syntax)
     }
 }
 
@@ -295,6 +864,102 @@ mod test {
         assert_eq!(RequestLine::new(b"GET / HTTP/1.1\n"), Err(Error::Partial));
     }
 
+    #[test]
+    fn test_request_line_method() {
+        let (rl, _) = RequestLine::new(b"DELETE /a HTTP/1.1\r\n").unwrap();
+        assert_eq!(rl.method(), Ok(Method::Delete));
+        assert_eq!(rl.method, "DELETE");
+
+        let (rl, _) = RequestLine::new(b"MKCOL /a HTTP/1.1\r\n").unwrap();
+        assert_eq!(rl.method(), Ok(Method::Extension("MKCOL")));
+
+        let (rl, _) = RequestLine::new(b"G:T /a HTTP/1.1\r\n").unwrap();
+        assert_eq!(rl.method(), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_request_line_version() {
+        let (rl, _) = RequestLine::new(b"GET / HTTP/1.0\r\n").unwrap();
+        assert_eq!(rl.version_parsed(), Ok(Version::HTTP_10));
+
+        let (rl, _) = RequestLine::new(b"GET / HTTP/1.1x\r\n").unwrap();
+        assert_eq!(rl.version_parsed(), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_request_line_config() {
+        let config = ParserConfig {
+            max_method_len: Some(7),
+            allowed_methods: Some(&["GET", "OPTIONS"]),
+            ..ParserConfig::new()
+        };
+
+        let (req, rest) = RequestLine::with_config(b"OPTIONS * HTTP/1.1\r\n\r\n", config)
+            .unwrap();
+        assert_eq!(req.method, "OPTIONS");
+        assert_eq!(rest, b"\r\n");
+
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1\r\n", config).unwrap().0.method,
+            "GET");
+        assert_eq!(RequestLine::with_config(b"get / HTTP/1.1\r\n", config),
+            Err(Error::UnsupportedMethod));
+        assert_eq!(RequestLine::with_config(b"POST / HTTP/1.1\r\n", config),
+            Err(Error::UnsupportedMethod));
+        assert_eq!(RequestLine::with_config(b"PROPFIND / HTTP/1.1\r\n", config),
+            Err(Error::UnsupportedMethod));
+
+        // Length is checked before the line is complete, but the allow-list isn't.
+        assert_eq!(RequestLine::with_config(b"\r\nPROPFIN", config), Err(Error::Partial));
+        assert_eq!(RequestLine::with_config(b"\r\nPROPFIND", config),
+            Err(Error::UnsupportedMethod));
+        assert_eq!(RequestLine::with_config(b"POST /", config), Err(Error::Partial));
+        assert_eq!(RequestLine::with_config(b"\r", config), Err(Error::Partial));
+        assert_eq!(RequestLine::with_config(b"GET\r\n", config), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_fast_get() {
+        for &buf in &[
+            &b"GET / HTTP/1.1\r\n\r\n"[..],
+            b"GET /a/b/c?d=e&f=%20 HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET /abcdefghijklmnopqrstuvwxyz HTTP/1.1\r\n",
+        ] {
+            let fast = fast_get(buf).unwrap();
+            assert_eq!(fast, RequestLine::parse(buf, LineEndings::Crlf).unwrap());
+            assert_eq!(fast.0.target.as_ptr(), buf[4..].as_ptr());
+        }
+
+        for &buf in &[
+            &b"GET / HTTP/1.1\r\n"[..4],
+            b"GET / HTTP/1.1\r",
+            b"GET / HTTP/1.0\r\n\r\n",
+            b"GET * HTTP/1.1\r\n\r\n",
+            b"POST / HTTP/1.1\r\n\r\n",
+            b"\r\nGET / HTTP/1.1\r\n\r\n",
+            b"GET /abcdefgh\tijk HTTP/1.1\r\n\r\n",
+            b"GET /abcdefgh\xc3\xa9 HTTP/1.1\r\n\r\n",
+            b"GET /abcdefgh\x7f HTTP/1.1\r\n\r\n",
+            b"GET /a  HTTP/1.1\r\n\r\n",
+        ] {
+            assert_eq!(fast_get(buf), None);
+        }
+    }
+
+    #[test]
+    fn test_visible_len() {
+        assert_eq!(visible_len(b""), 0);
+        assert_eq!(visible_len(b"abc"), 3);
+        assert_eq!(visible_len(b"!~!~!~!~!~!~!~!~ "), 16);
+
+        for i in 0..16 {
+            for &b in &[0, b' ', 0x7f, 0x80, 0xff] {
+                let mut buf = [b'a'; 16];
+                buf[i] = b;
+                assert_eq!(visible_len(&buf), i);
+            }
+        }
+    }
+
     #[test]
     fn test_headers() {
         let mut h = Headers::new(
@@ -334,6 +999,191 @@ mod test {
         assert_eq!(n, Err(Error::Partial));
     }
 
+    #[test]
+    fn test_headers_config() {
+        let config = ParserConfig {
+            max_value_len: Some(4),
+            ..ParserConfig::new()
+        };
+
+        let mut h = Headers::with_config(b"A: 123\r\nB:12345\r\n\r\n", config);
+        assert_eq!(h.next().unwrap().unwrap().val, b" 123");
+        assert_eq!(h.next().unwrap(), Err(Error::ValueTooLong));
+
+        let mut h = Headers::with_config(b"A:1234\r\n\r\n", config);
+        assert_eq!(h.next().unwrap().unwrap().val, b"1234");
+        assert!(h.next().is_none());
+
+        // Incomplete lines are still checked.
+        let mut h = Headers::with_config(b"Cookie: 1234", config);
+        assert_eq!(h.next().unwrap(), Err(Error::ValueTooLong));
+        let mut h = Headers::with_config(b"Cookie: 123", config);
+        assert_eq!(h.next().unwrap(), Err(Error::Partial));
+        let mut h = Headers::with_config(b"Cookie: 1234\r", config);
+        assert_eq!(h.next().unwrap(), Err(Error::ValueTooLong));
+
+        // Names aren't limited.
+        let mut h = Headers::with_config(b"Long-Header-Name: 1\r\n\r\n", config);
+        assert_eq!(h.next().unwrap().unwrap().name, "Long-Header-Name");
+    }
+
+    #[test]
+    fn test_proxy_connection() {
+        let buf = b"Proxy-Connection: keep-alive\r\nHost: a\r\n\r\n";
+
+        let mut h = Headers::new(buf);
+        let n = h.next().unwrap().unwrap();
+        assert_eq!(n.name, "Proxy-Connection");
+        assert_eq!(n.val, b" keep-alive");
+
+        let config = |proxy_connection| ParserConfig {
+            proxy_connection,
+            ..ParserConfig::new()
+        };
+
+        let mut h = Headers::with_config(buf, config(ProxyConnection::Connection));
+        let n = h.next().unwrap().unwrap();
+        assert_eq!(n.name, "Connection");
+        assert_eq!(n.val, b" keep-alive");
+
+        let mut h = Headers::with_config(buf, config(ProxyConnection::Strip));
+        assert_eq!(h.next().unwrap().unwrap().name, "Host");
+        assert!(h.next().is_none());
+        assert_eq!(h.into_inner(), b"");
+
+        let mut h = Headers::with_config(buf, config(ProxyConnection::Reject));
+        assert_eq!(h.next().unwrap(), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_whitespace_lines() {
+        let buf = b"A: 1\r\n \t\r\nB: 2\r\n \r\n\r\nbody";
+
+        let mut h = Headers::new(buf);
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert_eq!(h.next().unwrap(), Err(Error::Syntax));
+
+        let config = ParserConfig {
+            whitespace_lines: WhitespaceLines::Skip,
+            ..ParserConfig::new()
+        };
+
+        let mut h = Headers::with_config(buf, config);
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert_eq!(h.next().unwrap().unwrap().name, "B");
+        assert!(h.next().is_none());
+        assert_eq!(h.into_inner(), b"body");
+
+        let mut h = Headers::with_config(b"  \r\n", config);
+        assert_eq!(h.next().unwrap(), Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_obs_fold() {
+        let buf = b"A: 1\r\n 2\r\n\t3\r\nB: 4\r\n \r\n\r\nbody";
+
+        let mut h = Headers::new(buf);
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert_eq!(h.next().unwrap(), Err(Error::Syntax));
+
+        let config = ParserConfig { obs_fold: ObsFold::Reject, ..ParserConfig::new() };
+        let mut h = Headers::with_config(buf, config);
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert_eq!(h.next().unwrap(), Err(Error::ObsFold));
+        assert!(!Headers::with_config(b"A: 1\r\n B: 2\r\n\r\n", config).contains("B"));
+        assert!(Headers::new(b"A: 1\r\n B: 2\r\n\r\n").contains("B"));
+
+        let config = ParserConfig { obs_fold: ObsFold::Merge, ..ParserConfig::new() };
+        let mut h = Headers::with_config(buf, config);
+        let a = h.next().unwrap().unwrap();
+        assert_eq!(a, Header { name: "A", val: b" 1\r\n 2\r\n\t3" });
+        let b = h.next().unwrap().unwrap();
+        assert_eq!(b, Header { name: "B", val: b" 4\r\n " });
+        assert!(h.next().is_none());
+        assert_eq!(h.into_inner(), b"body");
+
+        let mut out = [0; 8];
+        assert_eq!(a.unfold(&mut out), Ok(6));
+        assert_eq!(&out[..6], b" 1 2 3");
+        assert_eq!(b.unfold(&mut out), Ok(3));
+        assert_eq!(&out[..3], b" 4 ");
+        assert_eq!(a.unfold(&mut out[..5]), Err(Error::Capacity));
+
+        let mut h = Headers::with_config(b"A: 1\r\n", config);
+        assert_eq!(h.next(), Some(Err(Error::Partial)));
+        assert_eq!(h.into_inner(), b"A: 1\r\n");
+
+        let mut h = Headers::with_config(b"A: 1\r\n 2", config);
+        assert_eq!(h.next(), Some(Err(Error::Partial)));
+
+        let mut h = Headers::with_config(b" A: 1\r\n\r\n", config);
+        assert_eq!(h.next(), Some(Err(Error::Syntax)));
+
+        let config = ParserConfig { max_value_len: Some(6), ..config };
+        let mut h = Headers::with_config(b"A: 1\r\n 2\r\n 3\r\n\r\n", config);
+        assert_eq!(h.next(), Some(Err(Error::ValueTooLong)));
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        let mut h = Headers::new(
+            b"X-Amz-Date: 1\r\nHost: a\r\nx-amz-content: 2\r\nX-Am: 3\r\n\r\nbody"
+        ).with_prefix("x-amz-");
+        assert_eq!(h.next().unwrap().unwrap().name, "X-Amz-Date");
+        assert_eq!(h.next().unwrap().unwrap().name, "x-amz-content");
+        assert!(h.next().is_none());
+
+        let mut h = Headers::new(b"Host: a\r\nX-Amz-Date\r\n\r\n").with_prefix("X-");
+        assert_eq!(h.next().unwrap(), Err(Error::Syntax));
+
+        let mut h = Headers::new(b"Host: a\r\n\r\n").with_prefix("");
+        assert_eq!(h.next().unwrap().unwrap().name, "Host");
+        assert!(h.next().is_none());
+    }
+
+    #[test]
+    fn test_contains() {
+        let h = Headers::new(b"Host: a\r\n Upgrade :b\r\nProxy-Connection: c\r\n\r\nExpect: d");
+        assert!(h.contains("host"));
+        assert!(h.contains("UPGRADE"));
+        assert!(h.contains("Proxy-Connection"));
+        assert!(!h.contains("Connection"));
+        assert!(!h.contains("Expect"));
+        assert!(!h.contains("Hos"));
+
+        assert!(Headers::new(b"Expect: 100-continue").contains("Expect"));
+        assert!(!Headers::new(b"").contains("Expect"));
+
+        let config = |proxy_connection| ParserConfig {
+            proxy_connection,
+            ..ParserConfig::new()
+        };
+
+        let buf = b"Proxy-Connection: close\r\n\r\n";
+        let h = Headers::with_config(buf, config(ProxyConnection::Connection));
+        assert!(h.contains("Connection"));
+        assert!(!h.contains("Proxy-Connection"));
+        let h = Headers::with_config(buf, config(ProxyConnection::Strip));
+        assert!(!h.contains("Connection"));
+        assert!(!h.contains("Proxy-Connection"));
+    }
+
+    #[test]
+    fn test_as_str_pairs() {
+        let mut h = Headers::new(b"Host:  a \r\nBad\r\nX: \xff\r\nY:\r\n\r\nbody")
+            .as_str_pairs();
+        assert_eq!(h.next(), Some(Ok(("Host", "a"))));
+        assert_eq!(h.next(), Some(Err(Error::Syntax)));
+        assert_eq!(h.next(), Some(Err(Error::Syntax)));
+        assert_eq!(h.next(), Some(Ok(("Y", ""))));
+        assert_eq!(h.next(), None);
+
+        let mut h = Headers::new(b"Host: a\r\nX: b").as_str_pairs();
+        assert_eq!(h.next(), Some(Ok(("Host", "a"))));
+        assert_eq!(h.next(), Some(Err(Error::Partial)));
+        assert_eq!(h.next(), None);
+    }
+
     #[test]
     fn test_skip_empty_lines() {
         assert_eq!(skip_empty_lines(b"GET"), Ok(&b"GET"[..]));
@@ -362,4 +1212,92 @@ mod test {
         assert_eq!(check_crlf(b"\nabc"), Err(Error::Syntax));
         assert_eq!(check_crlf(b"abc\r\n"), Err(Error::Syntax));
     }
+
+    #[test]
+    fn test_line_endings() {
+        let config = ParserConfig { line_endings: LineEndings::Lenient, ..ParserConfig::new() };
+
+        let (req, rest) = RequestLine::with_config(b"\n\r\nGET /a HTTP/1.0\nA: b\n", config)
+            .unwrap();
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.target, "/a");
+        assert_eq!(req.version, "HTTP/1.0");
+        assert_eq!(rest, b"A: b\n");
+
+        let (_, rest) = RequestLine::with_config(b"GET / HTTP/1.1\r\nA", config).unwrap();
+        assert_eq!(rest, b"A");
+
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1", config), Err(Error::Partial));
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1\r", config),
+            Err(Error::Partial));
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1\rA", config),
+            Err(Error::Syntax));
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1\r\r\n", config),
+            Err(Error::Syntax));
+        assert_eq!(RequestLine::with_config(b"GET / HTTP/1.1\n", ParserConfig::new()),
+            Err(Error::Partial));
+
+        let mut h = Headers::with_config(b"A: b\nC: d\r\n\nbody", config);
+        assert!(h.contains("C"));
+        assert!(!h.contains("body"));
+        assert_eq!(h.next(), Some(Ok(Header { name: "A", val: b" b" })));
+        assert_eq!(h.next(), Some(Ok(Header { name: "C", val: b" d" })));
+        assert_eq!(h.next(), None);
+        assert_eq!(h.into_inner(), b"body");
+
+        let mut h = Headers::with_config(b"A: b\rc\n\n", config);
+        assert_eq!(h.next(), Some(Err(Error::Syntax)));
+
+        let mut h = Headers::new(b"A: b\n\n");
+        assert_eq!(h.next(), Some(Err(Error::Partial)));
+    }
+
+    #[test]
+    fn test_new_strict() {
+        let (req, rest) = RequestLine::new_strict(b"\r\nM-SEARCH * HTTP/1.1\r\nA: b").unwrap();
+        assert_eq!(req.method, "M-SEARCH");
+        assert_eq!(req.target, "*");
+        assert_eq!(req.version, "HTTP/1.1");
+        assert_eq!(rest, b"A: b");
+
+        assert!(RequestLine::new_strict(b"GET /a?b=%20 HTTP/1.0\r\n").is_ok());
+        assert!(RequestLine::new_strict(b"GET / HTTP/2.0\r\n").is_ok());
+
+        assert_eq!(RequestLine::new_strict(b"GET / HTTP/1.1\r"), Err(Error::Partial));
+        assert_eq!(RequestLine::new_strict(b" / HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"G(T / HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GE\x7fT / HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GET  HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GET /\x01 HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GET /\xc3\xa9 HTTP/1.1\r\n"),
+            Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GET / http/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(RequestLine::new_strict(b"GET / HTTP/1.10\r\n"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_query_params() {
+        let (req, _) = RequestLine::new(b"GET /a?b=c&d HTTP/1.1\r\n").unwrap();
+        let mut q = req.query_params();
+        assert_eq!(q.next(), Some((&b"b"[..], &b"c"[..])));
+        assert_eq!(q.next(), Some((&b"d"[..], &b""[..])));
+        assert_eq!(q.next(), None);
+        assert_eq!(req.query().get_bool("d"), Ok(Some(true)));
+    }
+
+    #[test]
+    fn test_path_segments() {
+        let (req, _) = RequestLine::new(b"GET /a/b?c HTTP/1.1\r\n").unwrap();
+        assert_eq!(req.path_segments().collect::<Vec<_>>(), vec![&b"a"[..], b"b"]);
+    }
+
+    #[test]
+    fn test_const() {
+        static CONFIG: ParserConfig = ParserConfig::embedded_minimal();
+        const REASON: Option<&str> = response::reason(404);
+
+        let mut h = Headers::with_config(b"Proxy-Connection: close\r\nA: b\r\n\r\n", CONFIG);
+        assert_eq!(h.next(), Some(Ok(Header { name: "A", val: b" b" })));
+        assert_eq!(REASON, Some("Not Found"));
+    }
 }
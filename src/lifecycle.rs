@@ -0,0 +1,221 @@
+//! Parsing of the header fields that announce the deprecation and retirement of a
+//! resource, for API clients and gateways honoring endpoint lifecycle metadata.
+//!
+//! The Deprecation header [RFC9745§2] gives when a resource was or will be deprecated,
+//! the Sunset header [RFC8594§3] gives when it will stop responding, and a Link header
+//! with `rel=deprecation` [RFC9745§3] points at documentation of the deprecation.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::lifecycle::{deprecation_link, parse_deprecation, parse_sunset};
+//! use uhttp_request::lifecycle::Deprecation;
+//!
+//! assert_eq!(parse_deprecation(b"@1688169599"), Ok(Deprecation::Date(1688169599)));
+//! assert_eq!(parse_sunset(b"Sat, 01 Jul 2023 00:00:00 GMT"), Ok(1688169600));
+//!
+//! let link = b"<https://example.com/v2>; rel=successor-version, \
+//!              <https://example.com/deprecation>; rel=\"deprecation\"";
+//!
+//! assert_eq!(deprecation_link(link), Ok(Some(&b"https://example.com/deprecation"[..])));
+//! ```
+
+use date;
+use grammar::trim_ows;
+use params::{self, split_unquoted, Params};
+use {Error, Result};
+
+/// A Deprecation header value.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Deprecation {
+    /// Time of deprecation in seconds since the Unix epoch, which may be in the future.
+    Date(u64),
+    /// Boolean form from earlier drafts, where `true` means deprecated at an unspecified
+    /// time.
+    Flag(bool),
+}
+
+/// Try to parse the given Deprecation header value, with surrounding whitespace
+/// trimmed.
+///
+/// The specified Structured Field date [RFC9651§3.3.7] is accepted along with the
+/// boolean `?1`, `?0`, and `true` forms and HTTP-dates of earlier drafts. Dates before
+/// the Unix epoch cause `Error::Syntax`.
+pub fn parse_deprecation(val: &[u8]) -> Result<Deprecation> {
+    match trim_ows(val) {
+        b"?1" | b"true" => Ok(Deprecation::Flag(true)),
+        b"?0" => Ok(Deprecation::Flag(false)),
+        val => match val.strip_prefix(b"@") {
+            Some(secs) => parse_sf_date(secs).map(Deprecation::Date),
+            None => date::parse(val).map(Deprecation::Date),
+        },
+    }
+}
+
+/// Try to parse the given Sunset header value, an HTTP-date, into seconds since the Unix
+/// epoch.
+pub fn parse_sunset(val: &[u8]) -> Result<u64> { date::parse(val) }
+
+/// A single link from a Link header value [RFC8288§3].
+#[derive(Clone, Debug)]
+pub struct Link<'a> {
+    /// Target URI-reference, without its angle brackets, which may be relative to the
+    /// request target.
+    pub target: &'a [u8],
+
+    /// Iterator over the link's parameters, such as `rel`.
+    pub params: Params<'a>,
+}
+
+impl<'a> Link<'a> {
+    /// Check if any `rel` parameter of the link includes the given relation type,
+    /// compared case-insensitively.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.params.clone()
+            .filter_map(|p| p.ok())
+            .filter(|p| p.name.eq_ignore_ascii_case("rel"))
+            .filter_map(|p| p.val)
+            .any(|v| params::unquote(v).split(|&b| b == b' ')
+                .any(|r| r.eq_ignore_ascii_case(rel.as_bytes())))
+    }
+}
+
+/// Create an iterator over the links in the given Link header value.
+pub fn links<'a>(val: &'a [u8]) -> Links<'a> {
+    Links(Some(val))
+}
+
+/// Iterator over the links in a Link header value.
+///
+/// Commas within a target URI or quoted-string don't split links. If a link is
+/// malformed, `Error::Syntax` is yielded and iteration stops.
+#[derive(Clone, Debug)]
+pub struct Links<'a>(Option<&'a [u8]>);
+
+impl<'a> Links<'a> {
+    /// Parse the next link, if there is one.
+    fn parse(&mut self) -> Result<Option<Link<'a>>> {
+        let rest = loop {
+            let rest = match self.0 {
+                Some(rest) => trim_ows(rest),
+                None => return Ok(None),
+            };
+
+            match rest.strip_prefix(b",") {
+                Some(after) => self.0 = Some(after),
+                None if rest.is_empty() => return Ok(None),
+                None => break rest,
+            }
+        };
+
+        let rest = rest.strip_prefix(b"<").ok_or(Error::Syntax)?;
+        let end = rest.iter().position(|&b| b == b'>').ok_or(Error::Syntax)?;
+        let (params, next) = split_unquoted(&rest[end + 1..], b',')?;
+
+        if !trim_ows(params).is_empty() && !trim_ows(params).starts_with(b";") {
+            return Err(Error::Syntax);
+        }
+
+        self.0 = next;
+
+        Ok(Some(Link { target: &rest[..end], params: params::iter(params) }))
+    }
+}
+
+impl<'a> Iterator for Links<'a> {
+    type Item = Result<Link<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parse() {
+            Ok(l) => l.map(Ok),
+            Err(e) => {
+                self.0 = None;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Try to find the target of the first link with the `deprecation` relation type in the
+/// given Link header value.
+pub fn deprecation_link(val: &[u8]) -> Result<Option<&[u8]>> {
+    for link in links(val) {
+        let link = link?;
+
+        if link.has_rel("deprecation") {
+            return Ok(Some(link.target));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parse the given integer of a Structured Field date, following the `@`.
+fn parse_sf_date(secs: &[u8]) -> Result<u64> {
+    // Negative dates are before the epoch and so unrepresentable.
+    if secs.is_empty() || secs.len() > 15 || !secs.iter().all(u8::is_ascii_digit) {
+        return Err(Error::Syntax);
+    }
+
+    Ok(secs.iter().fold(0, |n, &b| n * 10 + (b - b'0') as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_deprecation() {
+        assert_eq!(parse_deprecation(b" @0 "), Ok(Deprecation::Date(0)));
+        assert_eq!(parse_deprecation(b"@999999999999999"),
+            Ok(Deprecation::Date(999999999999999)));
+        assert_eq!(parse_deprecation(b"?1"), Ok(Deprecation::Flag(true)));
+        assert_eq!(parse_deprecation(b"true"), Ok(Deprecation::Flag(true)));
+        assert_eq!(parse_deprecation(b"?0"), Ok(Deprecation::Flag(false)));
+        assert_eq!(parse_deprecation(b"Sun, 06 Nov 1994 08:49:37 GMT"),
+            Ok(Deprecation::Date(784111777)));
+
+        assert_eq!(parse_deprecation(b"@1000000000000000"), Err(Error::Syntax));
+        assert_eq!(parse_deprecation(b"@-1"), Err(Error::Syntax));
+        assert_eq!(parse_deprecation(b"@"), Err(Error::Syntax));
+        assert_eq!(parse_deprecation(b"@1.5"), Err(Error::Syntax));
+        assert_eq!(parse_deprecation(b"1688169599"), Err(Error::Syntax));
+        assert_eq!(parse_deprecation(b""), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_links() {
+        let mut l = links(b" , <a,b>;rel=\"x  Deprecation\" ;title=t,,<c> , <d>; rel=y ");
+
+        let link = l.next().unwrap().unwrap();
+        assert_eq!(link.target, b"a,b");
+        assert!(link.has_rel("deprecation"));
+        assert!(link.has_rel("X"));
+        assert!(!link.has_rel("title"));
+
+        let link = l.next().unwrap().unwrap();
+        assert_eq!(link.target, b"c");
+        assert!(!link.has_rel("deprecation"));
+
+        let link = l.next().unwrap().unwrap();
+        assert_eq!(link.target, b"d");
+        assert!(link.has_rel("y"));
+        assert!(l.next().is_none());
+
+        let mut l = links(b"<a> x, <b>");
+        assert!(matches!(l.next(), Some(Err(Error::Syntax))));
+        assert!(l.next().is_none());
+
+        assert!(matches!(links(b"a").next(), Some(Err(Error::Syntax))));
+        assert!(matches!(links(b"<a").next(), Some(Err(Error::Syntax))));
+        assert!(links(b" ").next().is_none());
+    }
+
+    #[test]
+    fn test_deprecation_link() {
+        assert_eq!(deprecation_link(b"<a>; rel=next, <b>; rel=deprecation"),
+            Ok(Some(&b"b"[..])));
+        assert_eq!(deprecation_link(b"<a>; rel=next"), Ok(None));
+        assert_eq!(deprecation_link(b"<a>; rel=next, b"), Err(Error::Syntax));
+    }
+}
@@ -0,0 +1,85 @@
+//! Splitting of comma-separated list values [RFC7230§7], as used in Connection, Accept,
+//! Cache-Control, and most other list-valued headers.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::list;
+//!
+//! let mut v = list::values(b"text/html, application/json;q=0.9, \"a,b\"");
+//!
+//! assert_eq!(v.next(), Some(Ok(&b"text/html"[..])));
+//! assert_eq!(v.next(), Some(Ok(&b"application/json;q=0.9"[..])));
+//! assert_eq!(v.next(), Some(Ok(&b"\"a,b\""[..])));
+//! assert_eq!(v.next(), None);
+//! ```
+
+use grammar::trim_ows;
+use params::split_unquoted;
+use Result;
+
+/// Create an iterator over the comma-separated elements in the given value.
+///
+/// Commas within quoted-strings [RFC7230§3.2.6] don't split elements, and empty elements
+/// are skipped as recipients are required to do [RFC7230§7].
+pub fn values<'a>(val: &'a [u8]) -> ListValues<'a> {
+    ListValues(Some(val))
+}
+
+/// Iterator over comma-separated list elements, each with surrounding whitespace
+/// trimmed.
+///
+/// Quoted elements are returned with their quotes and escapes intact. If a
+/// quoted-string is unterminated, `Error::Syntax` is yielded and iteration stops.
+#[derive(Clone, Debug)]
+pub struct ListValues<'a>(Option<&'a [u8]>);
+
+impl<'a> Iterator for ListValues<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.0?;
+
+            let (elem, next) = match split_unquoted(rest, b',') {
+                Ok(x) => x,
+                Err(e) => {
+                    self.0 = None;
+                    return Some(Err(e));
+                },
+            };
+
+            self.0 = next;
+
+            let elem = trim_ows(elem);
+
+            if !elem.is_empty() {
+                return Some(Ok(elem));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    #[test]
+    fn test_values() {
+        let mut v = values(b" , a ,,\tb=\"x,\\\"y\" ;q=1 , ");
+        assert_eq!(v.next(), Some(Ok(&b"a"[..])));
+        assert_eq!(v.next(), Some(Ok(&b"b=\"x,\\\"y\" ;q=1"[..])));
+        assert_eq!(v.next(), None);
+        assert_eq!(v.next(), None);
+
+        let mut v = values(b"close, \"open");
+        assert_eq!(v.next(), Some(Ok(&b"close"[..])));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), None);
+
+        assert_eq!(values(b"").next(), None);
+        assert_eq!(values(b" ,, ").next(), None);
+        assert_eq!(values(b"keep-alive").collect::<Vec<_>>(), [Ok(&b"keep-alive"[..])]);
+    }
+}
@@ -0,0 +1,281 @@
+//! Resolution of Location response header values [RFC7231§7.1.2] against the request
+//! target, for clients following redirects.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::location;
+//!
+//! let mut buf = [0; 64];
+//!
+//! let len = location::resolve(b"/a/b/c?x", b" ../d?y ", &mut buf).unwrap();
+//! assert_eq!(&buf[..len], b"/a/d?y");
+//!
+//! let len = location::resolve(b"http://a/b", b"//c/d", &mut buf).unwrap();
+//! assert_eq!(&buf[..len], b"http://c/d");
+//! ```
+
+use grammar::{is_vchar, trim_ows};
+use writer::Cursor;
+use {Error, Result};
+
+/// Components of a URI reference [RFC3986§4.1], split along its delimiters.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Reference<'a> {
+    scheme: Option<&'a [u8]>,
+    authority: Option<&'a [u8]>,
+    path: &'a [u8],
+    query: Option<&'a [u8]>,
+    fragment: Option<&'a [u8]>,
+}
+
+impl<'a> Reference<'a> {
+    /// Split the given URI reference as by the regular expression in [RFC3986§B].
+    fn new(uri: &'a [u8]) -> Self {
+        let delim = |b: u8| b == b'/' || b == b'?' || b == b'#';
+
+        let (scheme, rest) = match uri.iter().position(|&b| b == b':' || delim(b)) {
+            Some(idx) if idx > 0 && uri[idx] == b':' => (Some(&uri[..idx]), &uri[idx + 1..]),
+            _ => (None, uri),
+        };
+
+        let (authority, rest) = match rest.strip_prefix(b"//") {
+            Some(rest) => {
+                let end = rest.iter().position(|&b| delim(b)).unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            },
+            None => (None, rest),
+        };
+
+        let (rest, fragment) = match rest.iter().position(|&b| b == b'#') {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+            None => (rest, None),
+        };
+
+        let (path, query) = match rest.iter().position(|&b| b == b'?') {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+            None => (rest, None),
+        };
+
+        Reference { scheme, authority, path, query, fragment }
+    }
+}
+
+/// Try to resolve the given Location header value, with surrounding whitespace trimmed,
+/// against the given request target and write the resulting URI reference into the
+/// given buffer [RFC3986§5.2].
+///
+/// The target may be in origin-form or absolute-form, and the result then lacks or has
+/// a scheme and authority accordingly. Dot segments are removed from the path, and if
+/// the Location has no fragment, any fragment of the target is kept. A Location
+/// containing bytes other than VCHAR causes `Error::Syntax`.
+///
+/// On success, return the number of bytes written.
+pub fn resolve(target: &[u8], location: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let location = trim_ows(location);
+
+    if !location.iter().all(|&b| is_vchar(b)) {
+        return Err(Error::Syntax);
+    }
+
+    let base = Reference::new(target);
+    let rel = Reference::new(location);
+    let mut out = Cursor::new(buf);
+
+    let (scheme, authority) = if rel.scheme.is_some() {
+        (rel.scheme, rel.authority)
+    } else if rel.authority.is_some() {
+        (base.scheme, rel.authority)
+    } else {
+        (base.scheme, base.authority)
+    };
+
+    if let Some(scheme) = scheme {
+        out.push(scheme)?;
+        out.push(b":")?;
+    }
+
+    if let Some(authority) = authority {
+        out.push(b"//")?;
+        out.push(authority)?;
+    }
+
+    let query = if rel.scheme.is_some() || rel.authority.is_some() {
+        remove_dots(&mut out, &[rel.path])?;
+        rel.query
+    } else if rel.path.is_empty() {
+        out.push(base.path)?;
+        rel.query.or(base.query)
+    } else if rel.path.starts_with(b"/") {
+        remove_dots(&mut out, &[rel.path])?;
+        rel.query
+    } else if base.authority.is_some() && base.path.is_empty() {
+        remove_dots(&mut out, &[b"/", rel.path])?;
+        rel.query
+    } else {
+        // Merge with the base path up to and including its last segment separator.
+        let dir = base.path.iter().rposition(|&b| b == b'/').map_or(0, |idx| idx + 1);
+        remove_dots(&mut out, &[&base.path[..dir], rel.path])?;
+        rel.query
+    };
+
+    if let Some(query) = query {
+        out.push(b"?")?;
+        out.push(query)?;
+    }
+
+    if let Some(fragment) = rel.fragment.or(base.fragment) {
+        out.push(b"#")?;
+        out.push(fragment)?;
+    }
+
+    Ok(out.pos())
+}
+
+/// Write the path formed by concatenating the given parts with its dot segments removed
+/// [RFC3986§5.2.4].
+///
+/// Every part but the last must be empty or end with `/`, so segments never span parts.
+fn remove_dots(out: &mut Cursor, parts: &[&[u8]]) -> Result<()> {
+    let start = out.pos();
+    let abs = parts.iter().find(|p| !p.is_empty()).is_some_and(|p| p[0] == b'/');
+
+    let path_parts = parts.iter().enumerate().flat_map(|(i, p)| {
+        // Skip over the leading separator of the path, and the separator ending each
+        // part but the last, which is implied between segments.
+        let p = if abs && i == 0 { p.strip_prefix(b"/").unwrap_or(p) } else { p };
+        let p = if i + 1 < parts.len() { p.strip_suffix(b"/").unwrap_or(p) } else { p };
+
+        p.split(|&b| b == b'/').filter(move |_| !p.is_empty() || i + 1 == parts.len())
+    });
+
+    let mut segments = path_parts.peekable();
+
+    while let Some(seg) = segments.next() {
+        let last = segments.peek().is_none();
+
+        match seg {
+            b"." | b".." => {
+                if seg == b".." {
+                    // Remove the last segment along with its preceding separator.
+                    let idx = out.written()[start..].iter().rposition(|&b| b == b'/');
+                    out.truncate(start + idx.unwrap_or(0));
+                }
+
+                // A trailing dot segment leaves the path ending in a separator.
+                if last && (abs || out.pos() > start) {
+                    out.push(b"/")?;
+                }
+            },
+            _ => {
+                if abs || out.pos() > start {
+                    out.push(b"/")?;
+                }
+
+                out.push(seg)?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reference() {
+        assert_eq!(Reference::new(b"http://a/b?c#d"), Reference {
+            scheme: Some(b"http"),
+            authority: Some(b"a"),
+            path: b"/b",
+            query: Some(b"c"),
+            fragment: Some(b"d"),
+        });
+        assert_eq!(Reference::new(b"/a:b"), Reference {
+            scheme: None,
+            authority: None,
+            path: b"/a:b",
+            query: None,
+            fragment: None,
+        });
+        assert_eq!(Reference::new(b":x").scheme, None);
+        assert_eq!(Reference::new(b"?").query, Some(&b""[..]));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = b"http://a/b/c/d;p?q";
+        let mut buf = [0; 64];
+
+        let mut check = |loc: &[u8], expected: &[u8]| {
+            let len = resolve(base, loc, &mut buf).unwrap();
+            assert_eq!(&buf[..len], expected, "{:?}", std::str::from_utf8(loc));
+        };
+
+        // Examples from [RFC3986§5.4].
+        check(b"g:h", b"g:h");
+        check(b"g", b"http://a/b/c/g");
+        check(b"./g", b"http://a/b/c/g");
+        check(b"g/", b"http://a/b/c/g/");
+        check(b"/g", b"http://a/g");
+        check(b"//g", b"http://g");
+        check(b"?y", b"http://a/b/c/d;p?y");
+        check(b"g?y", b"http://a/b/c/g?y");
+        check(b"#s", b"http://a/b/c/d;p?q#s");
+        check(b"g#s", b"http://a/b/c/g#s");
+        check(b"g?y#s", b"http://a/b/c/g?y#s");
+        check(b";x", b"http://a/b/c/;x");
+        check(b"g;x", b"http://a/b/c/g;x");
+        check(b"g;x?y#s", b"http://a/b/c/g;x?y#s");
+        check(b"", b"http://a/b/c/d;p?q");
+        check(b".", b"http://a/b/c/");
+        check(b"./", b"http://a/b/c/");
+        check(b"..", b"http://a/b/");
+        check(b"../", b"http://a/b/");
+        check(b"../g", b"http://a/b/g");
+        check(b"../..", b"http://a/");
+        check(b"../../", b"http://a/");
+        check(b"../../g", b"http://a/g");
+        check(b"../../../g", b"http://a/g");
+        check(b"../../../../g", b"http://a/g");
+        check(b"/./g", b"http://a/g");
+        check(b"/../g", b"http://a/g");
+        check(b"g.", b"http://a/b/c/g.");
+        check(b".g", b"http://a/b/c/.g");
+        check(b"g..", b"http://a/b/c/g..");
+        check(b"..g", b"http://a/b/c/..g");
+        check(b"./../g", b"http://a/b/g");
+        check(b"./g/.", b"http://a/b/c/g/");
+        check(b"g/./h", b"http://a/b/c/g/h");
+        check(b"g/../h", b"http://a/b/c/h");
+        check(b"g;x=1/./y", b"http://a/b/c/g;x=1/y");
+        check(b"g;x=1/../y", b"http://a/b/c/y");
+        check(b"g?y/./x", b"http://a/b/c/g?y/./x");
+        check(b"g#s/../x", b"http://a/b/c/g#s/../x");
+        check(b"http:g", b"http:g");
+        check(b"https://x/./y/../z", b"https://x/z");
+        check(b"a//b", b"http://a/b/c/a//b");
+    }
+
+    #[test]
+    fn test_resolve_targets() {
+        let mut buf = [0; 32];
+
+        let mut check = |base: &[u8], loc: &[u8]| {
+            resolve(base, loc, &mut buf).map(|len| buf[..len].to_vec())
+        };
+
+        assert_eq!(check(b"/", b"a"), Ok(b"/a".to_vec()));
+        assert_eq!(check(b"/a/b", b"c"), Ok(b"/a/c".to_vec()));
+        assert_eq!(check(b"/a#f", b"b"), Ok(b"/b#f".to_vec()));
+        assert_eq!(check(b"/a#f", b"b#g"), Ok(b"/b#g".to_vec()));
+        assert_eq!(check(b"http://h", b"a"), Ok(b"http://h/a".to_vec()));
+        assert_eq!(check(b"http://h", b"?a"), Ok(b"http://h?a".to_vec()));
+        assert_eq!(check(b"*", b"a/../b"), Ok(b"b".to_vec()));
+        assert_eq!(check(b"/", b"a b"), Err(Error::Syntax));
+        assert_eq!(check(b"/", b"https://example.com/very/long/path"),
+            Err(Error::Capacity));
+    }
+}
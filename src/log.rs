@@ -0,0 +1,190 @@
+//! Access log lines in the Common and Combined Log Formats.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::Request;
+//! use uhttp_request::log::{LogFormat, LogLine};
+//!
+//! let req = Request::new(b"GET /a HTTP/1.1\r\nUser-Agent: curl/7.52\r\n\r\n").unwrap();
+//!
+//! let line = LogLine {
+//!     request: &req,
+//!     remote: "127.0.0.1",
+//!     user: None,
+//!     time: 971186136,
+//!     status: 200,
+//!     size: Some(2326),
+//!     format: LogFormat::Combined,
+//! };
+//!
+//! assert_eq!(line.to_string(), "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \
+//!     \"GET /a HTTP/1.1\" 200 2326 \"-\" \"curl/7.52\"");
+//! ```
+
+use std::fmt::{self, Write};
+
+use date::civil;
+use writer::{Cursor, FmtCursor};
+use {Error, Request, Result};
+
+/// Abbreviated month names, starting with January.
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Access log line format.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum LogFormat {
+    /// Common Log Format: remote host, identity, user, time, Request-Line, status, and
+    /// response size.
+    Common,
+    /// Combined Log Format: the Common Log Format followed by the Referer and User-Agent
+    /// headers.
+    Combined,
+}
+
+/// An access log line for a request and its response.
+///
+/// Lines are formatted through `Display`, so they can be written to any `fmt::Write` or
+/// `io::Write`, or into a caller-provided buffer with `write`. The remote address, user,
+/// and quoted fields have `"` and `\` backslash-escaped and non-printable bytes written
+/// as `\xHH`, so untrusted request contents can't forge log lines.
+#[derive(Copy, Clone, Debug)]
+pub struct LogLine<'a> {
+    /// Request to log.
+    pub request: &'a Request<'a>,
+
+    /// Address or hostname of the remote client.
+    pub remote: &'a str,
+
+    /// Authenticated user, if any.
+    pub user: Option<&'a str>,
+
+    /// Time the request was received, in seconds since the Unix epoch, logged as UTC.
+    pub time: u64,
+
+    /// Response status code.
+    pub status: u16,
+
+    /// Size of the response body, or `None` if no body was sent.
+    pub size: Option<u64>,
+
+    /// Format of the line.
+    pub format: LogFormat,
+}
+
+impl<'a> LogLine<'a> {
+    /// Write the log line, without a trailing newline, into the given buffer.
+    ///
+    /// On success, return the number of bytes written.
+    pub fn write(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut out = Cursor::new(buf);
+        write!(FmtCursor(&mut out), "{}", self).map_err(|_| Error::Capacity)?;
+        Ok(out.pos())
+    }
+}
+
+impl<'a> fmt::Display for LogLine<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = self.request.line();
+
+        let days = self.time / 86400;
+        let secs = self.time % 86400;
+        let (year, month, day) = civil(days);
+
+        escape(f, self.remote.as_bytes())?;
+        f.write_str(" - ")?;
+        escape(f, self.user.unwrap_or("-").as_bytes())?;
+
+        write!(f, " [{:02}/{}/{}:{:02}:{:02}:{:02} +0000] \"",
+            day, MONTHS[month as usize - 1], year, secs / 3600, secs / 60 % 60, secs % 60)?;
+
+        escape(f, line.method.as_bytes())?;
+        f.write_char(' ')?;
+        escape(f, line.target.as_bytes())?;
+        f.write_char(' ')?;
+        escape(f, line.version.as_bytes())?;
+
+        write!(f, "\" {} ", self.status)?;
+
+        match self.size {
+            Some(size) => write!(f, "{}", size)?,
+            None => f.write_char('-')?,
+        }
+
+        if self.format == LogFormat::Combined {
+            for name in &["Referer", "User-Agent"] {
+                f.write_str(" \"")?;
+
+                match self.request.header(name) {
+                    Some(val) => escape(f, val)?,
+                    None => f.write_char('-')?,
+                }
+
+                f.write_char('"')?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Write the given bytes escaped for use within a log field.
+fn escape(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+            0x20..=0x7E => f.write_char(b as char)?,
+            _ => write!(f, "\\x{:02x}", b)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_log_line() {
+        let req = Request::new(b"GET /a\"b\\c\xc3\xa9 HTTP/1.0\r\n\
+                                 Referer: http://example.com/\r\n\
+                                 User-Agent: \"evil\"\r\n\r\n").unwrap();
+
+        let mut line = LogLine {
+            request: &req,
+            remote: "::1",
+            user: Some("frank"),
+            time: 0,
+            status: 404,
+            size: None,
+            format: LogFormat::Common,
+        };
+
+        assert_eq!(line.to_string(), "::1 - frank [01/Jan/1970:00:00:00 +0000] \
+            \"GET /a\\\"b\\\\c\\xc3\\xa9 HTTP/1.0\" 404 -");
+
+        line.format = LogFormat::Combined;
+        line.size = Some(0);
+        line.time = 1485129599;
+
+        assert_eq!(line.to_string(), "::1 - frank [22/Jan/2017:23:59:59 +0000] \
+            \"GET /a\\\"b\\\\c\\xc3\\xa9 HTTP/1.0\" 404 0 \"http://example.com/\" \
+            \"\\\"evil\\\"\"");
+
+        let mut buf = [0; 256];
+        let len = line.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], line.to_string().as_bytes());
+
+        assert_eq!(line.write(&mut buf[..10]), Err(Error::Capacity));
+
+        line.format = LogFormat::Common;
+        line.remote = "1.2.3.4\n5.6.7.8 - x";
+        line.user = Some("a\"\r\nb\\");
+
+        assert_eq!(line.to_string(), "1.2.3.4\\x0a5.6.7.8 - x - a\\\"\\x0d\\x0ab\\\\ \
+            [22/Jan/2017:23:59:59 +0000] \"GET /a\\\"b\\\\c\\xc3\\xa9 HTTP/1.0\" 404 0");
+    }
+}
@@ -0,0 +1,117 @@
+//! Header iteration with names normalized to lowercase.
+
+use std::fmt;
+use std::ops::Deref;
+
+use {Error, Headers, Result};
+
+/// Maximum length of a name that can be lowercased inline.
+pub const MAX_LOWER_NAME: usize = 32;
+
+/// Header name lowercased into an inline buffer.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct LowerName {
+    buf: [u8; MAX_LOWER_NAME],
+    len: usize,
+}
+
+impl LowerName {
+    /// Try to lowercase the given name, failing with `Error::Capacity` if it's longer than
+    /// `MAX_LOWER_NAME` bytes.
+    pub fn new(name: &str) -> Result<Self> {
+        if name.len() > MAX_LOWER_NAME {
+            return Err(Error::Capacity);
+        }
+
+        let mut buf = [0; MAX_LOWER_NAME];
+
+        for (dst, src) in buf.iter_mut().zip(name.bytes()) {
+            *dst = src.to_ascii_lowercase();
+        }
+
+        Ok(LowerName { buf, len: name.len() })
+    }
+
+    /// Retrieve the lowercased name.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII bytes were changed, so this can't fail.
+        std::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl Deref for LowerName {
+    type Target = str;
+
+    fn deref(&self) -> &str { self.as_str() }
+}
+
+impl fmt::Debug for LowerName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.as_str().fmt(f) }
+}
+
+impl PartialEq<str> for LowerName {
+    fn eq(&self, other: &str) -> bool { self.as_str() == other }
+}
+
+impl<'a> PartialEq<&'a str> for LowerName {
+    fn eq(&self, other: &&'a str) -> bool { self.as_str() == *other }
+}
+
+/// A header field with its name lowercased.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct LowerHeader<'a> {
+    /// Header name, with surrounding whitespace trimmed and ASCII letters lowercased.
+    pub name: LowerName,
+
+    /// Raw header value.
+    pub val: &'a [u8],
+}
+
+/// Iterator over header fields with lowercased names.
+pub struct Lowercase<'a>(pub(crate) Headers<'a>);
+
+impl<'a> Iterator for Lowercase<'a> {
+    type Item = Result<LowerHeader<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(|h| Ok(LowerHeader {
+            name: LowerName::new(h.name)?,
+            val: h.val,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lower_name() {
+        let n = LowerName::new("Content-Type").unwrap();
+        assert_eq!(n, "content-type");
+        assert_eq!(n.len(), 12);
+        assert_eq!(format!("{:?}", n), "\"content-type\"");
+
+        assert_eq!(LowerName::new("").unwrap(), "");
+        assert_eq!(LowerName::new("X-\u{e9}A").unwrap(), "x-\u{e9}a");
+
+        let long = "X-Abcdefghijklmnopqrstuvwxyz0123";
+        assert_eq!(LowerName::new(long).unwrap().as_str(), long.to_ascii_lowercase());
+        assert_eq!(LowerName::new(&format!("{}4", long)), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_lowercase() {
+        let mut h = Headers::new(b"HOST: a\r\nx-Y:b\r\n\r\n").lowercase();
+        let x = h.next().unwrap().unwrap();
+        assert_eq!(x.name, "host");
+        assert_eq!(x.val, b" a");
+        assert_eq!(h.next().unwrap().unwrap().name, "x-y");
+        assert!(h.next().is_none());
+
+        let mut h = Headers::new(b"X-Very-Long-Header-Name-For-Testing: a\r\nA: b\r\n\r\n")
+            .lowercase();
+        assert_eq!(h.next(), Some(Err(Error::Capacity)));
+        assert_eq!(h.next().unwrap().unwrap().name, "a");
+    }
+}
@@ -0,0 +1,272 @@
+//! Random-access header map, requiring the `alloc` feature.
+
+use std::borrow::Cow;
+
+use grammar::trim_ows;
+use {Error, Header, Headers, Result};
+
+/// Header fields that may appear at most once in a request, because their values aren't
+/// lists [RFC7230§3.2.2].
+static SINGLETONS: &[&str] = &[
+    "Authorization", "Content-Length", "Content-Type", "Date", "From", "Host",
+    "If-Modified-Since", "If-Range", "If-Unmodified-Since", "Max-Forwards",
+    "Proxy-Authorization", "Range", "Referer", "User-Agent",
+];
+
+/// Header fields whose values are comma-separated lists, so that repeated fields can be
+/// combined [RFC7230§3.2.2].
+static LISTS: &[&str] = &[
+    "Accept", "Accept-Charset", "Accept-Encoding", "Accept-Language", "Cache-Control",
+    "Connection", "Content-Encoding", "Expect", "Forwarded", "If-Match", "If-None-Match",
+    "Pragma", "TE", "Trailer", "Transfer-Encoding", "Upgrade", "Via", "Warning",
+    "X-Forwarded-For",
+];
+
+/// How repeated header fields with the same name are handled.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Duplicates {
+    /// Fail with `Error::Syntax` if the header appears more than once.
+    Reject,
+    /// Combine the values into a single comma-separated list.
+    Merge,
+    /// Keep every value separately, as given.
+    Keep,
+}
+
+/// Table assigning each header name how its duplicates are handled, with names compared
+/// case-insensitively.
+///
+/// Names in neither list are kept as given.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct DuplicatePolicy {
+    /// Names that are rejected when repeated.
+    pub singletons: &'static [&'static str],
+
+    /// Names whose repeated values are merged.
+    pub lists: &'static [&'static str],
+}
+
+impl DuplicatePolicy {
+    /// Create a new `DuplicatePolicy` with the given singleton and list names.
+    pub const fn new(singletons: &'static [&'static str], lists: &'static [&'static str])
+        -> Self
+    {
+        DuplicatePolicy { singletons, lists }
+    }
+
+    /// Create a new `DuplicatePolicy` that keeps every header as given, which is how
+    /// `HeaderMap::new` behaves.
+    pub const fn keep_all() -> Self { DuplicatePolicy::new(&[], &[]) }
+
+    /// Create a new `DuplicatePolicy` for the standard request headers.
+    ///
+    /// Singleton fields such as Host, Content-Length, and Authorization, whose
+    /// duplicates can be read differently by different servers, are rejected even when
+    /// their values agree, and list fields such as Accept and Cache-Control are merged.
+    pub const fn standard() -> Self { DuplicatePolicy::new(SINGLETONS, LISTS) }
+
+    /// Retrieve how duplicates of the header with the given name are handled.
+    pub fn duplicates(&self, name: &str) -> Duplicates {
+        let is = |names: &[&str]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+
+        if is(self.singletons) {
+            Duplicates::Reject
+        } else if is(self.lists) {
+            Duplicates::Merge
+        } else {
+            Duplicates::Keep
+        }
+    }
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> Self { DuplicatePolicy::standard() }
+}
+
+/// Multimap from header names, compared case-insensitively, to their values.
+///
+/// Names are kept in order of first appearance, and each name's values are kept in the
+/// order they appear in the head. Values have surrounding whitespace trimmed. Lookups
+/// scan the entries linearly, which is fast for the handful of headers in a typical
+/// request.
+///
+/// The original header fields are also kept, untrimmed and in wire order, for indexed
+/// access.
+///
+/// A `DuplicatePolicy` decides whether repeated headers are rejected when the map is
+/// collected and how `value` combines them.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct HeaderMap<'a> {
+    entries: Vec<(&'a str, Vec<&'a [u8]>)>,
+    fields: Vec<Header<'a>>,
+    policy: DuplicatePolicy,
+}
+
+impl<'a> HeaderMap<'a> {
+    /// Try to collect all the header fields from the given iterator into a map, keeping
+    /// every duplicate.
+    pub fn new(headers: Headers<'a>) -> Result<Self> {
+        HeaderMap::with_policy(headers, DuplicatePolicy::keep_all())
+    }
+
+    /// Try to collect all the header fields from the given iterator into a map, applying
+    /// the given duplicate policy.
+    ///
+    /// A repeated header that the policy rejects causes `Error::Syntax`.
+    pub fn with_policy(headers: Headers<'a>, policy: DuplicatePolicy) -> Result<Self> {
+        let mut map = HeaderMap { policy, ..HeaderMap::default() };
+
+        for h in headers {
+            let h = h?;
+            let val = trim_ows(h.val);
+
+            map.fields.push(h);
+
+            match map.entries.iter_mut().find(|e| e.0.eq_ignore_ascii_case(h.name)) {
+                Some(_) if policy.duplicates(h.name) == Duplicates::Reject =>
+                    return Err(Error::Syntax),
+                Some(e) => e.1.push(val),
+                None => map.entries.push((h.name, vec![val])),
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Retrieve the first value of the header with the given name.
+    pub fn get(&self, name: &str) -> Option<&'a [u8]> {
+        self.get_all(name).first().cloned()
+    }
+
+    /// Retrieve all values of the header with the given name, which is empty if the
+    /// header isn't present.
+    pub fn get_all(&self, name: &str) -> &[&'a [u8]] {
+        self.entries.iter()
+            .find(|e| e.0.eq_ignore_ascii_case(name))
+            .map_or(&[], |e| &e.1[..])
+    }
+
+    /// Retrieve the value of the header with the given name as the map's policy combines
+    /// it.
+    ///
+    /// The values of a merged header are joined with `, ` into a single list, skipping
+    /// empty ones, and otherwise the first value is returned. A value is only allocated
+    /// when more than one has to be joined.
+    pub fn value(&self, name: &str) -> Option<Cow<'a, [u8]>> {
+        let vals = self.get_all(name);
+
+        if self.policy.duplicates(name) != Duplicates::Merge || vals.len() <= 1 {
+            return vals.first().map(|&v| Cow::Borrowed(v));
+        }
+
+        let mut merged = Vec::new();
+
+        for v in vals.iter().filter(|v| !v.is_empty()) {
+            if !merged.is_empty() {
+                merged.extend_from_slice(b", ");
+            }
+
+            merged.extend_from_slice(v);
+        }
+
+        Some(Cow::Owned(merged))
+    }
+
+    /// Retrieve the duplicate policy the map was collected with.
+    pub fn policy(&self) -> DuplicatePolicy { self.policy }
+
+    /// Retrieve the header field at the given index in wire order, with its raw value.
+    ///
+    /// Fields are indexed from 0 in the order they appeared in the head, regardless of
+    /// name, so iterating over indexes reproduces the original field order.
+    pub fn get_index(&self, idx: usize) -> Option<Header<'a>> {
+        self.fields.get(idx).cloned()
+    }
+
+    /// Retrieve all header fields, with raw values, in wire order.
+    pub fn fields(&self) -> &[Header<'a>] { &self.fields }
+
+    /// Retrieve the number of distinct header names.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Check if the map has no headers.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Create an iterator over each distinct header name, as first spelled in the head,
+    /// along with its values.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &[&'a [u8]])> + '_ {
+        self.entries.iter().map(|e| (e.0, &e.1[..]))
+    }
+}
+
+impl<'a> Default for HeaderMap<'a> {
+    fn default() -> Self {
+        HeaderMap { entries: vec![], fields: vec![], policy: DuplicatePolicy::keep_all() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    #[test]
+    fn test_header_map() {
+        let m = HeaderMap::new(Headers::new(
+            b"Host: a\r\nAccept: x \r\nX-Empty:\r\naccept:  y\r\n\r\n"
+        )).unwrap();
+
+        assert_eq!(m.len(), 3);
+        assert!(!m.is_empty());
+        assert_eq!(m.get("host"), Some(&b"a"[..]));
+        assert_eq!(m.get("ACCEPT"), Some(&b"x"[..]));
+        assert_eq!(m.get_all("Accept"), &[&b"x"[..], &b"y"[..]]);
+        assert_eq!(m.get("X-Empty"), Some(&b""[..]));
+        assert_eq!(m.get("Missing"), None);
+        assert!(m.get_all("Missing").is_empty());
+
+        let names: Vec<_> = m.iter().map(|(n, v)| (n, v.len())).collect();
+        assert_eq!(names, vec![("Host", 1), ("Accept", 2), ("X-Empty", 1)]);
+
+        assert_eq!(m.fields().len(), 4);
+        assert_eq!(m.get_index(0), Some(Header { name: "Host", val: b" a" }));
+        assert_eq!(m.get_index(1), Some(Header { name: "Accept", val: b" x " }));
+        assert_eq!(m.get_index(2), Some(Header { name: "X-Empty", val: b"" }));
+        assert_eq!(m.get_index(3), Some(Header { name: "accept", val: b"  y" }));
+        assert_eq!(m.get_index(4), None);
+
+        assert!(HeaderMap::new(Headers::new(b"\r\n")).unwrap().is_empty());
+        assert_eq!(HeaderMap::new(Headers::new(b"Host: a\r\n")), Err(Error::Partial));
+
+        // Without a policy, nothing is merged or rejected.
+        let m = HeaderMap::new(Headers::new(b"Host: a\r\nHost: b\r\n\r\n")).unwrap();
+        assert_eq!(m.value("Host"), Some(Cow::Borrowed(&b"a"[..])));
+        assert_eq!(m.get_all("Host").len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_policy() {
+        let p = DuplicatePolicy::standard();
+
+        assert_eq!(p.duplicates("host"), Duplicates::Reject);
+        assert_eq!(p.duplicates("ACCEPT"), Duplicates::Merge);
+        assert_eq!(p.duplicates("Cookie"), Duplicates::Keep);
+        assert_eq!(DuplicatePolicy::keep_all().duplicates("Host"), Duplicates::Keep);
+        assert_eq!(DuplicatePolicy::new(&["X-A"], &[]).duplicates("x-a"), Duplicates::Reject);
+
+        let with = |buf: &'static [u8]| HeaderMap::with_policy(Headers::new(buf), p);
+
+        let m = with(b"Host: a\r\nAccept: x\r\nCookie: c=1\r\naccept:\r\n\
+                       ACCEPT: y, z\r\ncookie: d=2\r\n\r\n").unwrap();
+        assert_eq!(m.policy(), p);
+        assert_eq!(m.value("host"), Some(Cow::Borrowed(&b"a"[..])));
+        assert_eq!(m.value("Accept"), Some(Cow::Owned(b"x, y, z".to_vec())));
+        assert_eq!(m.value("Cookie"), Some(Cow::Borrowed(&b"c=1"[..])));
+        assert_eq!(m.get_all("Cookie"), &[&b"c=1"[..], &b"d=2"[..]]);
+        assert_eq!(m.value("Missing"), None);
+
+        assert_eq!(with(b"Host: a\r\nHOST: a\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(with(b"Content-Length: 1\r\nContent-Length: 2\r\n\r\n"),
+            Err(Error::Syntax));
+    }
+}
@@ -0,0 +1,90 @@
+//! Typed request methods.
+
+use grammar::is_token;
+use {Error, Result};
+
+/// A request method [RFC7231§4].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Method<'a> {
+    /// GET [RFC7231§4.3.1].
+    Get,
+    /// HEAD [RFC7231§4.3.2].
+    Head,
+    /// POST [RFC7231§4.3.3].
+    Post,
+    /// PUT [RFC7231§4.3.4].
+    Put,
+    /// DELETE [RFC7231§4.3.5].
+    Delete,
+    /// CONNECT [RFC7231§4.3.6].
+    Connect,
+    /// OPTIONS [RFC7231§4.3.7].
+    Options,
+    /// TRACE [RFC7231§4.3.8].
+    Trace,
+    /// PATCH [RFC5789§2].
+    Patch,
+    /// Any other method token.
+    Extension(&'a str),
+}
+
+impl<'a> Method<'a> {
+    /// Try to parse the given method token.
+    ///
+    /// Methods are case-sensitive [RFC7231§4.1], so only the uppercase forms map to the
+    /// standard methods. Any other valid token becomes an `Extension`.
+    pub fn parse(s: &'a str) -> Result<Self> {
+        use self::Method::*;
+
+        Ok(match s {
+            "GET" => Get,
+            "HEAD" => Head,
+            "POST" => Post,
+            "PUT" => Put,
+            "DELETE" => Delete,
+            "CONNECT" => Connect,
+            "OPTIONS" => Options,
+            "TRACE" => Trace,
+            "PATCH" => Patch,
+            _ if is_token(s.as_bytes()) => Extension(s),
+            _ => return Err(Error::Syntax),
+        })
+    }
+
+    /// Retrieve the method token.
+    pub fn as_str(&self) -> &'a str {
+        use self::Method::*;
+
+        match *self {
+            Get => "GET",
+            Head => "HEAD",
+            Post => "POST",
+            Put => "PUT",
+            Delete => "DELETE",
+            Connect => "CONNECT",
+            Options => "OPTIONS",
+            Trace => "TRACE",
+            Patch => "PATCH",
+            Extension(s) => s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_method() {
+        assert_eq!(Method::parse("GET"), Ok(Method::Get));
+        assert_eq!(Method::parse("PATCH"), Ok(Method::Patch));
+        assert_eq!(Method::parse("get"), Ok(Method::Extension("get")));
+        assert_eq!(Method::parse("PROPFIND"), Ok(Method::Extension("PROPFIND")));
+        assert_eq!(Method::parse(""), Err(Error::Syntax));
+        assert_eq!(Method::parse("G T"), Err(Error::Syntax));
+        assert_eq!(Method::parse("GET\n"), Err(Error::Syntax));
+
+        assert_eq!(Method::Delete.as_str(), "DELETE");
+        assert_eq!(Method::Extension("MKCOL").as_str(), "MKCOL");
+    }
+}
@@ -0,0 +1,176 @@
+//! Metrics recorded for each parsed request head.
+//!
+//! This requires the `metrics` feature.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::metrics::ErrorClass;
+//! use uhttp_request::{Head, ParserConfig};
+//!
+//! let mut ticks = 0;
+//! let mut clock = || { ticks += 5; ticks };
+//!
+//! let buf = b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody";
+//! let (res, m) = Head::with_metrics(buf, ParserConfig::new(), &mut clock);
+//!
+//! assert!(res.is_ok());
+//! assert_eq!(m.duration, 5);
+//! assert_eq!(m.buffered, 31);
+//! assert_eq!(m.head_len, 27);
+//! assert_eq!(m.headers, 1);
+//! assert_eq!(m.error, None);
+//!
+//! let (_, m) = Head::with_metrics(b"GET / HTTP/1.1\r\nHost\r\n\r\n", ParserConfig::new(),
+//!     &mut clock);
+//! assert_eq!(m.error, Some(ErrorClass::Malformed));
+//! assert_eq!(m.error.unwrap().as_str(), "malformed");
+//! ```
+
+use {Error, Head, ParserConfig, Result};
+
+/// Source of timestamps for measuring parse durations.
+///
+/// Timestamps are in caller-defined units, such as nanoseconds from a monotonic clock or
+/// cycle counts, and only their differences are recorded. Any `FnMut() -> u64` closure
+/// is a clock.
+pub trait Clock {
+    /// Retrieve the current timestamp.
+    fn now(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now(&mut self) -> u64 { self() }
+}
+
+/// Coarse classification of a parse error, suited to a low-cardinality metric label.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ErrorClass {
+    /// Input ended before the head was complete.
+    Incomplete,
+    /// Input was malformed.
+    Malformed,
+    /// Input exceeded a configured limit or buffer.
+    Limit,
+    /// Request method wasn't supported.
+    Method,
+    /// Body was framed incorrectly.
+    Body,
+}
+
+impl ErrorClass {
+    /// Retrieve the lowercase name of the class.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorClass::Incomplete => "incomplete",
+            ErrorClass::Malformed => "malformed",
+            ErrorClass::Limit => "limit",
+            ErrorClass::Method => "method",
+            ErrorClass::Body => "body",
+        }
+    }
+}
+
+impl From<Error> for ErrorClass {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Partial => ErrorClass::Incomplete,
+            Error::Syntax | Error::ObsFold => ErrorClass::Malformed,
+            Error::Capacity | Error::ValueTooLong | Error::Budget => ErrorClass::Limit,
+            Error::UnsupportedMethod => ErrorClass::Method,
+            Error::BodyTruncated | Error::BodyTooLong => ErrorClass::Body,
+        }
+    }
+}
+
+/// Compact record of a single parse.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ParseMetrics {
+    /// Time spent parsing, in the units of the clock used.
+    pub duration: u64,
+
+    /// Number of bytes available to the parser.
+    pub buffered: usize,
+
+    /// Length of the parsed head, or 0 if parsing failed.
+    pub head_len: usize,
+
+    /// Number of header fields in the parsed head, or 0 if parsing failed.
+    pub headers: usize,
+
+    /// Class of the error that parsing failed with, if any.
+    pub error: Option<ErrorClass>,
+}
+
+impl<'a> Head<'a> {
+    /// Try to parse the given bytes into a `Head` like `with_config`, timing the parse
+    /// with the given clock.
+    ///
+    /// Return the parse result along with a record of the parse.
+    pub fn with_metrics<C: Clock>(buf: &'a [u8], config: ParserConfig, clock: &mut C)
+        -> (Result<(Self, &'a [u8])>, ParseMetrics)
+    {
+        let start = clock.now();
+        let res = Head::with_config(buf, config);
+        let duration = clock.now().saturating_sub(start);
+
+        let mut metrics = ParseMetrics {
+            duration,
+            buffered: buf.len(),
+            head_len: 0,
+            headers: 0,
+            error: None,
+        };
+
+        match res {
+            Ok((ref head, _)) => {
+                metrics.head_len = head.stats.head_len;
+                metrics.headers = head.stats.headers;
+            },
+            Err(e) => metrics.error = Some(e.into()),
+        }
+
+        (res, metrics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_with_metrics() {
+        let mut ticks = vec![10, 25, 30, 20];
+        let mut clock = || ticks.remove(0);
+
+        let buf = b"GET / HTTP/1.1\r\nA: b\r\nC: d\r\n\r\n";
+        let (res, m) = Head::with_metrics(buf, ParserConfig::new(), &mut clock);
+        assert_eq!(res, Head::new(buf));
+        assert_eq!(m, ParseMetrics {
+            duration: 15,
+            buffered: 30,
+            head_len: 30,
+            headers: 2,
+            error: None,
+        });
+
+        // Clocks that go backwards don't underflow.
+        let (res, m) = Head::with_metrics(b"GET /", ParserConfig::new(), &mut clock);
+        assert_eq!(res, Err(Error::Partial));
+        assert_eq!(m, ParseMetrics {
+            duration: 0,
+            buffered: 5,
+            head_len: 0,
+            headers: 0,
+            error: Some(ErrorClass::Incomplete),
+        });
+    }
+
+    #[test]
+    fn test_error_class() {
+        assert_eq!(ErrorClass::from(Error::Budget), ErrorClass::Limit);
+        assert_eq!(ErrorClass::from(Error::ObsFold), ErrorClass::Malformed);
+        assert_eq!(ErrorClass::from(Error::UnsupportedMethod), ErrorClass::Method);
+        assert_eq!(ErrorClass::from(Error::BodyTooLong).as_str(), "body");
+    }
+}
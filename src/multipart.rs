@@ -0,0 +1,567 @@
+//! Streaming decoding of multipart bodies [RFC2046§5.1], such as `multipart/form-data`
+//! uploads [RFC7578].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::multipart::{boundary, Event, MultipartDecoder};
+//!
+//! let b = boundary(b"multipart/form-data; boundary=XyZ").unwrap();
+//! let mut dec = MultipartDecoder::new(b);
+//!
+//! let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nhello\r\n--XyZ--";
+//! let mut buf = &body[..];
+//!
+//! let (n, e) = dec.decode(buf).unwrap();
+//! assert_eq!(e, Some(Event::Headers(&b"Content-Disposition: form-data; name=\"a\"\r\n\r\n"[..])));
+//! buf = &buf[n..];
+//!
+//! let (n, e) = dec.decode(buf).unwrap();
+//! assert_eq!(e, Some(Event::Data(&b"hello"[..])));
+//! buf = &buf[n..];
+//!
+//! let (n, e) = dec.decode(buf).unwrap();
+//! assert_eq!(e, Some(Event::PartEnd));
+//! buf = &buf[n..];
+//!
+//! assert_eq!(dec.decode(buf).unwrap(), (2, Some(Event::End)));
+//! ```
+
+use memchr::memchr;
+
+use grammar::{is_ows, trim_ows};
+use params;
+use writer::Cursor;
+use {Error, Headers, Result};
+
+/// Maximum length of a boundary [RFC2046§5.1.1].
+pub const MAX_BOUNDARY_LEN: usize = 70;
+
+/// Extract the boundary parameter from the given multipart Content-Type header value.
+pub fn boundary(content_type: &[u8]) -> Result<&[u8]> {
+    let mut p = params::iter(content_type);
+
+    match p.next() {
+        Some(Ok(ty)) if ty.name.len() > 10 &&
+            ty.name.as_bytes().get(..10)
+                .is_some_and(|p| p.eq_ignore_ascii_case(b"multipart/")) => {},
+        _ => return Err(Error::Syntax),
+    }
+
+    for param in p {
+        let param = param?;
+
+        if param.name.eq_ignore_ascii_case("boundary") {
+            let b = params::unquote(param.val.ok_or(Error::Syntax)?);
+            return if b.is_empty() { Err(Error::Syntax) } else { Ok(b) };
+        }
+    }
+
+    Err(Error::Syntax)
+}
+
+/// Check if the given bytes are a valid boundary [RFC2046§5.1.1].
+///
+/// A boundary must be 1 to 70 characters from a restricted set of ASCII letters,
+/// digits, punctuation, and spaces, and must not end with a space.
+pub fn validate_boundary(b: &[u8]) -> Result<()> {
+    if b.is_empty() || b.len() > MAX_BOUNDARY_LEN || b.last() == Some(&b' ') {
+        return Err(Error::Syntax);
+    }
+
+    if b.iter().all(|&c| is_bchar(c)) { Ok(()) } else { Err(Error::Syntax) }
+}
+
+/// Check if the given byte is allowed in a boundary.
+fn is_bchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b)
+}
+
+/// Write a boundary derived from the given random bytes into the given buffer.
+///
+/// Each random byte selects one character of the boundary from a set of 64 characters
+/// that need no quoting in a Content-Type parameter, so the boundary has 6 bits of
+/// entropy per byte. The caller is responsible for supplying enough unpredictable
+/// randomness that the boundary won't appear within any encapsulated content, and for
+/// supplying at most `MAX_BOUNDARY_LEN` bytes.
+///
+/// On success, return the number of bytes written.
+pub fn write_boundary(random: &[u8], buf: &mut [u8]) -> Result<usize> {
+    const CHARS: &[u8; 64] =
+        b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz-_";
+
+    if random.is_empty() || random.len() > MAX_BOUNDARY_LEN {
+        return Err(Error::Syntax);
+    }
+
+    let mut out = Cursor::new(buf);
+
+    for &r in random {
+        out.push(&[CHARS[(r & 0x3F) as usize]])?;
+    }
+
+    Ok(out.pos())
+}
+
+/// Parsed Content-Disposition header value of a multipart part [RFC7578§4.2].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Disposition<'a> {
+    /// Disposition type, such as `form-data`.
+    pub kind: &'a str,
+
+    /// Value of the `name` parameter, with any surrounding quotes stripped but escapes
+    /// left intact.
+    pub name: Option<&'a [u8]>,
+
+    /// Value of the `filename` parameter, with any surrounding quotes stripped but
+    /// escapes left intact.
+    pub filename: Option<&'a [u8]>,
+}
+
+impl<'a> Disposition<'a> {
+    /// Try to parse the given Content-Disposition header value.
+    ///
+    /// Parameter names are compared case-insensitively, and unrecognized parameters are
+    /// ignored.
+    pub fn new(val: &'a [u8]) -> Result<Self> {
+        let mut p = params::iter(val);
+
+        let kind = match p.next() {
+            Some(Ok(p)) if p.val.is_none() => p.name,
+            _ => return Err(Error::Syntax),
+        };
+
+        let mut disp = Disposition { kind, name: None, filename: None };
+
+        for param in p {
+            let param = param?;
+
+            let dest = if param.name.eq_ignore_ascii_case("name") {
+                &mut disp.name
+            } else if param.name.eq_ignore_ascii_case("filename") {
+                &mut disp.filename
+            } else {
+                continue;
+            };
+
+            *dest = Some(params::unquote(param.val.ok_or(Error::Syntax)?));
+        }
+
+        Ok(disp)
+    }
+}
+
+/// Whether a form part carries a plain field value or an uploaded file.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum PartKind {
+    /// Part is a regular form field.
+    Field,
+    /// Part is a file upload.
+    File,
+}
+
+/// Header fields of a multipart part.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Part<'a> {
+    /// Parsed Content-Disposition header, if present.
+    pub disposition: Option<Disposition<'a>>,
+
+    /// Content-Type header value with surrounding whitespace trimmed, if present.
+    pub content_type: Option<&'a [u8]>,
+
+    /// Content-Transfer-Encoding header value with surrounding whitespace trimmed, if
+    /// present [RFC2045§6].
+    ///
+    /// This is deprecated for form data [RFC7578§4.7] but may still be sent by some
+    /// clients.
+    pub transfer_encoding: Option<&'a [u8]>,
+}
+
+impl<'a> Part<'a> {
+    /// Try to parse the given part header block, such as yielded by `Event::Headers`.
+    ///
+    /// If a header appears multiple times, the last one is used.
+    pub fn new(headers: &'a [u8]) -> Result<Self> {
+        let mut part = Part {
+            disposition: None,
+            content_type: None,
+            transfer_encoding: None,
+        };
+
+        for h in Headers::new(headers) {
+            let h = h?;
+
+            if h.name.eq_ignore_ascii_case("content-disposition") {
+                part.disposition = Some(Disposition::new(h.val)?);
+            } else if h.name.eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(trim_ows(h.val));
+            } else if h.name.eq_ignore_ascii_case("content-transfer-encoding") {
+                part.transfer_encoding = Some(trim_ows(h.val));
+            }
+        }
+
+        Ok(part)
+    }
+
+    /// Retrieve the form field name from the Content-Disposition header, if any.
+    pub fn name(&self) -> Option<&'a [u8]> { self.disposition.and_then(|d| d.name) }
+
+    /// Retrieve the uploaded file name from the Content-Disposition header, if any.
+    pub fn filename(&self) -> Option<&'a [u8]> {
+        self.disposition.and_then(|d| d.filename)
+    }
+
+    /// Retrieve the media type of the part's content, which defaults to `text/plain`
+    /// [RFC7578§4.4].
+    pub fn media_type(&self) -> &'a [u8] { self.content_type.unwrap_or(b"text/plain") }
+
+    /// Classify the part as a file upload if it has a filename, or as a regular field
+    /// otherwise.
+    pub fn kind(&self) -> PartKind {
+        if self.filename().is_some() { PartKind::File } else { PartKind::Field }
+    }
+}
+
+/// An event produced while decoding a multipart body.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Event<'a> {
+    /// A new part begins with the given header block, including its terminating empty
+    /// line, which can be parsed with `Headers::new`.
+    Headers(&'a [u8]),
+    /// Some of the body of the current part.
+    Data(&'a [u8]),
+    /// The current part is complete.
+    PartEnd,
+    /// The final delimiter was reached, and any following bytes are an epilogue to be
+    /// ignored.
+    End,
+}
+
+/// Current position within the multipart body.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum State {
+    /// At the very start, where the first delimiter may lack its leading CRLF.
+    Start,
+    /// Within the preamble before the first delimiter.
+    Preamble,
+    /// Directly after a delimiter.
+    Delimiter,
+    /// At the start of a part's header block.
+    Headers,
+    /// Within a part's body.
+    Body,
+    /// After the final delimiter.
+    Done,
+}
+
+/// Multipart body decoder that can be fed successive buffers.
+///
+/// The decoder doesn't buffer anything itself. Instead, each call to `decode` reports how
+/// many bytes at the start of the given buffer were consumed, and the caller should keep
+/// any unconsumed bytes and append newly received ones to them before calling again.
+pub struct MultipartDecoder<'b> {
+    boundary: &'b [u8],
+    state: State,
+}
+
+impl<'b> MultipartDecoder<'b> {
+    /// Create a new `MultipartDecoder` for a body with the given boundary, such as
+    /// returned by `boundary`.
+    pub const fn new(boundary: &'b [u8]) -> Self {
+        MultipartDecoder {
+            boundary,
+            state: State::Start,
+        }
+    }
+
+    /// Check if the final delimiter has been reached.
+    pub fn is_done(&self) -> bool { self.state == State::Done }
+
+    /// Try to decode the next event from the given buffer.
+    ///
+    /// On success, return `Ok((consumed, event))`, where `consumed` is the number of
+    /// bytes at the start of the buffer that were processed. If `event` is `None`, more
+    /// bytes are needed before the next event can be produced. Data is yielded as soon as
+    /// it can't be part of a delimiter, so a part's body may arrive as several `Data`
+    /// events. Once the final delimiter is reached, all further bytes are consumed
+    /// without producing events.
+    pub fn decode<'a>(&mut self, buf: &'a [u8]) -> Result<(usize, Option<Event<'a>>)> {
+        let mut pos = 0;
+
+        loop {
+            let rest = &buf[pos..];
+
+            match self.state {
+                State::Start => match self.match_at(rest, 2) {
+                    Some(true) => {
+                        pos += self.delim_len() - 2;
+                        self.state = State::Delimiter;
+                    },
+                    Some(false) => self.state = State::Preamble,
+                    None => return Ok((pos, None)),
+                },
+                State::Preamble => match self.find(rest) {
+                    Found::At(idx) => {
+                        pos += idx + self.delim_len();
+                        self.state = State::Delimiter;
+                    },
+                    Found::Partial(idx) => return Ok((pos + idx, None)),
+                },
+                State::Delimiter => {
+                    if rest.starts_with(b"--") {
+                        self.state = State::Done;
+                        return Ok((pos + 2, Some(Event::End)));
+                    }
+
+                    // Skip transport padding up to the CRLF.
+                    let ws = rest.iter().take_while(|&&b| is_ows(b)).count();
+
+                    match rest.get(ws..ws + 2) {
+                        Some(b"\r\n") => {
+                            pos += ws + 2;
+                            self.state = State::Headers;
+                        },
+                        Some(_) => return Err(Error::Syntax),
+                        // A lone `-` may still begin the final `--`.
+                        None if rest.get(ws).is_none_or(|&b| b == b'\r') ||
+                            rest == b"-" => return Ok((pos, None)),
+                        None => return Err(Error::Syntax),
+                    }
+                },
+                State::Headers => {
+                    let end = if rest.starts_with(b"\r\n") {
+                        Some(2)
+                    } else {
+                        rest.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
+                    };
+
+                    return Ok(match end {
+                        Some(end) => {
+                            self.state = State::Body;
+                            (pos + end, Some(Event::Headers(&rest[..end])))
+                        },
+                        None => (pos, None),
+                    });
+                },
+                State::Body => {
+                    return Ok(match self.find(rest) {
+                        Found::At(0) => {
+                            self.state = State::Delimiter;
+                            (pos + self.delim_len(), Some(Event::PartEnd))
+                        },
+                        Found::At(idx) | Found::Partial(idx) if idx > 0 =>
+                            (pos + idx, Some(Event::Data(&rest[..idx]))),
+                        _ => (pos, None),
+                    });
+                },
+                State::Done => return Ok((buf.len(), None)),
+            }
+        }
+    }
+
+    /// Length of the full delimiter, including its leading CRLF.
+    fn delim_len(&self) -> usize { 4 + self.boundary.len() }
+
+    /// Retrieve the byte at the given index of the full delimiter.
+    fn delim_byte(&self, idx: usize) -> u8 {
+        match idx {
+            0..=3 => b"\r\n--"[idx],
+            _ => self.boundary[idx - 4],
+        }
+    }
+
+    /// Check if the given bytes begin with the delimiter, starting from the given index
+    /// within the delimiter, returning `None` if they're a proper prefix of it.
+    fn match_at(&self, bytes: &[u8], skip: usize) -> Option<bool> {
+        for (i, &b) in bytes.iter().enumerate().take(self.delim_len() - skip) {
+            if b != self.delim_byte(skip + i) {
+                return Some(false);
+            }
+        }
+
+        if bytes.len() >= self.delim_len() - skip { Some(true) } else { None }
+    }
+
+    /// Find the first full or partial occurrence of the delimiter in the given bytes.
+    fn find(&self, bytes: &[u8]) -> Found {
+        let mut pos = 0;
+
+        while let Some(idx) = memchr(b'\r', &bytes[pos..]) {
+            match self.match_at(&bytes[pos + idx..], 0) {
+                Some(true) => return Found::At(pos + idx),
+                Some(false) => pos += idx + 1,
+                None => return Found::Partial(pos + idx),
+            }
+        }
+
+        Found::Partial(bytes.len())
+    }
+}
+
+/// Result of searching for a delimiter.
+enum Found {
+    /// A full delimiter begins at the given offset.
+    At(usize),
+    /// No full delimiter was found, but the bytes from the given offset to the end may
+    /// begin one.
+    Partial(usize),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Decode the given body fed in chunks of the given size, collecting events with
+    /// adjacent data merged.
+    fn decode_all(body: &[u8], boundary: &[u8], chunk: usize) -> Vec<String> {
+        let mut dec = MultipartDecoder::new(boundary);
+        let mut buf = Vec::new();
+        let mut events: Vec<String> = Vec::new();
+        let mut fed = 0;
+
+        loop {
+            let (n, e) = dec.decode(&buf).unwrap();
+
+            let e = e.map(|e| match e {
+                Event::Headers(h) => format!("H:{}", String::from_utf8_lossy(h)),
+                Event::Data(d) => format!("D:{}", String::from_utf8_lossy(d)),
+                Event::PartEnd => "E".to_string(),
+                Event::End => "END".to_string(),
+            });
+
+            buf.drain(..n);
+
+            match e {
+                Some(e) => match (events.last_mut(), e.starts_with("D:")) {
+                    (Some(last), true) if last.starts_with("D:") =>
+                        last.push_str(&e[2..]),
+                    _ => events.push(e),
+                },
+                None if fed < body.len() => {
+                    let end = std::cmp::min(fed + chunk, body.len());
+                    buf.extend_from_slice(&body[fed..end]);
+                    fed = end;
+                },
+                None => break,
+            }
+        }
+
+        events
+    }
+
+    #[test]
+    fn test_boundary() {
+        assert_eq!(boundary(b"multipart/form-data; boundary=abc"), Ok(&b"abc"[..]));
+        assert_eq!(boundary(b"Multipart/Mixed;BOUNDARY=\"a b;c\""), Ok(&b"a b;c"[..]));
+        assert_eq!(boundary(b"multipart/form-data"), Err(Error::Syntax));
+        assert_eq!(boundary(b"multipart/form-data; boundary=\"\""), Err(Error::Syntax));
+        assert_eq!(boundary(b"text/plain; boundary=abc"), Err(Error::Syntax));
+        assert_eq!(boundary("multipar\u{e9}/x; boundary=abc".as_bytes()), Err(Error::Syntax));
+        assert_eq!(boundary("multipart\u{e9}x; boundary=abc".as_bytes()), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_validate_boundary() {
+        assert_eq!(validate_boundary(b"abc"), Ok(()));
+        assert_eq!(validate_boundary(b"'()+_,-./:=? x"), Ok(()));
+        assert_eq!(validate_boundary(&[b'a'; 70]), Ok(()));
+        assert_eq!(validate_boundary(&[b'a'; 71]), Err(Error::Syntax));
+        assert_eq!(validate_boundary(b""), Err(Error::Syntax));
+        assert_eq!(validate_boundary(b"abc "), Err(Error::Syntax));
+        assert_eq!(validate_boundary(b"a;b"), Err(Error::Syntax));
+        assert_eq!(validate_boundary(b"a\"b"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_write_boundary() {
+        let mut buf = [0; 80];
+
+        let len = write_boundary(&[0, 10, 36, 62, 63, 64, 255], &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"0Aa-_0_");
+        assert_eq!(validate_boundary(&buf[..len]), Ok(()));
+
+        assert_eq!(write_boundary(&[7; 70], &mut buf), Ok(70));
+        assert_eq!(write_boundary(&[7; 71], &mut buf), Err(Error::Syntax));
+        assert_eq!(write_boundary(&[], &mut buf), Err(Error::Syntax));
+        assert_eq!(write_boundary(&[7; 4], &mut buf[..3]), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_disposition() {
+        assert_eq!(Disposition::new(b"form-data; NAME=\"a;b\"; filename=x.txt").unwrap(),
+            Disposition {
+                kind: "form-data",
+                name: Some(b"a;b"),
+                filename: Some(b"x.txt"),
+            });
+
+        assert_eq!(Disposition::new(b"attachment; size=3").unwrap(), Disposition {
+            kind: "attachment",
+            name: None,
+            filename: None,
+        });
+
+        assert_eq!(Disposition::new(b"").unwrap_err(), Error::Syntax);
+        assert_eq!(Disposition::new(b"a=b").unwrap_err(), Error::Syntax);
+        assert_eq!(Disposition::new(b"form-data; name").unwrap_err(), Error::Syntax);
+    }
+
+    #[test]
+    fn test_part() {
+        let p = Part::new(b"Content-Disposition: form-data; name=\"f\"; filename=\"a.png\"\r\n\
+                            content-type:  image/png \r\n\r\n").unwrap();
+        assert_eq!(p.name(), Some(&b"f"[..]));
+        assert_eq!(p.filename(), Some(&b"a.png"[..]));
+        assert_eq!(p.media_type(), b"image/png");
+        assert_eq!(p.transfer_encoding, None);
+        assert_eq!(p.kind(), PartKind::File);
+
+        let p = Part::new(b"Content-Disposition: form-data; name=q\r\n\
+                            Content-Transfer-Encoding: 8bit\r\n\r\n").unwrap();
+        assert_eq!(p.name(), Some(&b"q"[..]));
+        assert_eq!(p.media_type(), b"text/plain");
+        assert_eq!(p.transfer_encoding, Some(&b"8bit"[..]));
+        assert_eq!(p.kind(), PartKind::Field);
+
+        let p = Part::new(b"\r\n").unwrap();
+        assert_eq!(p.disposition, None);
+        assert_eq!(p.name(), None);
+        assert_eq!(p.kind(), PartKind::Field);
+
+        assert_eq!(Part::new(b"Content-Disposition: ;\r\n\r\n"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_decode() {
+        let body = b"preamble\r\n--b\r\nA: 1\r\n\r\nxy\r\n--\r\n-b\r\n\r\n--b \t\r\n\r\n\
+                     z\r\n--b--\r\nepilogue";
+
+        let expected = vec![
+            "H:A: 1\r\n\r\n", "D:xy\r\n--\r\n-b\r\n", "E", "H:\r\n", "D:z", "E", "END",
+        ];
+
+        for chunk in 1..body.len() + 1 {
+            assert_eq!(decode_all(body, b"b", chunk), expected, "chunk {}", chunk);
+        }
+
+        assert_eq!(decode_all(b"--b\r\n\r\n\r\n--b--", b"b", 100),
+            vec!["H:\r\n", "E", "END"]);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut dec = MultipartDecoder::new(b"b");
+        assert_eq!(dec.decode(b"--bx\r\n"), Err(Error::Syntax));
+
+        let mut dec = MultipartDecoder::new(b"b");
+        assert_eq!(dec.decode(b"--b \r"), Ok((3, None)));
+        assert_eq!(dec.decode(b" x"), Err(Error::Syntax));
+
+        let mut dec = MultipartDecoder::new(b"b");
+        assert_eq!(dec.decode(b"--b"), Ok((3, None)));
+        assert_eq!(dec.decode(b"-"), Ok((0, None)));
+        assert_eq!(dec.decode(b"--"), Ok((2, Some(Event::End))));
+        assert!(dec.is_done());
+        assert_eq!(dec.decode(b"anything"), Ok((8, None)));
+    }
+}
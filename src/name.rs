@@ -0,0 +1,103 @@
+//! Case-insensitive header names.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// Header name that compares and hashes ASCII case-insensitively, as header names are
+/// defined to be [RFC7230§3.2].
+///
+/// ## Example
+///
+/// ```rust
+/// use uhttp_request::{Headers, HeaderName};
+///
+/// let mut h = Headers::new(b"Content-Length: 4\r\n\r\n");
+/// let name = h.next().unwrap().unwrap().header_name();
+///
+/// assert_eq!(name, "content-length");
+/// assert_eq!(name, HeaderName::new("CONTENT-LENGTH"));
+/// assert_eq!(name.as_str(), "Content-Length");
+/// ```
+#[derive(Copy, Clone)]
+pub struct HeaderName<'a>(&'a str);
+
+impl<'a> HeaderName<'a> {
+    /// Create a new `HeaderName` wrapping the given name.
+    pub const fn new(name: &'a str) -> Self { HeaderName(name) }
+
+    /// Retrieve the name as it was given, with its original case.
+    pub const fn as_str(&self) -> &'a str { self.0 }
+}
+
+impl<'a> From<&'a str> for HeaderName<'a> {
+    fn from(name: &'a str) -> Self { HeaderName(name) }
+}
+
+impl<'a> Deref for HeaderName<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str { self.0 }
+}
+
+impl<'a> fmt::Debug for HeaderName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.0.fmt(f) }
+}
+
+impl<'a> fmt::Display for HeaderName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.0.fmt(f) }
+}
+
+impl<'a, 'b> PartialEq<HeaderName<'b>> for HeaderName<'a> {
+    fn eq(&self, other: &HeaderName<'b>) -> bool { self.0.eq_ignore_ascii_case(other.0) }
+}
+
+impl<'a> Eq for HeaderName<'a> {}
+
+impl<'a> PartialEq<str> for HeaderName<'a> {
+    fn eq(&self, other: &str) -> bool { self.0.eq_ignore_ascii_case(other) }
+}
+
+impl<'a, 'b> PartialEq<&'b str> for HeaderName<'a> {
+    fn eq(&self, other: &&'b str) -> bool { self.0.eq_ignore_ascii_case(other) }
+}
+
+impl<'a> Hash for HeaderName<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+
+        // Terminate like `str` so adjacent names in a tuple don't collide.
+        state.write_u8(0xff);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(name: HeaderName) -> u64 {
+        let mut h = DefaultHasher::new();
+        name.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn test_header_name() {
+        let a = HeaderName::new("Content-Length");
+        let b = HeaderName::from("content-LENGTH");
+
+        assert_eq!(a, b);
+        assert_eq!(a, "CONTENT-LENGTH");
+        assert_eq!(a, *"content-length");
+        assert!(a != "Content-Type");
+        assert!(a != HeaderName::new("Content-Lengt"));
+        assert_eq!(hash(a), hash(b));
+        assert!(hash(a) != hash(HeaderName::new("Content-Type")));
+        assert_eq!(a.as_str(), "Content-Length");
+        assert_eq!(a.len(), 14);
+        assert_eq!(format!("{:?} {}", a, b), "\"Content-Length\" content-LENGTH");
+    }
+}
@@ -0,0 +1,232 @@
+//! Harness checking that the parsers never panic on untrusted input.
+//!
+//! Each entry point is run over every prefix of some sample inputs and over every
+//! variant with a single byte deleted or replaced by a byte significant to the grammar,
+//! so any panicking index or overflow in the scanning code fails the test suite. Heads
+//! and bodies are drawn from one corpus and header field values from another, which
+//! feeds every public field parser.
+
+use {accept, auth, body, cache, chunked, content, cookie, cors, date, etag, from, host};
+use {icap, idempotency, legacy, lifecycle, list, location, multipart, params, path};
+use {percent, privacy, proxy, query, range, response, sniff, suspicion, upgrade};
+use {websocket, writer};
+use {Head, Headers, Method, ParserConfig, Progress, Request, RequestLine, StatusLine};
+use Version;
+#[cfg(feature = "alloc")]
+use {HeadStore, RequestBuf};
+
+/// Sample inputs covering the parsed grammars.
+const CORPUS: &[&[u8]] = &[
+    b"GET / HTTP/1.1\r\nHost: a\r\n\r\n",
+    b"\r\n\r\nPOST /a?b=c HTTP/1.0\r\nA:b\r\n  C : d \r\nProxy-Connection: x\r\n\r\nbody",
+    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    b"5;a=\"b;c\"\r\nhello\r\n0\r\nX: y\r\n\r\n",
+    b"--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--b--\r\n",
+];
+
+/// Sample header field values covering the parsed value grammars.
+const VALUES: &[&[u8]] = &[
+    b"gzip;q=0.5, *;q=0, br",
+    b"text/html;level=1;q=0.7, */*;q=0.1",
+    b"en-US, fr;q=0.8",
+    b"Basic YWxhZGRpbjpvcGVuc2VzYW1l",
+    b"Digest realm=\"a\", nonce=\"b\\\"c\", Bearer",
+    b"W/\"abc\", \"def\", *",
+    b"bytes=0-1,5-,-3",
+    b"Sun, 06 Nov 1994 08:49:37 GMT",
+    b"[::1]:8080",
+    b"example.com:80",
+    b"form-data; name=\"a\"; filename=\"b.txt\"",
+    b"a=1; Path=/; Max-Age=10; HttpOnly",
+    b"<https://a/b>; rel=\"deprecation sunset\"",
+    b"/a/b/../c?x=1&y=%20z+w%e9",
+    b"h3=\":443\"; ma=86400, h2=\"alt:443\"",
+    b"user@example.com",
+    b"req-hdr=0, req-body=12, null-body=20",
+    b"@1688169599",
+    b"\"8e03978e-40d5-43e8-bc93-6894a57f9324\"",
+    b"websocket, h2c, foo/1",
+    "caf\u{e9}.example".as_bytes(),
+];
+
+/// Bytes substituted into the corpus.
+const SPECIAL: &[u8] = b"\r\n: \t;\"-0\x00\x7f\xff";
+
+/// Bytes substituted into the field values.
+const VALUE_SPECIAL: &[u8] = b" ,;=\"\\/*[]:%@<>\xc3\xff";
+
+/// Run the given function over every variant of every corpus entry.
+fn each_variant<F: FnMut(&[u8])>(f: F) {
+    variants(CORPUS, SPECIAL, f)
+}
+
+/// Run the given function over every variant of every sample input, substituting the
+/// given bytes.
+fn variants<F: FnMut(&[u8])>(corpus: &[&[u8]], special: &[u8], mut f: F) {
+    let mut buf = Vec::new();
+
+    for input in corpus {
+        for end in 0..input.len() + 1 {
+            f(&input[..end]);
+        }
+
+        for i in 0..input.len() {
+            buf.clear();
+            buf.extend_from_slice(&input[..i]);
+            buf.extend_from_slice(&input[i + 1..]);
+            f(&buf);
+
+            for &b in special {
+                buf.clear();
+                buf.extend_from_slice(input);
+                buf[i] = b;
+                f(&buf);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_no_panic_head() {
+    let configs = [
+        ParserConfig::new(),
+        ParserConfig::strict_rfc9112(),
+        ParserConfig::lenient_browser(),
+        ParserConfig::embedded_minimal(),
+        ParserConfig {
+            max_scan_len: Some(20),
+            max_value_len: Some(2),
+            ..ParserConfig::new()
+        },
+    ];
+
+    each_variant(|buf| {
+        for &config in &configs {
+            let _ = RequestLine::with_config(buf, config);
+            let _ = Progress::with_config(buf, config);
+            let _ = Head::with_details(buf, config);
+
+            if let Ok((head, _)) = Head::with_config(buf, config) {
+                assert_eq!(head.headers().filter(|h| h.is_err()).count(), 0);
+            }
+
+            let h = Headers::with_config(buf, config);
+            let _ = h.contains("host");
+
+            // Errors may be yielded forever, so stop at the first.
+            let _ = h.take_while(|h| h.is_ok()).count();
+        }
+
+        let _ = Request::new(buf).map(|r| r.content_length());
+        let _ = Request::new(buf).map(|r| body::BodyVerifier::new(&r));
+        #[cfg(feature = "alloc")]
+        let _ = RequestBuf::new(buf);
+        let _ = StatusLine::new(buf);
+        let _ = response::parse_status(buf, response::ResponseConfig::strict());
+        let _ = writer::write_original(buf, &mut [0; 64]);
+        let _ = writer::validate(buf);
+        let _ = writer::write_edited(buf, &[], &mut [0; 64]);
+        let _ = proxy::strip_hop_by_hop(buf).map(|h| h.count());
+        let _ = proxy::write_origin_form(buf, &mut [0; 64]);
+        let _ = proxy::write_absolute_form(buf, "http", &mut [0; 64]);
+        let _ = sniff::classify(buf);
+        let _ = response::heads(buf, response::ResponseConfig::strict()).take(8).count();
+
+        if let Ok((head, _)) = Head::new(buf) {
+            let _ = suspicion::Suspicion::new(&head);
+            #[cfg(feature = "alloc")]
+            let _ = HeadStore::new(&head);
+            let _ = cache::cache_key(&head, &["accept", "host"]);
+            let _ = upgrade::requested_protocol(&head);
+            let _ = legacy::method_override(head.headers());
+        }
+    });
+}
+
+#[test]
+fn test_no_panic_values() {
+    variants(VALUES, VALUE_SPECIAL, |val| {
+        let mut out = [0; 32];
+
+        let _ = accept::encodings(val).take(8).count();
+        let _ = accept::language_ranges(val).take(8).count();
+        let _ = accept::media_ranges(val).take(8).count();
+        let _ = accept::negotiate_encoding(val, &[("gzip", 1.0), ("br", 0.5)]);
+        let _ = accept::negotiate_charset(val, &["utf-8"]);
+        let _ = accept::negotiate_media_type(val, &["text/html", "*/*"]);
+        let _ = auth::parse_authorization(val).map(|a| a.basic(&mut [0; 16]).is_ok());
+        let _ = auth::challenges(val).take(8).count();
+        let _ = body::parse_content_length(val);
+        let _ = body::transfer_encoding(val).is_chunked();
+        let _ = content::languages(val).take(8).count();
+        let _ = content::ContentLocation::new(val);
+        let _ = cookie::parse_set_cookie(val);
+        let _ = cors::first_disallowed(val, &["content-type"]);
+        let _ = date::parse(val);
+        let _ = etag::EntityTag::new(val);
+
+        if let Ok(etag::TagList::Tags(t)) = etag::tag_list(val) {
+            let _ = t.take(8).count();
+        }
+
+        let _ = from::addr_spec(val);
+        let _ = host::parse_host(val);
+        let _ = icap::encapsulated(val).take(8).count();
+        let _ = idempotency::parse_idempotency_key(val, 16);
+        let _ = legacy::soap_action(val);
+        let _ = lifecycle::parse_deprecation(val);
+        let _ = lifecycle::links(val).take(8).count();
+        let _ = lifecycle::deprecation_link(val);
+        let _ = list::values(val).take(8).count();
+        let _ = location::resolve(b"/a/b?c", val, &mut out);
+        let _ = location::resolve(val, b"../d", &mut out);
+        let _ = multipart::boundary(val);
+        let _ = multipart::validate_boundary(val);
+        let _ = multipart::Disposition::new(val);
+        let _ = multipart::Part::new(val);
+        let _ = params::iter(val).take(8).count();
+        let _ = params::parameterized(val);
+        let _ = params::unquote(val);
+        let _ = path::PathSegments::new(val).count();
+        let _ = path::safe_path(val, "/srv");
+        let _ = percent::decode(val, &mut out);
+        let _ = percent::decoded(val).count();
+        let _ = percent::check_utf8(val, percent::Utf8Policy::Require);
+        let _ = privacy::parse_dnt(val);
+        let _ = privacy::parse_sec_gpc(val);
+        let _ = query::QueryParams::new(val).count();
+        let _ = query::decode_form(val, &mut out);
+        let _ = range::byte_ranges(val).map(|r| r.take(8).count());
+
+        if let Ok(response::AltSvc::Services(s)) = response::alt_svc(val) {
+            let _ = s.take(8).count();
+        }
+
+        let _ = upgrade::protocols(val).take(8).count();
+        let _ = websocket::versions(val).take(8).count();
+
+        if let Ok(s) = std::str::from_utf8(val) {
+            let _ = host::split(s);
+            let _ = host::HostPattern::new(s);
+            let _ = host::HostPattern::new("*.ab").map(|p| p.matches(s));
+            let _ = upgrade::masque_udp_target(s);
+            let _ = Method::parse(s);
+            let _ = Version::parse(s);
+        }
+    });
+}
+
+#[test]
+fn test_no_panic_body() {
+    each_variant(|buf| {
+        let _ = chunked::decode(buf, &mut [0; 16]);
+        let _ = chunked::chunks(buf).count();
+
+        let mut dec = multipart::MultipartDecoder::new(b"b");
+        let mut pos = 0;
+
+        while let Ok((n, Some(_))) = dec.decode(&buf[pos..]) {
+            pos += n;
+        }
+    });
+}
@@ -0,0 +1,264 @@
+//! Splitting of semicolon-delimited parameters, as used in media types, Content-Disposition,
+//! cookies, and chunk extensions.
+//!
+//! Values made of a base followed by parameters, such as media types, can be split with
+//! `parameterized`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::params;
+//!
+//! let mut p = params::iter(b"text/html; charset=utf-8; boundary=\"a;b\"");
+//!
+//! let x = p.next().unwrap().unwrap();
+//! assert_eq!(x.name, "text/html");
+//! assert_eq!(x.val, None);
+//!
+//! let x = p.next().unwrap().unwrap();
+//! assert_eq!(x.name, "charset");
+//! assert_eq!(x.val, Some(&b"utf-8"[..]));
+//!
+//! let x = p.next().unwrap().unwrap();
+//! assert_eq!(x.name, "boundary");
+//! assert_eq!(x.val, Some(&b"\"a;b\""[..]));
+//! assert_eq!(params::unquote(x.val.unwrap()), b"a;b");
+//!
+//! assert!(p.next().is_none());
+//! ```
+
+use grammar::{is_token, trim_ows};
+use {Error, Result};
+
+/// A single parameter.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Param<'a> {
+    /// Parameter name, with surrounding whitespace trimmed.
+    ///
+    /// This isn't validated as a token, so it may be something like `text/html` when
+    /// iterating over a media type.
+    pub name: &'a str,
+
+    /// Raw parameter value following the `=`, with surrounding whitespace trimmed, or
+    /// `None` if there's no `=`.
+    ///
+    /// Quoted values are returned with their quotes and escapes intact, so `unquote` can
+    /// be used to strip them.
+    pub val: Option<&'a [u8]>,
+}
+
+/// Create an iterator over the semicolon-delimited parameters in the given value.
+///
+/// Semicolons within quoted-strings [RFC7230§3.2.6] don't split parameters, and empty
+/// parameters are skipped.
+pub fn iter<'a>(val: &'a [u8]) -> Params<'a> {
+    Params(Some(val))
+}
+
+/// Iterator over semicolon-delimited parameters.
+#[derive(Clone, Debug)]
+pub struct Params<'a>(Option<&'a [u8]>);
+
+impl<'a> Iterator for Params<'a> {
+    type Item = Result<Param<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.0?;
+
+            let (param, next) = match split_unquoted(rest, b';') {
+                Ok(x) => x,
+                Err(e) => {
+                    self.0 = None;
+                    return Some(Err(e));
+                },
+            };
+
+            self.0 = next;
+
+            let param = trim_ows(param);
+
+            if param.is_empty() {
+                continue;
+            }
+
+            let (name, val) = match param.iter().position(|&b| b == b'=') {
+                Some(idx) => (&param[..idx], Some(trim_ows(&param[idx + 1..]))),
+                None => (param, None),
+            };
+
+            return Some(match std::str::from_utf8(trim_ows(name)) {
+                Ok(name) if !name.is_empty() => Ok(Param { name, val }),
+                _ => Err(Error::Syntax),
+            });
+        }
+    }
+}
+
+/// A value made of a base followed by semicolon-delimited parameters, as in media types
+/// [RFC7231§3.1.1.1] and Content-Disposition [RFC6266§4.1].
+#[derive(Clone, Debug)]
+pub struct Parameterized<'a> {
+    /// Base before the first parameter, with surrounding whitespace trimmed, such as
+    /// `text/html` or `attachment`.
+    pub base: &'a str,
+
+    /// Iterator over the parameters that follow the base.
+    pub params: Params<'a>,
+}
+
+/// Try to split the given value into its base and parameters.
+///
+/// The base must be nonempty and can't contain `=`. Parameters are only parsed as they're
+/// iterated.
+pub fn parameterized<'a>(val: &'a [u8]) -> Result<Parameterized<'a>> {
+    let (base, rest) = split_unquoted(val, b';')?;
+    let base = trim_ows(base);
+
+    if base.is_empty() || base.contains(&b'=') {
+        return Err(Error::Syntax);
+    }
+
+    Ok(Parameterized {
+        base: std::str::from_utf8(base).map_err(|_| Error::Syntax)?,
+        params: Params(rest),
+    })
+}
+
+impl<'a> Parameterized<'a> {
+    /// Try to split the base into the type and subtype of a media type [RFC7231§3.1.1.1],
+    /// each of which must be a token.
+    pub fn media_type(&self) -> Result<(&'a str, &'a str)> {
+        match self.base.split_once('/') {
+            Some((ty, sub)) if is_token(ty.as_bytes()) && is_token(sub.as_bytes()) =>
+                Ok((ty, sub)),
+            _ => Err(Error::Syntax),
+        }
+    }
+
+    /// Try to find the value of the first parameter with the given name, compared
+    /// case-insensitively, with any surrounding quotes stripped by `unquote`.
+    ///
+    /// Return `Ok(None)` if there's no such parameter, and `Ok(Some(b""))` if it has no
+    /// value.
+    pub fn param(&self, name: &str) -> Result<Option<&'a [u8]>> {
+        for p in self.params.clone() {
+            let p = p?;
+
+            if p.name.eq_ignore_ascii_case(name) {
+                return Ok(Some(p.val.map(unquote).unwrap_or_default()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Strip the surrounding quotes from the given value if it's a quoted-string, leaving
+/// any backslash escapes intact.
+pub fn unquote(val: &[u8]) -> &[u8] {
+    if val.len() >= 2 && val[0] == b'"' && val[val.len() - 1] == b'"' {
+        &val[1..val.len() - 1]
+    } else {
+        val
+    }
+}
+
+/// Split the given bytes at the first occurrence of the given delimiter outside of any
+/// quoted-string, returning the bytes before the delimiter and the bytes after it, if
+/// the delimiter was found.
+pub(crate) fn split_unquoted(bytes: &[u8], delim: u8) -> Result<(&[u8], Option<&[u8]>)> {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, &b) in bytes.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if quoted {
+            match b {
+                b'\\' => escaped = true,
+                b'"' => quoted = false,
+                _ => {},
+            }
+        } else if b == b'"' {
+            quoted = true;
+        } else if b == delim {
+            return Ok((&bytes[..i], Some(&bytes[i + 1..])));
+        }
+    }
+
+    if quoted {
+        Err(Error::Syntax)
+    } else {
+        Ok((bytes, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter() {
+        let mut p = iter(b" attachment ; filename=\"a\\\";b.txt\" ;; size = 12;x=;");
+
+        assert_eq!(p.next(), Some(Ok(Param { name: "attachment", val: None })));
+        assert_eq!(p.next(), Some(Ok(Param {
+            name: "filename",
+            val: Some(&b"\"a\\\";b.txt\""[..]),
+        })));
+        assert_eq!(p.next(), Some(Ok(Param { name: "size", val: Some(&b"12"[..]) })));
+        assert_eq!(p.next(), Some(Ok(Param { name: "x", val: Some(&b""[..]) })));
+        assert_eq!(p.next(), None);
+
+        let mut p = iter(b"a=1; b=\"unterminated; c=3");
+        assert_eq!(p.next(), Some(Ok(Param { name: "a", val: Some(&b"1"[..]) })));
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), None);
+
+        let mut p = iter(b"=1");
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+
+        assert_eq!(iter(b"").next(), None);
+        assert_eq!(iter(b" ; ").next(), None);
+    }
+
+    #[test]
+    fn test_parameterized() {
+        let v = parameterized(b" Text/HTML ;charset=\"utf-8\"; q ; x=\"a;b\"").unwrap();
+        assert_eq!(v.base, "Text/HTML");
+        assert_eq!(v.media_type(), Ok(("Text", "HTML")));
+        assert_eq!(v.param("CHARSET"), Ok(Some(&b"utf-8"[..])));
+        assert_eq!(v.param("q"), Ok(Some(&b""[..])));
+        assert_eq!(v.param("x"), Ok(Some(&b"a;b"[..])));
+        assert_eq!(v.param("y"), Ok(None));
+        assert_eq!(v.params.count(), 3);
+
+        let v = parameterized(b"attachment; filename=a.txt").unwrap();
+        assert_eq!(v.base, "attachment");
+        assert_eq!(v.media_type(), Err(Error::Syntax));
+        assert_eq!(v.param("filename"), Ok(Some(&b"a.txt"[..])));
+
+        let v = parameterized(b"text/plain").unwrap();
+        assert_eq!(v.param("charset"), Ok(None));
+        assert_eq!(v.params.count(), 0);
+
+        let v = parameterized(b"text/plain; a=\"b").unwrap();
+        assert_eq!(v.param("a"), Err(Error::Syntax));
+
+        assert_eq!(parameterized(b"text/ html").unwrap().media_type(), Err(Error::Syntax));
+        assert_eq!(parameterized(b"/html").unwrap().media_type(), Err(Error::Syntax));
+        assert!(parameterized(b" ; a=1").is_err());
+        assert!(parameterized(b"a=1").is_err());
+        assert!(parameterized(b"\"text/plain").is_err());
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote(b"\"abc\""), b"abc");
+        assert_eq!(unquote(b"\"\""), b"");
+        assert_eq!(unquote(b"\"a\\\"b\""), b"a\\\"b");
+        assert_eq!(unquote(b"abc"), b"abc");
+        assert_eq!(unquote(b"\""), b"\"");
+    }
+}
@@ -0,0 +1,169 @@
+//! Iteration over request paths and guards for mapping them onto a filesystem.
+
+use percent;
+use {Error, Result};
+
+/// Iterator over the `/`-delimited segments of the path in a request target
+/// [RFC3986§3.3].
+///
+/// The query and any fragment are skipped, and for an absolute-form target the scheme
+/// and authority are skipped too. The slash beginning the path doesn't produce an empty
+/// first segment, so `/` has no segments, but any other empty segment is yielded, such
+/// as the last one in `/a/`. Targets in asterisk-form or authority-form have no path and
+/// so no segments.
+///
+/// Segments are yielded raw, so use `next_decoded` or `percent::decode` to decode them.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PathSegments<'a>(Option<&'a [u8]>);
+
+impl<'a> PathSegments<'a> {
+    /// Create a new `PathSegments` iterator over the path in the given request target.
+    pub fn new(target: &'a [u8]) -> Self {
+        let path = match target.windows(3).position(|w| w == b"://") {
+            Some(idx) if !target.starts_with(b"/") => {
+                let rest = &target[idx + 3..];
+                // Target has an authority but may have no path at all.
+                let start = rest.iter()
+                    .position(|&b| b == b'/' || b == b'?' || b == b'#')
+                    .unwrap_or(rest.len());
+                &rest[start..]
+            },
+            _ => target,
+        };
+
+        let end = path.iter().position(|&b| b == b'?' || b == b'#').unwrap_or(path.len());
+
+        PathSegments(match path[..end].split_first() {
+            Some((&b'/', rest)) if !rest.is_empty() => Some(rest),
+            _ => None,
+        })
+    }
+
+    /// Try to percent-decode the next segment into the given buffer with
+    /// `percent::decode`.
+    ///
+    /// Since a decoded segment may contain `/`, the decoded form shouldn't be rejoined
+    /// into a path without care.
+    pub fn next_decoded<'b>(&mut self, buf: &'b mut [u8]) -> Option<Result<&'b [u8]>> {
+        let seg = self.next()?;
+
+        Some(match percent::decode(seg, buf) {
+            Ok(len) => Ok(&buf[..len]),
+            Err(e) => Err(e),
+        })
+    }
+}
+
+impl<'a> Iterator for PathSegments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0?;
+
+        Some(match rest.iter().position(|&b| b == b'/') {
+            Some(idx) => {
+                self.0 = Some(&rest[idx + 1..]);
+                &rest[..idx]
+            },
+            None => {
+                self.0 = None;
+                rest
+            },
+        })
+    }
+}
+
+/// Check the given percent-decoded path against the given root and return the part of
+/// the path under the root, relative to it and with leading slashes removed.
+///
+/// The root is matched along segment boundaries, so the root `/static` matches
+/// `/static/a.css` but not `/statics/a.css`, and `None` is returned if the path isn't
+/// under the root.
+///
+/// The path is rejected with `Error::Syntax` if it contains a NUL byte or a backslash, or
+/// if any `..` segment would climb above the root once dot-segments are removed
+/// [RFC3986§5.2.4]. Dot-segments that stay within the root are kept in the returned path,
+/// so it's safe to join onto the root directory but may not be in normal form.
+pub fn safe_path<'a>(path: &'a [u8], root: &str) -> Result<Option<&'a [u8]>> {
+    if path.contains(&0) || path.contains(&b'\\') {
+        return Err(Error::Syntax);
+    }
+
+    let root = root.trim_end_matches('/').as_bytes();
+
+    if !path.starts_with(root) {
+        return Ok(None);
+    }
+
+    let rest = &path[root.len()..];
+
+    if !rest.is_empty() && rest[0] != b'/' {
+        return Ok(None);
+    }
+
+    let start = rest.iter().position(|&b| b != b'/').unwrap_or(rest.len());
+    let rel = &rest[start..];
+
+    // Number of segments between the root and the current position.
+    let mut depth = 0usize;
+
+    for seg in rel.split(|&b| b == b'/') {
+        match seg {
+            b"" | b"." => {},
+            b".." => depth = depth.checked_sub(1).ok_or(Error::Syntax)?,
+            _ => depth += 1,
+        }
+    }
+
+    Ok(Some(rel))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_path_segments() {
+        fn s(t: &[u8]) -> Vec<&[u8]> { PathSegments::new(t).collect() }
+
+        let empty: Vec<&[u8]> = vec![];
+
+        assert_eq!(s(b"/a/b%20c"), vec![&b"a"[..], b"b%20c"]);
+        assert_eq!(s(b"/a//b/?x=/y#z"), vec![&b"a"[..], b"", b"b", b""]);
+        assert_eq!(s(b"/a#b/c"), vec![&b"a"[..]]);
+        assert_eq!(s(b"/"), empty);
+        assert_eq!(s(b"/?a/b"), empty);
+        assert_eq!(s(b""), empty);
+        assert_eq!(s(b"*"), empty);
+        assert_eq!(s(b"example.com:443"), empty);
+        assert_eq!(s(b"http://example.com/a/b?c"), vec![&b"a"[..], b"b"]);
+        assert_eq!(s(b"http://example.com"), empty);
+        assert_eq!(s(b"http://example.com?a/b"), empty);
+        assert_eq!(s(b"/a?b=http://c/d"), vec![&b"a"[..]]);
+
+        let mut p = PathSegments::new(b"/a%2Fb/%zz/c");
+        let mut buf = [0; 4];
+        assert_eq!(p.next_decoded(&mut buf), Some(Ok(&b"a/b"[..])));
+        assert_eq!(p.next_decoded(&mut buf), Some(Err(Error::Syntax)));
+        assert_eq!(p.next_decoded(&mut buf), Some(Ok(&b"c"[..])));
+        assert_eq!(p.next_decoded(&mut buf), None);
+    }
+
+    #[test]
+    fn test_safe_path() {
+        assert_eq!(safe_path(b"/a/b.txt", "/"), Ok(Some(&b"a/b.txt"[..])));
+        assert_eq!(safe_path(b"/a/b.txt", ""), Ok(Some(&b"a/b.txt"[..])));
+        assert_eq!(safe_path(b"/static/a.css", "/static"), Ok(Some(&b"a.css"[..])));
+        assert_eq!(safe_path(b"/static//a.css", "/static/"), Ok(Some(&b"a.css"[..])));
+        assert_eq!(safe_path(b"/static", "/static"), Ok(Some(&b""[..])));
+        assert_eq!(safe_path(b"/static/a/../b", "/static"), Ok(Some(&b"a/../b"[..])));
+        assert_eq!(safe_path(b"/statics/a.css", "/static"), Ok(None));
+        assert_eq!(safe_path(b"/other", "/static"), Ok(None));
+
+        assert_eq!(safe_path(b"/static/../etc/passwd", "/static"), Err(Error::Syntax));
+        assert_eq!(safe_path(b"/a/../../b", "/"), Err(Error::Syntax));
+        assert_eq!(safe_path(b"/a/./../..", "/"), Err(Error::Syntax));
+        assert_eq!(safe_path(b"/a\\..\\..\\b", "/"), Err(Error::Syntax));
+        assert_eq!(safe_path(b"/a.txt\0.png", "/"), Err(Error::Syntax));
+    }
+}
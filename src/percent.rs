@@ -0,0 +1,309 @@
+//! Percent-encoding [RFC3986§2.1].
+
+use writer::Cursor;
+use {Error, Result};
+
+/// Sets of characters that may appear unencoded within a URI component.
+///
+/// In every set, `%` is always encoded, so arbitrary raw bytes can be encoded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum EncodeSet {
+    /// Only unreserved characters [RFC3986§2.3] are left unencoded.
+    Unreserved,
+    /// Characters allowed in a single path segment [RFC3986§3.3], so `/` and `?` are
+    /// encoded.
+    PathSegment,
+    /// Characters allowed in a query [RFC3986§3.4], except for `&`, `=`, `+`, and `;`,
+    /// so the result can be used as a name or value in `name=value&...` pairs.
+    Query,
+    /// Characters allowed in userinfo [RFC3986§3.2.1], except for `:`, so the user and
+    /// password can be encoded separately.
+    Userinfo,
+}
+
+impl EncodeSet {
+    /// Check if the given byte may appear unencoded.
+    fn contains(self, b: u8) -> bool {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            return true;
+        }
+
+        match self {
+            EncodeSet::Unreserved => false,
+            EncodeSet::PathSegment => b"!$&'()*+,;=:@".contains(&b),
+            EncodeSet::Query => b"!$'()*,:@/?".contains(&b),
+            EncodeSet::Userinfo => b"!$&'()*+,;=".contains(&b),
+        }
+    }
+}
+
+/// Percent-encode the given bytes into the given buffer, leaving characters in the given
+/// set unencoded and encoding all others with uppercase hex digits.
+///
+/// On success, return the number of bytes written.
+pub fn encode(src: &[u8], set: EncodeSet, buf: &mut [u8]) -> Result<usize> {
+    let mut out = Cursor::new(buf);
+
+    for b in encoded(src.iter().cloned(), set) {
+        out.push(&[b])?;
+    }
+
+    Ok(out.pos())
+}
+
+/// Create an iterator that percent-encodes the given bytes like `encode`, leaving
+/// characters in the given set unencoded.
+pub fn encoded<I: IntoIterator<Item = u8>>(src: I, set: EncodeSet) -> Encoded<I::IntoIter> {
+    Encoded { src: src.into_iter(), set, buf: [0; 3], pos: 0, len: 0 }
+}
+
+/// Iterator over percent-encoded bytes.
+#[derive(Clone, Debug)]
+pub struct Encoded<I> {
+    src: I,
+    set: EncodeSet,
+    /// Remaining bytes of the current escape.
+    buf: [u8; 3],
+    pos: usize,
+    len: usize,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Encoded<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+        if self.pos < self.len {
+            self.pos += 1;
+            return Some(self.buf[self.pos - 1]);
+        }
+
+        let b = self.src.next()?;
+
+        if self.set.contains(b) {
+            return Some(b);
+        }
+
+        self.buf = [b'%', HEX[(b >> 4) as usize], HEX[(b & 0xF) as usize]];
+        self.pos = 1;
+        self.len = 3;
+
+        Some(b'%')
+    }
+}
+
+/// Policy for handling percent-decoded components that aren't valid UTF-8.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Utf8Policy {
+    /// Reject invalid UTF-8 with `Error::Syntax`.
+    Require,
+    /// Pass invalid UTF-8 through as raw bytes.
+    AllowRaw,
+    /// Replace invalid sequences with U+FFFD.
+    #[cfg(feature = "alloc")]
+    Replace,
+}
+
+/// A percent-decoded component after UTF-8 validation.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Utf8Checked<'a> {
+    /// Component is valid UTF-8.
+    Str(&'a str),
+    /// Component isn't valid UTF-8 and was passed through under `Utf8Policy::AllowRaw`.
+    Raw(&'a [u8]),
+    /// Component isn't valid UTF-8 and had invalid sequences replaced under
+    /// `Utf8Policy::Replace`.
+    #[cfg(feature = "alloc")]
+    Replaced(String),
+}
+
+impl<'a> Utf8Checked<'a> {
+    /// Retrieve the bytes of the component.
+    pub fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Utf8Checked::Str(s) => s.as_bytes(),
+            Utf8Checked::Raw(b) => b,
+            #[cfg(feature = "alloc")]
+            Utf8Checked::Replaced(ref s) => s.as_bytes(),
+        }
+    }
+}
+
+/// Validate the given percent-decoded component as UTF-8 under the given policy.
+///
+/// Since percent-escapes can encode arbitrary bytes, a decoded target may not be valid
+/// UTF-8 even though the raw target was, so servers mapping targets onto filesystems or
+/// string-keyed routes should apply a single policy consistently.
+pub fn check_utf8<'a>(decoded: &'a [u8], policy: Utf8Policy) -> Result<Utf8Checked<'a>> {
+    if let Ok(s) = std::str::from_utf8(decoded) {
+        return Ok(Utf8Checked::Str(s));
+    }
+
+    match policy {
+        Utf8Policy::Require => Err(Error::Syntax),
+        Utf8Policy::AllowRaw => Ok(Utf8Checked::Raw(decoded)),
+        #[cfg(feature = "alloc")]
+        Utf8Policy::Replace =>
+            Ok(Utf8Checked::Replaced(String::from_utf8_lossy(decoded).into_owned())),
+    }
+}
+
+/// Parse the given ASCII hex digit.
+pub(crate) fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Decode the percent-escapes in the given bytes into the given buffer.
+///
+/// Every `%` must begin a valid escape of two hex digits, otherwise `Error::Syntax` is
+/// returned. A `+` isn't treated as a space, so query values in the form encoding need
+/// it replaced separately.
+///
+/// On success, return the number of bytes written.
+pub fn decode(src: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let mut out = Cursor::new(buf);
+    let mut rest = src;
+
+    while let Some(idx) = rest.iter().position(|&b| b == b'%') {
+        out.push(&rest[..idx])?;
+
+        let b = match (rest.get(idx + 1).and_then(|&b| hex(b)),
+                       rest.get(idx + 2).and_then(|&b| hex(b)))
+        {
+            (Some(hi), Some(lo)) => hi << 4 | lo,
+            _ => return Err(Error::Syntax),
+        };
+
+        out.push(&[b])?;
+        rest = &rest[idx + 3..];
+    }
+
+    out.push(rest)?;
+
+    Ok(out.pos())
+}
+
+/// Create an iterator that decodes valid percent-escapes in the given bytes, passing
+/// through any malformed escapes as-is.
+///
+/// Use `decode` instead to reject malformed escapes.
+pub fn decoded<'a>(raw: &'a [u8]) -> Decoded<'a> {
+    Decoded(raw)
+}
+
+/// Iterator over bytes with valid percent-escapes decoded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Decoded<'a>(&'a [u8]);
+
+impl<'a> Iterator for Decoded<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let (&b, rest) = self.0.split_first()?;
+
+        if b == b'%' {
+            if let (Some(hi), Some(lo)) = (rest.first().and_then(|&b| hex(b)),
+                                           rest.get(1).and_then(|&b| hex(b)))
+            {
+                self.0 = &rest[2..];
+                return Some(hi << 4 | lo);
+            }
+        }
+
+        self.0 = rest;
+
+        Some(b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    #[test]
+    fn test_encode() {
+        let e = |s: &[u8], set| {
+            let mut buf = [0; 64];
+            let len = encode(s, set, &mut buf).unwrap();
+            String::from_utf8(buf[..len].to_vec()).unwrap()
+        };
+
+        assert_eq!(e(b"", EncodeSet::PathSegment), "");
+        assert_eq!(e(b"a b/c?d;e=f@g%", EncodeSet::PathSegment), "a%20b%2Fc%3Fd;e=f@g%25");
+        assert_eq!(e(b"a b/c?d&e=f+g#", EncodeSet::Query), "a%20b/c?d%26e%3Df%2Bg%23");
+        assert_eq!(e(b"us:er@x!", EncodeSet::Userinfo), "us%3Aer%40x!");
+        assert_eq!(e(b"~a-b_c.d!*", EncodeSet::Unreserved), "~a-b_c.d%21%2A");
+        assert_eq!(e(b"\xc3\xa9\x00", EncodeSet::Unreserved), "%C3%A9%00");
+
+        let mut buf = [0; 4];
+        assert_eq!(encode(b"a b", EncodeSet::Query, &mut buf), Err(Error::Capacity));
+        assert_eq!(encode(b"ab", EncodeSet::Query, &mut buf), Ok(2));
+    }
+
+    #[test]
+    fn test_encoded() {
+        let e = |s: &[u8], set| encoded(s.iter().cloned(), set).collect::<Vec<u8>>();
+
+        assert_eq!(e(b"", EncodeSet::Unreserved), b"");
+        assert_eq!(e(b"a-._~Z9", EncodeSet::Unreserved), b"a-._~Z9");
+        assert_eq!(e(b"a b/\xff%", EncodeSet::Unreserved), b"a%20b%2F%FF%25");
+        assert_eq!(e(b"%%", EncodeSet::PathSegment), b"%25%25");
+        assert_eq!(e(b"a@b", EncodeSet::PathSegment), b"a@b");
+        assert_eq!(encoded(decoded(b"%41%2f"), EncodeSet::Unreserved).collect::<Vec<u8>>(),
+            b"A%2F");
+    }
+
+    #[test]
+    fn test_check_utf8() {
+        for &p in &[Utf8Policy::Require, Utf8Policy::AllowRaw] {
+            assert_eq!(check_utf8(b"/caf\xc3\xa9", p), Ok(Utf8Checked::Str("/caf\u{e9}")));
+        }
+
+        assert_eq!(check_utf8(b"/\xff", Utf8Policy::Require), Err(Error::Syntax));
+        assert_eq!(check_utf8(b"/\xff", Utf8Policy::AllowRaw),
+            Ok(Utf8Checked::Raw(&b"/\xff"[..])));
+        assert_eq!(check_utf8(b"/\xff", Utf8Policy::AllowRaw).unwrap().as_bytes(), b"/\xff");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_check_utf8_replace() {
+        assert_eq!(check_utf8(b"/a", Utf8Policy::Replace), Ok(Utf8Checked::Str("/a")));
+
+        let c = check_utf8(b"/a\xffb", Utf8Policy::Replace).unwrap();
+        assert_eq!(c, Utf8Checked::Replaced("/a\u{fffd}b".to_string()));
+        assert_eq!(c.as_bytes(), "/a\u{fffd}b".as_bytes());
+    }
+
+    #[test]
+    fn test_decode() {
+        let d = |s: &[u8]| {
+            let mut buf = [0; 8];
+            decode(s, &mut buf).map(|len| buf[..len].to_vec())
+        };
+
+        assert_eq!(d(b""), Ok(b"".to_vec()));
+        assert_eq!(d(b"a+b"), Ok(b"a+b".to_vec()));
+        assert_eq!(d(b"a%20b%2f%2F"), Ok(b"a b//".to_vec()));
+        assert_eq!(d(b"%c3%A9%00"), Ok(b"\xc3\xa9\x00".to_vec()));
+        assert_eq!(d(b"%zz"), Err(Error::Syntax));
+        assert_eq!(d(b"a%4"), Err(Error::Syntax));
+        assert_eq!(d(b"a%"), Err(Error::Syntax));
+        assert_eq!(d(b"abcdefghi"), Err(Error::Capacity));
+        assert_eq!(d(b"%41%41%41%41%41%41%41%41"), Ok(b"AAAAAAAA".to_vec()));
+    }
+
+    #[test]
+    fn test_decoded() {
+        let d = |s: &[u8]| decoded(s).collect::<Vec<u8>>();
+
+        assert_eq!(d(b""), b"");
+        assert_eq!(d(b"abc"), b"abc");
+        assert_eq!(d(b"a%20b%2f%2F"), b"a b//");
+        assert_eq!(d(b"%c3%A9"), b"\xc3\xa9");
+        assert_eq!(d(b"%zz%4%"), b"%zz%4%");
+        assert_eq!(d(b"%%41"), b"%A");
+    }
+}
@@ -0,0 +1,216 @@
+//! Bundled request limits for gateways.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::policy::{Policy, Violation};
+//! use uhttp_request::Request;
+//!
+//! let policy = Policy {
+//!     max_target_len: Some(8),
+//!     require_host: true,
+//!     ..Policy::new()
+//! };
+//!
+//! let req = Request::with_config(b"GET /a HTTP/1.1\r\nHost: a\r\n\r\n", policy.config())
+//!     .unwrap();
+//! assert_eq!(policy.enforce(&req), Ok(()));
+//!
+//! let req = Request::new(b"GET /abcdefghi HTTP/1.1\r\n\r\n").unwrap();
+//! assert_eq!(policy.enforce(&req), Err(Violation::TargetTooLong));
+//! assert_eq!(Violation::TargetTooLong.status(), 414);
+//! ```
+
+use {Error, ParserConfig, Request};
+
+/// Limits applied to every request passing through a gateway.
+///
+/// The default policy imposes no limits.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Policy {
+    /// Maximum length in bytes of the request head, or `None` for no limit.
+    pub max_head_len: Option<usize>,
+
+    /// Maximum number of header fields, or `None` for no limit.
+    pub max_headers: Option<usize>,
+
+    /// Maximum length in bytes of the request target, or `None` for no limit.
+    pub max_target_len: Option<usize>,
+
+    /// Maximum Content-Length of the body, or `None` for no limit.
+    ///
+    /// The length of a chunked body isn't known up front, so it must be checked while
+    /// reading, such as with `body::BodyVerifier`.
+    pub max_body_len: Option<u64>,
+
+    /// Request methods to accept, compared case-sensitively, or `None` to accept any
+    /// method.
+    pub allowed_methods: Option<&'static [&'static str]>,
+
+    /// Whether to require exactly one Host header field, as every HTTP/1.1 request must
+    /// have [RFC7230§5.4].
+    pub require_host: bool,
+}
+
+/// A limit in a `Policy` that a request exceeded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Violation {
+    /// Head is longer than `max_head_len`.
+    HeadTooLarge,
+    /// Head has more header fields than `max_headers`.
+    TooManyHeaders,
+    /// Target is longer than `max_target_len`.
+    TargetTooLong,
+    /// Content-Length is larger than `max_body_len`.
+    BodyTooLarge,
+    /// Method isn't in `allowed_methods`.
+    MethodNotAllowed,
+    /// Host header field is missing or repeated while `require_host` is set.
+    Host,
+    /// Request is malformed in a way detected while checking the limits.
+    Malformed(Error),
+}
+
+impl Violation {
+    /// Retrieve the status code of the response suited to the violation.
+    pub fn status(self) -> u16 {
+        match self {
+            Violation::HeadTooLarge | Violation::TooManyHeaders => 431,
+            Violation::TargetTooLong => 414,
+            Violation::BodyTooLarge => 413,
+            Violation::MethodNotAllowed => 501,
+            Violation::Host | Violation::Malformed(_) => 400,
+        }
+    }
+}
+
+impl Policy {
+    /// Create a new `Policy` with no limits.
+    pub const fn new() -> Self {
+        Policy {
+            max_head_len: None,
+            max_headers: None,
+            max_target_len: None,
+            max_body_len: None,
+            allowed_methods: None,
+            require_host: false,
+        }
+    }
+
+    /// Create a `ParserConfig` that enforces the limits of this policy which can be
+    /// checked while parsing, so an oversized head is rejected before it's fully
+    /// received.
+    ///
+    /// Heads exceeding `max_head_len` then fail with `Error::Budget`, and disallowed
+    /// methods with `Error::UnsupportedMethod`.
+    pub const fn config(&self) -> ParserConfig {
+        ParserConfig {
+            max_scan_len: self.max_head_len,
+            allowed_methods: self.allowed_methods,
+            ..ParserConfig::new()
+        }
+    }
+
+    /// Check the given request against every limit, returning the first violation.
+    pub fn enforce(&self, req: &Request) -> std::result::Result<(), Violation> {
+        let stats = req.head.stats;
+
+        if self.max_head_len.is_some_and(|max| stats.head_len > max) {
+            return Err(Violation::HeadTooLarge);
+        }
+
+        if self.max_headers.is_some_and(|max| stats.headers > max) {
+            return Err(Violation::TooManyHeaders);
+        }
+
+        if self.max_target_len.is_some_and(|max| stats.target_len > max) {
+            return Err(Violation::TargetTooLong);
+        }
+
+        if let Some(allowed) = self.allowed_methods {
+            if !allowed.contains(&req.line().method) {
+                return Err(Violation::MethodNotAllowed);
+            }
+        }
+
+        if self.require_host {
+            let hosts = req.headers()
+                .filter_map(|h| h.ok())
+                .filter(|h| h.name.eq_ignore_ascii_case("Host"))
+                .count();
+
+            if hosts != 1 {
+                return Err(Violation::Host);
+            }
+        }
+
+        if let Some(max) = self.max_body_len {
+            match req.content_length() {
+                Ok(Some(len)) if len > max => return Err(Violation::BodyTooLarge),
+                Ok(_) => {},
+                Err(e) => return Err(Violation::Malformed(e)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self { Policy::new() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_enforce() {
+        let req = Request::new(b"POST /abc HTTP/1.1\r\nHost: a\r\nContent-Length: 10\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(Policy::new().enforce(&req), Ok(()));
+
+        let check = |policy: Policy| policy.enforce(&req);
+
+        assert_eq!(check(Policy { max_head_len: Some(50), ..Policy::new() }),
+            Err(Violation::HeadTooLarge));
+        assert_eq!(check(Policy { max_head_len: Some(51), ..Policy::new() }), Ok(()));
+        assert_eq!(check(Policy { max_headers: Some(1), ..Policy::new() }),
+            Err(Violation::TooManyHeaders));
+        assert_eq!(check(Policy { max_target_len: Some(4), ..Policy::new() }), Ok(()));
+        assert_eq!(check(Policy { max_target_len: Some(3), ..Policy::new() }),
+            Err(Violation::TargetTooLong));
+        assert_eq!(check(Policy { max_body_len: Some(9), ..Policy::new() }),
+            Err(Violation::BodyTooLarge));
+        assert_eq!(check(Policy { max_body_len: Some(10), ..Policy::new() }), Ok(()));
+        assert_eq!(check(Policy { allowed_methods: Some(&["GET"]), ..Policy::new() }),
+            Err(Violation::MethodNotAllowed));
+        assert_eq!(check(Policy { require_host: true, ..Policy::new() }), Ok(()));
+
+        let policy = Policy { require_host: true, ..Policy::new() };
+        let req = Request::new(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(policy.enforce(&req), Err(Violation::Host));
+        let req = Request::new(b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n").unwrap();
+        assert_eq!(policy.enforce(&req), Err(Violation::Host));
+
+        let policy = Policy { max_body_len: Some(10), ..Policy::new() };
+        let req = Request::new(b"GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n").unwrap();
+        assert_eq!(policy.enforce(&req), Err(Violation::Malformed(Error::Syntax)));
+    }
+
+    #[test]
+    fn test_config() {
+        let policy = Policy {
+            max_head_len: Some(20),
+            allowed_methods: Some(&["GET"]),
+            ..Policy::new()
+        };
+
+        assert_eq!(Request::with_config(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n", policy.config()),
+            Err(Error::Budget));
+        assert_eq!(Request::with_config(b"PUT / HTTP/1.1\r\n\r\n", policy.config()),
+            Err(Error::UnsupportedMethod));
+        assert!(Request::with_config(b"GET / HTTP/1.1\r\n\r\n", policy.config()).is_ok());
+    }
+}
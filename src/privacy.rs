@@ -0,0 +1,105 @@
+//! Accessors for privacy preference header fields.
+
+use grammar::trim_ows;
+use {Error, Headers, Result};
+
+/// Parse the given DNT header value [TRACKING-DNT§5.2], returning `true` if the user
+/// prefers not to be tracked and `false` if they consent to tracking.
+///
+/// Any extension characters following the initial `0` or `1` are ignored.
+pub fn parse_dnt(val: &[u8]) -> Result<bool> {
+    let val = trim_ows(val);
+
+    let pref = match val.first() {
+        Some(&b'0') => false,
+        Some(&b'1') => true,
+        _ => return Err(Error::Syntax),
+    };
+
+    // DNT-extension = %x21 / %x23-2B / %x2D-5B / %x5D-7E
+    if val[1..].iter().all(|&b| (0x21..=0x7E).contains(&b) && !b"\",\\".contains(&b)) {
+        Ok(pref)
+    } else {
+        Err(Error::Syntax)
+    }
+}
+
+/// Parse the given Sec-GPC header value [GPC§3.1], returning `true` if the user has
+/// enabled Global Privacy Control.
+///
+/// Both the specified `1` form and the Structured Field boolean forms `?1` and `?0`
+/// [RFC8941§3.3.6] are accepted.
+pub fn parse_sec_gpc(val: &[u8]) -> Result<bool> {
+    match trim_ows(val) {
+        b"1" | b"?1" => Ok(true),
+        b"?0" => Ok(false),
+        _ => Err(Error::Syntax),
+    }
+}
+
+/// Find and parse the DNT header in the given headers, returning `None` if the user
+/// hasn't expressed a tracking preference.
+pub fn dnt(headers: Headers) -> Result<Option<bool>> {
+    find(headers, "DNT", parse_dnt)
+}
+
+/// Find and parse the Sec-GPC header in the given headers, returning `None` if the
+/// header isn't present.
+pub fn sec_gpc(headers: Headers) -> Result<Option<bool>> {
+    find(headers, "Sec-GPC", parse_sec_gpc)
+}
+
+/// Parse the first header with the given name using the given parser.
+fn find<F>(headers: Headers, name: &str, parse: F) -> Result<Option<bool>>
+    where F: Fn(&[u8]) -> Result<bool>
+{
+    for h in headers {
+        let h = h?;
+
+        if h.name.eq_ignore_ascii_case(name) {
+            return parse(h.val).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_dnt() {
+        assert_eq!(parse_dnt(b"1"), Ok(true));
+        assert_eq!(parse_dnt(b" 0 "), Ok(false));
+        assert_eq!(parse_dnt(b"1xyz"), Ok(true));
+        assert_eq!(parse_dnt(b""), Err(Error::Syntax));
+        assert_eq!(parse_dnt(b"2"), Err(Error::Syntax));
+        assert_eq!(parse_dnt(b"1,0"), Err(Error::Syntax));
+        assert_eq!(parse_dnt(b"1 0"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_parse_sec_gpc() {
+        assert_eq!(parse_sec_gpc(b"1"), Ok(true));
+        assert_eq!(parse_sec_gpc(b" ?1"), Ok(true));
+        assert_eq!(parse_sec_gpc(b"?0"), Ok(false));
+        assert_eq!(parse_sec_gpc(b"0"), Err(Error::Syntax));
+        assert_eq!(parse_sec_gpc(b"?2"), Err(Error::Syntax));
+        assert_eq!(parse_sec_gpc(b""), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_find() {
+        let h = b"Host: a\r\ndnt: 1\r\nSec-GPC: 1\r\n\r\n";
+        assert_eq!(dnt(Headers::new(h)), Ok(Some(true)));
+        assert_eq!(sec_gpc(Headers::new(h)), Ok(Some(true)));
+
+        let h = b"Host: a\r\n\r\n";
+        assert_eq!(dnt(Headers::new(h)), Ok(None));
+        assert_eq!(sec_gpc(Headers::new(h)), Ok(None));
+
+        assert_eq!(dnt(Headers::new(b"DNT: x\r\n\r\n")), Err(Error::Syntax));
+        assert_eq!(dnt(Headers::new(b"Host: a\r\n")), Err(Error::Partial));
+    }
+}
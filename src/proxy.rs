@@ -0,0 +1,561 @@
+//! Helpers for forwarding requests through proxies.
+
+use std::fmt::Write;
+use std::net::IpAddr;
+
+use grammar::{is_obs_text, is_ows, is_tchar, is_vchar, trim_ows};
+use host;
+use list::{self, ListValues};
+use writer::{Cursor, Edit, FmtCursor, LineSpans, Spans};
+use {Error, Headers, RequestLine, Result};
+
+/// Header fields that are hop-by-hop regardless of the Connection header [RFC7230§6.1],
+/// along with the obsolete `Keep-Alive` [RFC2068§19.7.1].
+const HOP_BY_HOP: &[&str] = &[
+    "Connection",
+    "Keep-Alive",
+    "TE",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// Maximum number of Connection options that `strip_hop_by_hop` can track.
+pub const MAX_CONNECTION_OPTIONS: usize = 16;
+
+/// Create an iterator over `Drop` edits that remove every hop-by-hop header field from
+/// the given request head, for use with `writer::write_edited`.
+///
+/// This covers the fixed hop-by-hop fields, any `Proxy-*` field, and every field named
+/// in a Connection header [RFC7230§6.1]. All header fields in the head are validated
+/// and the Connection options collected before the iterator is returned. More than
+/// `MAX_CONNECTION_OPTIONS` options cause `Error::Capacity`.
+pub fn strip_hop_by_hop<'a>(head: &'a [u8]) -> Result<HopByHop<'a>> {
+    let (_, block) = RequestLine::new(head)?;
+
+    for s in Spans::new(head)? {
+        s?;
+    }
+
+    let mut options = [&b""[..]; MAX_CONNECTION_OPTIONS];
+    let mut len = 0;
+
+    for opt in connection_options(Headers::new(block)) {
+        let opt = opt?;
+        *options.get_mut(len).ok_or(Error::Capacity)? = opt;
+        len += 1;
+    }
+
+    Ok(HopByHop {
+        spans: Spans::new(head)?,
+        options,
+        len,
+    })
+}
+
+/// Iterator over edits that strip hop-by-hop header fields.
+pub struct HopByHop<'a> {
+    spans: Spans<'a>,
+    options: [&'a [u8]; MAX_CONNECTION_OPTIONS],
+    len: usize,
+}
+
+impl<'a> Iterator for HopByHop<'a> {
+    type Item = Edit<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Fields were validated up front, so any error here is unreachable.
+            let (h, span) = self.spans.next()?.ok()?;
+
+            let options = self.options.get(..self.len).unwrap_or_default();
+
+            if is_hop_by_hop(h.name, options) {
+                return Some(Edit::Drop(span));
+            }
+        }
+    }
+}
+
+/// Write a Via value with an entry for this proxy into the given buffer [RFC7230§5.7.1].
+///
+/// The entry is formed from the protocol version of the received request, as in its
+/// Request-Line, the given pseudonym or `host[:port]` of this proxy, and an optional
+/// comment. For HTTP, only the version number is written, such as `1.1` for
+/// `HTTP/1.1`. If an existing Via value is given, the entry is appended to its list, so
+/// the result can replace the existing field; otherwise the result can be written as a
+/// new field.
+///
+/// Return `Error::Syntax` if the pseudonym is empty or contains characters outside a
+/// token or host, if the comment contains parentheses, backslashes, or control
+/// characters, or if the existing value contains control characters, which prevents
+/// header injection from untrusted strings.
+///
+/// On success, return the number of bytes written.
+pub fn write_via(existing: Option<&[u8]>, version: &str, pseudonym: &str,
+                 comment: Option<&str>, buf: &mut [u8])
+    -> Result<usize>
+{
+    let protocol = match version.strip_prefix("HTTP/") {
+        Some(num) => num,
+        None => version,
+    };
+
+    let valid_protocol = !protocol.is_empty() &&
+        protocol.bytes().all(|b| is_tchar(b) || b == b'/');
+    let valid_pseudonym = !pseudonym.is_empty() &&
+        pseudonym.bytes().all(|b| is_tchar(b) || b":[]".contains(&b));
+
+    if !valid_protocol || !valid_pseudonym {
+        return Err(Error::Syntax);
+    }
+
+    let mut out = Cursor::new(buf);
+    push_existing(&mut out, existing)?;
+
+    out.push(protocol.as_bytes())?;
+    out.push(b" ")?;
+    out.push(pseudonym.as_bytes())?;
+
+    if let Some(comment) = comment {
+        let valid = |b| (is_vchar(b) || b == b' ' || b == b'\t') && !b"()\\".contains(&b);
+
+        if !comment.bytes().all(valid) {
+            return Err(Error::Syntax);
+        }
+
+        out.push(b" (")?;
+        out.push(comment.as_bytes())?;
+        out.push(b")")?;
+    }
+
+    Ok(out.pos())
+}
+
+/// Write an X-Forwarded-For value with the given client address appended into the given
+/// buffer.
+///
+/// An IPv6 address is written in brackets. If an existing X-Forwarded-For value is
+/// given, the address is appended to its list, so the result can replace the existing
+/// field; otherwise the result can be written as a new field. Return `Error::Syntax` if
+/// the existing value contains control characters.
+///
+/// On success, return the number of bytes written.
+pub fn write_forwarded_for(existing: Option<&[u8]>, client: IpAddr, buf: &mut [u8])
+    -> Result<usize>
+{
+    let mut out = Cursor::new(buf);
+    push_existing(&mut out, existing)?;
+
+    match client {
+        IpAddr::V4(ip) => write!(FmtCursor(&mut out), "{}", ip),
+        IpAddr::V6(ip) => write!(FmtCursor(&mut out), "[{}]", ip),
+    }.map_err(|_| Error::Capacity)?;
+
+    Ok(out.pos())
+}
+
+/// Write a Forwarded value [RFC7239§4] with an element for the given client address
+/// appended into the given buffer.
+///
+/// The element is written as `for=192.0.2.1`, or as `for="[2001:db8::1]"` for an IPv6
+/// address, which must be quoted [RFC7239§6]. Existing values are handled as with
+/// `write_forwarded_for`.
+///
+/// On success, return the number of bytes written.
+pub fn write_forwarded(existing: Option<&[u8]>, client: IpAddr, buf: &mut [u8])
+    -> Result<usize>
+{
+    let mut out = Cursor::new(buf);
+    push_existing(&mut out, existing)?;
+
+    match client {
+        IpAddr::V4(ip) => write!(FmtCursor(&mut out), "for={}", ip),
+        IpAddr::V6(ip) => write!(FmtCursor(&mut out), "for=\"[{}]\"", ip),
+    }.map_err(|_| Error::Capacity)?;
+
+    Ok(out.pos())
+}
+
+/// Write the given request head, which must have an absolute-form target, into the given
+/// buffer with the target converted to origin-form, as when relaying to an origin
+/// server [RFC7230§5.3].
+///
+/// The authority of the target replaces any Host header fields, and is written as the
+/// first field [RFC7230§5.4]. An empty path is written as `/`, and any fragment is
+/// dropped. Return `Error::Syntax` if the target isn't in absolute-form, has userinfo,
+/// or has an authority that isn't a valid host and optional port, which prevents header
+/// injection through the target.
+///
+/// On success, return the number of bytes written.
+pub fn write_origin_form(head: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let (line, _) = RequestLine::new(head)?;
+    let (_, authority, path) = split_absolute(line.target)?;
+    let spans = LineSpans::new(head)?;
+
+    let mut out = Cursor::new(buf);
+    out.push(&head[spans.line.start..spans.target.start])?;
+
+    if !path.starts_with('/') {
+        out.push(b"/")?;
+    }
+
+    out.push(path.as_bytes())?;
+    out.push(&head[spans.target.end..spans.line.end])?;
+    out.push(b"Host: ")?;
+    out.push(authority.as_bytes())?;
+    out.push(b"\r\n")?;
+
+    for next in Spans::new(head)? {
+        let (h, span) = next?;
+
+        if !h.name.eq_ignore_ascii_case("Host") {
+            out.push(&head[span.start..span.end])?;
+        }
+    }
+
+    out.push(b"\r\n")?;
+
+    Ok(out.pos())
+}
+
+/// Write the given request head, which must have an origin-form target, into the given
+/// buffer with the target converted to absolute-form using the given scheme and the
+/// authority in the Host header, as when sending to a forward proxy [RFC7230§5.3.2].
+///
+/// Header fields, including Host, are written unchanged. Return `Error::Syntax` if the
+/// target isn't in origin-form or there isn't exactly one Host header field with a valid
+/// `host[:port]` value.
+///
+/// On success, return the number of bytes written.
+pub fn write_absolute_form(head: &[u8], scheme: &str, buf: &mut [u8]) -> Result<usize> {
+    let (line, block) = RequestLine::new(head)?;
+
+    if !line.target.starts_with('/') || scheme.is_empty() ||
+       !scheme.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+    {
+        return Err(Error::Syntax);
+    }
+
+    let mut host = None;
+
+    for h in Headers::new(block) {
+        let h = h?;
+
+        if h.name.eq_ignore_ascii_case("Host") {
+            if host.is_some() {
+                return Err(Error::Syntax);
+            }
+
+            host = Some(trim_ows(h.val));
+        }
+    }
+
+    let host = host.ok_or(Error::Syntax)?;
+    host::parse_host(host)?;
+
+    let spans = LineSpans::new(head)?;
+    let mut fields = Spans::new(head)?;
+
+    for next in fields.by_ref() {
+        next?;
+    }
+
+    let mut out = Cursor::new(buf);
+    out.push(&head[spans.line.start..spans.target.start])?;
+    out.push(scheme.as_bytes())?;
+    out.push(b"://")?;
+    out.push(host)?;
+    out.push(&head[spans.target.start..fields.pos()])?;
+
+    Ok(out.pos())
+}
+
+/// Split the given absolute-form target into its scheme, authority, and path with any
+/// query, dropping any fragment.
+fn split_absolute(target: &str) -> Result<(&str, &str, &str)> {
+    let sep = target.find("://").ok_or(Error::Syntax)?;
+    let (scheme, rest) = (&target[..sep], &target[sep + 3..]);
+
+    let valid_scheme = scheme.bytes().next().is_some_and(|b| b.is_ascii_alphabetic()) &&
+        scheme.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(end);
+    let path = path.split('#').next().unwrap_or_default();
+
+    if !valid_scheme || !authority.bytes().all(is_vchar) {
+        return Err(Error::Syntax);
+    }
+
+    // This also rejects an empty authority and userinfo.
+    host::parse_host(authority.as_bytes())?;
+
+    Ok((scheme, authority, path))
+}
+
+/// Write the given existing list value, if it's non-empty, followed by a separator for
+/// the next element.
+fn push_existing(out: &mut Cursor, existing: Option<&[u8]>) -> Result<()> {
+    let existing = match existing.map(trim_ows) {
+        Some(e) if !e.is_empty() => e,
+        _ => return Ok(()),
+    };
+
+    if !existing.iter().all(|&b| is_vchar(b) || is_obs_text(b) || is_ows(b)) {
+        return Err(Error::Syntax);
+    }
+
+    out.push(existing)?;
+    out.push(b", ")
+}
+
+/// Create an iterator over the options listed in every Connection header among the
+/// given header fields [RFC7230§6.1], in order.
+///
+/// Options are returned with surrounding whitespace trimmed and should be compared
+/// case-insensitively. Iteration stops after the first error in a header field or its
+/// list value.
+pub fn connection_options<'a>(headers: Headers<'a>) -> ConnectionOptions<'a> {
+    ConnectionOptions { headers: Some(headers), values: None }
+}
+
+/// Iterator over the options in Connection headers.
+pub struct ConnectionOptions<'a> {
+    headers: Option<Headers<'a>>,
+    values: Option<ListValues<'a>>,
+}
+
+impl<'a> Iterator for ConnectionOptions<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(opt) = self.values.as_mut().and_then(|v| v.next()) {
+                if opt.is_err() {
+                    self.headers = None;
+                }
+
+                return Some(opt);
+            }
+
+            match self.headers.as_mut()?.next()? {
+                Ok(h) if h.name.eq_ignore_ascii_case("Connection") => {
+                    self.values = Some(list::values(h.val));
+                },
+                Ok(_) => {},
+                Err(e) => {
+                    self.headers = None;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
+
+/// Check if the given header name is hop-by-hop given the options listed in the
+/// request's Connection headers.
+fn is_hop_by_hop(name: &str, options: &[&[u8]]) -> bool {
+    HOP_BY_HOP.iter().any(|h| h.eq_ignore_ascii_case(name)) ||
+        name.as_bytes().get(..6).is_some_and(|p| p.eq_ignore_ascii_case(b"proxy-")) ||
+        options.iter().any(|opt| opt.eq_ignore_ascii_case(name.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use writer::write_edited;
+    use Error;
+
+    #[test]
+    fn test_strip_hop_by_hop() {
+        let head = b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close, X-Foo\r\n\
+                     Keep-Alive: 300\r\nx-foo: 1\r\nTE: trailers\r\nUpgrade: h2c\r\n\
+                     Transfer-Encoding: chunked\r\nProxy-Authorization: x\r\n\
+                     Proxy-Connection: keep-alive\r\nX-Bar: 2\r\n\r\n";
+
+        let edits = strip_hop_by_hop(head).unwrap().collect::<Vec<_>>();
+        assert_eq!(edits.len(), 8);
+
+        let mut buf = [0; 256];
+        let len = write_edited(head, &edits, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nHost: a\r\nX-Bar: 2\r\n\r\n"[..]);
+
+        let head = b"GET / HTTP/1.1\r\nHost: a\r\nProxy: b\r\n\r\n";
+        assert_eq!(strip_hop_by_hop(head).unwrap().count(), 0);
+
+        let head = b"GET / HTTP/1.1\r\nConnection: X-A\r\nX-A: 1\r\nX-B: 2\r\n\
+                     connection: \"x\", X-B\r\nX-C: 3\r\n\r\n";
+        let len = write_edited(head, &strip_hop_by_hop(head).unwrap().collect::<Vec<_>>(),
+            &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nX-C: 3\r\n\r\n"[..]);
+
+        let mut head = b"GET / HTTP/1.1\r\nConnection: ".to_vec();
+        head.extend_from_slice(&b"a,"[..].repeat(MAX_CONNECTION_OPTIONS));
+        head.extend_from_slice(b"\r\n\r\n");
+        assert!(strip_hop_by_hop(&head).is_ok());
+        head.splice(28..28, b"b,".iter().cloned());
+        assert_eq!(strip_hop_by_hop(&head).err(), Some(Error::Capacity));
+
+        assert_eq!(strip_hop_by_hop(b"GET / HTTP/1.1\r\nConnection: \"a\r\n\r\n").err(),
+            Some(Error::Syntax));
+        assert!(strip_hop_by_hop(b"GET / HTTP/1.1\r\nHost\r\n\r\n").is_err());
+        assert_eq!(strip_hop_by_hop(b"GET / HTTP/1.1\r\nHost: a\r\n").err(),
+            Some(Error::Partial));
+    }
+
+    #[test]
+    fn test_connection_options() {
+        let block = b"Connection: close, ,Upgrade\r\nHost: a\r\nCONNECTION: \"a,b\"\r\n\r\n";
+        let mut opts = connection_options(Headers::new(block));
+        assert_eq!(opts.next(), Some(Ok(&b"close"[..])));
+        assert_eq!(opts.next(), Some(Ok(&b"Upgrade"[..])));
+        assert_eq!(opts.next(), Some(Ok(&b"\"a,b\""[..])));
+        assert_eq!(opts.next(), None);
+
+        let block = b"Connection: a, \"b\r\nConnection: c\r\n\r\n";
+        let mut opts = connection_options(Headers::new(block));
+        assert_eq!(opts.next(), Some(Ok(&b"a"[..])));
+        assert_eq!(opts.next(), Some(Err(Error::Syntax)));
+        assert_eq!(opts.next(), None);
+
+        assert_eq!(connection_options(Headers::new(b"Host: a\r\n\r\n")).next(), None);
+    }
+
+    #[test]
+    fn test_write_via() {
+        let v = |existing, version, pseudonym, comment| {
+            let mut buf = [0; 64];
+            write_via(existing, version, pseudonym, comment, &mut buf)
+                .map(|len| String::from_utf8(buf[..len].to_vec()).unwrap())
+        };
+
+        assert_eq!(v(None, "HTTP/1.1", "proxy", None), Ok("1.1 proxy".to_string()));
+        assert_eq!(v(None, "HTTP/1.0", "a.com:8080", Some("cache v2")),
+            Ok("1.0 a.com:8080 (cache v2)".to_string()));
+        assert_eq!(v(Some(b" 1.0 fred, 1.1 p.com "), "HTTP/1.1", "[::1]:80", None),
+            Ok("1.0 fred, 1.1 p.com, 1.1 [::1]:80".to_string()));
+        assert_eq!(v(Some(b" "), "ICAP/1.0", "x", None), Ok("ICAP/1.0 x".to_string()));
+
+        assert_eq!(v(None, "HTTP/1.1", "", None), Err(Error::Syntax));
+        assert_eq!(v(None, "HTTP/1.1", "a\r\nX: y", None), Err(Error::Syntax));
+        assert_eq!(v(None, "HTTP/1.1", "a b", None), Err(Error::Syntax));
+        assert_eq!(v(None, "HTTP/", "a", None), Err(Error::Syntax));
+        assert_eq!(v(None, "HTTP/1.1", "a", Some("x)\r\nY: (z")), Err(Error::Syntax));
+        assert_eq!(v(None, "HTTP/1.1", "a", Some("\\")), Err(Error::Syntax));
+
+        let mut buf = [0; 8];
+        assert_eq!(write_via(None, "HTTP/1.1", "proxy", None, &mut buf), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_write_forwarded() {
+        let v4 = IpAddr::from([192, 0, 2, 1]);
+        let v6 = IpAddr::from([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+
+        let x = |existing, ip| {
+            let mut buf = [0; 64];
+            write_forwarded_for(existing, ip, &mut buf)
+                .map(|len| String::from_utf8(buf[..len].to_vec()).unwrap())
+        };
+
+        assert_eq!(x(None, v4), Ok("192.0.2.1".to_string()));
+        assert_eq!(x(None, v6), Ok("[2001:db8::1]".to_string()));
+        assert_eq!(x(Some(b" 10.0.0.1, 10.0.0.2 "), v4),
+            Ok("10.0.0.1, 10.0.0.2, 192.0.2.1".to_string()));
+        assert_eq!(x(Some(b""), v4), Ok("192.0.2.1".to_string()));
+        assert_eq!(x(Some(b"a\r\nX: y"), v4), Err(Error::Syntax));
+
+        let f = |existing, ip| {
+            let mut buf = [0; 64];
+            write_forwarded(existing, ip, &mut buf)
+                .map(|len| String::from_utf8(buf[..len].to_vec()).unwrap())
+        };
+
+        assert_eq!(f(None, v4), Ok("for=192.0.2.1".to_string()));
+        assert_eq!(f(Some(b"for=10.0.0.1;proto=https"), v6),
+            Ok("for=10.0.0.1;proto=https, for=\"[2001:db8::1]\"".to_string()));
+        assert_eq!(f(Some(b"\x00"), v4), Err(Error::Syntax));
+
+        let mut buf = [0; 12];
+        assert_eq!(write_forwarded_for(None, v6, &mut buf), Err(Error::Capacity));
+        assert_eq!(write_forwarded(None, v4, &mut buf), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_write_origin_form() {
+        let mut buf = [0; 128];
+
+        let head = b"\r\nGET http://a.com:8080/b?c#d HTTP/1.1\r\nX: 1\r\nhost: z\r\n\
+                     Y: 2\r\n\r\nbody";
+        let len = write_origin_form(head, &mut buf).unwrap();
+        assert_eq!(&buf[..len],
+            &b"GET /b?c HTTP/1.1\r\nHost: a.com:8080\r\nX: 1\r\nY: 2\r\n\r\n"[..]);
+
+        let len = write_origin_form(b"GET HTTP://a?b HTTP/1.1\r\n\r\n", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET /?b HTTP/1.1\r\nHost: a\r\n\r\n"[..]);
+
+        let len = write_origin_form(b"GET http://a HTTP/1.1\r\n\r\n", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"[..]);
+
+        assert_eq!(write_origin_form(b"GET /a HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http:///a HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://u@a/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET 1://a/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://a/ HTTP/1.1\r\nA\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://a/ HTTP/1.1\r\n\r\n", &mut buf[..20]),
+            Err(Error::Capacity));
+
+        // The authority is written as a header value, so it must be a valid host.
+        assert_eq!(write_origin_form(b"GET http://a\nEvil:x/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://a\rb/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://a\tb/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_origin_form(b"GET http://a:x/ HTTP/1.1\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+
+        let len = write_origin_form(b"GET http://[::1]:80/ HTTP/1.1\r\n\r\n", &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nHost: [::1]:80\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn test_write_absolute_form() {
+        let mut buf = [0; 128];
+
+        let head = b"GET /a?b HTTP/1.1\r\nX: 1\r\nHost:  a.com \r\n\r\nbody";
+        let len = write_absolute_form(head, "http", &mut buf).unwrap();
+        assert_eq!(&buf[..len],
+            &b"GET http://a.com/a?b HTTP/1.1\r\nX: 1\r\nHost:  a.com \r\n\r\n"[..]);
+
+        assert_eq!(write_absolute_form(b"GET http://a/ HTTP/1.1\r\nHost: a\r\n\r\n", "http",
+            &mut buf), Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET / HTTP/1.1\r\n\r\n", "http", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET / HTTP/1.1\r\nHost:\r\n\r\n", "http", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n",
+            "http", &mut buf), Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n", "a b",
+            &mut buf), Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET / HTTP/1.1\r\nHost: a\r\n", "http", &mut buf),
+            Err(Error::Partial));
+        assert_eq!(write_absolute_form(b"GET /p HTTP/1.1\r\nHost: a@evil.com\r\n\r\n",
+            "http", &mut buf), Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET /p HTTP/1.1\r\nHost: evil.com/x\r\n\r\n",
+            "http", &mut buf), Err(Error::Syntax));
+        assert_eq!(write_absolute_form(b"GET /p HTTP/1.1\r\nHost: a@evil.com/x\r\n\r\n",
+            "http", &mut buf), Err(Error::Syntax));
+
+        let len = write_absolute_form(b"GET /p HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n",
+            "http", &mut buf).unwrap();
+        assert_eq!(&buf[..len],
+            &b"GET http://[::1]:8080/p HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n"[..]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Iteration over the query parameters of a request target [RFC3986§3.4].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::query::QueryParams;
+//!
+//! let mut q = QueryParams::new(b"/search?q=rust+http&page=2&&flag");
+//! assert_eq!(q.next(), Some((&b"q"[..], &b"rust+http"[..])));
+//! assert_eq!(q.next(), Some((&b"page"[..], &b"2"[..])));
+//! assert_eq!(q.next(), Some((&b"flag"[..], &b""[..])));
+//! assert_eq!(q.next(), None);
+//! ```
+//!
+//! For looking up parameters by name, `Query` decodes values from the form encoding.
+//!
+//! ```rust
+//! use uhttp_request::query::Query;
+//!
+//! let q = Query::new(b"/search?q=rust+http%21&page=2&flag&tag=a&tag=b");
+//! let mut buf = [0; 16];
+//!
+//! assert_eq!(q.get_str("q", &mut buf), Ok(Some("rust http!")));
+//! assert_eq!(q.get_u64("page"), Ok(Some(2)));
+//! assert_eq!(q.get_bool("flag"), Ok(Some(true)));
+//! assert_eq!(q.get_all("tag").collect::<Vec<_>>(), [&b"a"[..], b"b"]);
+//! assert_eq!(q.get_u64("missing"), Ok(None));
+//! ```
+
+use percent;
+use {Error, Result};
+
+/// Iterator over `key=value` pairs in the query of a request target.
+///
+/// Pairs are split on `&`, and each is split on its first `=`, with a pair lacking one
+/// yielded with an empty value. Empty pairs are skipped. Keys and values are yielded raw,
+/// so `+` and percent-encoded bytes are left for the caller to decode, such as with
+/// `percent::decode`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct QueryParams<'a>(&'a [u8]);
+
+impl<'a> QueryParams<'a> {
+    /// Create a new `QueryParams` iterator over the query in the given request target.
+    ///
+    /// The query begins after the first `?` and ends at any `#`, and there are no
+    /// parameters if the target has no `?`.
+    pub fn new(target: &'a [u8]) -> Self {
+        let query = match target.iter().position(|&b| b == b'?') {
+            Some(idx) => &target[idx + 1..],
+            None => &[],
+        };
+
+        QueryParams::from_query(query)
+    }
+
+    /// Create a new `QueryParams` iterator over the given bare query, without a leading
+    /// `?`.
+    pub fn from_query(query: &'a [u8]) -> Self {
+        match query.iter().position(|&b| b == b'#') {
+            Some(idx) => QueryParams(&query[..idx]),
+            None => QueryParams(query),
+        }
+    }
+}
+
+impl<'a> Iterator for QueryParams<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.0.is_empty() {
+                return None;
+            }
+
+            let pair = match self.0.iter().position(|&b| b == b'&') {
+                Some(idx) => {
+                    let pair = &self.0[..idx];
+                    self.0 = &self.0[idx + 1..];
+                    pair
+                },
+                None => std::mem::take(&mut self.0),
+            };
+
+            if pair.is_empty() {
+                continue;
+            }
+
+            return Some(match pair.iter().position(|&b| b == b'=') {
+                Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+                None => (pair, &[]),
+            });
+        }
+    }
+}
+
+/// Wrapper over the query of a request target for looking up parameters by name.
+///
+/// Names are compared against each key after the key is decoded from the form
+/// encoding, and the first matching parameter is used by the single-value getters.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Query<'a>(QueryParams<'a>);
+
+impl<'a> Query<'a> {
+    /// Create a new `Query` over the query in the given request target, as with
+    /// `QueryParams::new`.
+    pub fn new(target: &'a [u8]) -> Self { Query(QueryParams::new(target)) }
+
+    /// Create a new `Query` over the given bare query, without a leading `?`.
+    pub fn from_query(query: &'a [u8]) -> Self { Query(QueryParams::from_query(query)) }
+
+    /// Create an iterator over all the raw parameters.
+    pub fn params(&self) -> QueryParams<'a> { self.0 }
+
+    /// Retrieve the raw value of the first parameter with the given name.
+    pub fn get_raw(&self, name: &str) -> Option<&'a [u8]> { self.get_all(name).next() }
+
+    /// Create an iterator over the raw values of every parameter with the given name, in
+    /// order, which can be decoded with `decode_form`.
+    pub fn get_all<'n>(&self, name: &'n str) -> GetAll<'a, 'n> {
+        GetAll { params: self.0, name }
+    }
+
+    /// Try to decode the value of the first parameter with the given name into the given
+    /// buffer as a UTF-8 string.
+    ///
+    /// Return `Ok(None)` if there's no such parameter. Malformed percent-escapes and
+    /// invalid UTF-8 cause `Error::Syntax`, and a value too long for the buffer causes
+    /// `Error::Capacity`.
+    pub fn get_str<'b>(&self, name: &str, buf: &'b mut [u8]) -> Result<Option<&'b str>> {
+        let raw = match self.get_raw(name) {
+            Some(raw) => raw,
+            None => return Ok(None),
+        };
+
+        let len = decode_form(raw, buf)?;
+
+        std::str::from_utf8(&buf[..len]).map(Some).map_err(|_| Error::Syntax)
+    }
+
+    /// Try to parse the value of the first parameter with the given name as a decimal
+    /// `u64`.
+    ///
+    /// Return `Ok(None)` if there's no such parameter. A value that isn't only digits,
+    /// including an empty value, or that overflows causes `Error::Syntax`.
+    pub fn get_u64(&self, name: &str) -> Result<Option<u64>> {
+        let mut buf = [0; 20];
+
+        let digits = match self.get_raw(name) {
+            Some(raw) => match decode_form(raw, &mut buf) {
+                Ok(len) => &buf[..len],
+                Err(_) => return Err(Error::Syntax),
+            },
+            None => return Ok(None),
+        };
+
+        if digits.is_empty() {
+            return Err(Error::Syntax);
+        }
+
+        digits.iter().try_fold(0u64, |n, &b| {
+            if !b.is_ascii_digit() {
+                return Err(Error::Syntax);
+            }
+
+            n.checked_mul(10)
+                .and_then(|n| n.checked_add((b - b'0') as u64))
+                .ok_or(Error::Syntax)
+        }).map(Some)
+    }
+
+    /// Try to parse the value of the first parameter with the given name as a boolean.
+    ///
+    /// `true`, `1`, `yes`, and `on` are true and `false`, `0`, `no`, and `off` are false,
+    /// compared case-insensitively, and a parameter without a value, as in `?flag`, is
+    /// true. Return `Ok(None)` if there's no such parameter, and `Error::Syntax` for any
+    /// other value.
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>> {
+        let mut buf = [0; 5];
+
+        let val = match self.get_raw(name) {
+            Some(raw) => match decode_form(raw, &mut buf) {
+                Ok(len) => &buf[..len],
+                Err(_) => return Err(Error::Syntax),
+            },
+            None => return Ok(None),
+        };
+
+        let is = |s: &[&str]| s.iter().any(|s| val.eq_ignore_ascii_case(s.as_bytes()));
+
+        if val.is_empty() || is(&["true", "1", "yes", "on"]) {
+            Ok(Some(true))
+        } else if is(&["false", "0", "no", "off"]) {
+            Ok(Some(false))
+        } else {
+            Err(Error::Syntax)
+        }
+    }
+}
+
+/// Iterator over the raw values of every query parameter with a certain name.
+#[derive(Clone, Debug)]
+pub struct GetAll<'a, 'n> {
+    params: QueryParams<'a>,
+    name: &'n str,
+}
+
+impl<'a, 'n> Iterator for GetAll<'a, 'n> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let name = self.name;
+        self.params.find(|&(key, _)| key_eq(key, name)).map(|(_, val)| val)
+    }
+}
+
+/// Decode the given query key or value from the form encoding into the given buffer,
+/// where `+` is a space and percent-escapes are decoded as with `percent::decode`.
+///
+/// On success, return the number of bytes written.
+pub fn decode_form(raw: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let mut pos = 0;
+
+    for (i, part) in raw.split(|&b| b == b'+').enumerate() {
+        if i > 0 {
+            *buf.get_mut(pos).ok_or(Error::Capacity)? = b' ';
+            pos += 1;
+        }
+
+        pos += percent::decode(part, &mut buf[pos..])?;
+    }
+
+    Ok(pos)
+}
+
+/// Check if the given raw key decodes to the given name, passing through any malformed
+/// escapes.
+fn key_eq(raw: &[u8], name: &str) -> bool {
+    raw.split(|&b| b == b'+')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            let space = if i > 0 { Some(b' ') } else { None };
+            space.into_iter().chain(percent::decoded(part))
+        })
+        .eq(name.bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query_params() {
+        let mut q = QueryParams::new(b"/a?b=c&d=e=f&=g&h");
+        assert_eq!(q.next(), Some((&b"b"[..], &b"c"[..])));
+        assert_eq!(q.next(), Some((&b"d"[..], &b"e=f"[..])));
+        assert_eq!(q.next(), Some((&b""[..], &b"g"[..])));
+        assert_eq!(q.next(), Some((&b"h"[..], &b""[..])));
+        assert_eq!(q.next(), None);
+        assert_eq!(q.next(), None);
+
+        assert_eq!(QueryParams::new(b"/a").next(), None);
+        assert_eq!(QueryParams::new(b"/a?").next(), None);
+        assert_eq!(QueryParams::new(b"/a?&&").next(), None);
+        assert_eq!(QueryParams::new(b"/a?b#c=d").collect::<Vec<_>>(),
+            vec![(&b"b"[..], &b""[..])]);
+        assert_eq!(QueryParams::new(b"/a?b=?c").next(), Some((&b"b"[..], &b"?c"[..])));
+        assert_eq!(QueryParams::from_query(b"x=%20").next(),
+            Some((&b"x"[..], &b"%20"[..])));
+    }
+
+    #[test]
+    fn test_decode_form() {
+        let mut buf = [0; 8];
+
+        let mut d = |raw: &[u8]| decode_form(raw, &mut buf).map(|n| buf[..n].to_vec());
+
+        assert_eq!(d(b""), Ok(b"".to_vec()));
+        assert_eq!(d(b"a+b%2B"), Ok(b"a b+".to_vec()));
+        assert_eq!(d(b"++"), Ok(b"  ".to_vec()));
+        assert_eq!(d(b"%41%zz"), Err(Error::Syntax));
+        assert_eq!(d(b"12345678+"), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_query() {
+        let q = Query::new(b"/a?n=42&n=7&big=18446744073709551616&neg=-1&e=&b=Off&b2=x\
+                            &s=%C3%A9+x&bad=%ff&my+key=1&my%20key=2#n=1");
+        let mut buf = [0; 8];
+
+        assert_eq!(q.get_raw("n"), Some(&b"42"[..]));
+        assert_eq!(q.get_all("n").collect::<Vec<_>>(), [&b"42"[..], b"7"]);
+        assert_eq!(q.get_all("my key").collect::<Vec<_>>(), [&b"1"[..], b"2"]);
+        assert_eq!(q.get_all("x").count(), 0);
+        assert_eq!(q.params().count(), 11);
+
+        assert_eq!(q.get_u64("n"), Ok(Some(42)));
+        assert_eq!(q.get_u64("big"), Err(Error::Syntax));
+        assert_eq!(q.get_u64("neg"), Err(Error::Syntax));
+        assert_eq!(q.get_u64("e"), Err(Error::Syntax));
+        assert_eq!(q.get_u64("x"), Ok(None));
+
+        assert_eq!(q.get_bool("b"), Ok(Some(false)));
+        assert_eq!(q.get_bool("e"), Ok(Some(true)));
+        assert_eq!(q.get_bool("b2"), Err(Error::Syntax));
+        assert_eq!(q.get_bool("big"), Err(Error::Syntax));
+        assert_eq!(q.get_bool("x"), Ok(None));
+
+        assert_eq!(q.get_str("s", &mut buf), Ok(Some("é x")));
+        assert_eq!(q.get_str("bad", &mut buf), Err(Error::Syntax));
+        assert_eq!(q.get_str("big", &mut buf), Err(Error::Capacity));
+        assert_eq!(q.get_str("x", &mut buf), Ok(None));
+    }
+}
@@ -0,0 +1,183 @@
+//! Parsing of Range header values [RFC7233§3.1], for serving partial content such as
+//! resumable downloads.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::range::{byte_ranges, ByteRangeSpec};
+//!
+//! let mut r = byte_ranges(b"bytes=0-499, 500-, -200").unwrap();
+//!
+//! assert_eq!(r.next(), Some(Ok(ByteRangeSpec::FromTo(0, 499))));
+//! assert_eq!(r.next(), Some(Ok(ByteRangeSpec::From(500))));
+//! assert_eq!(r.next(), Some(Ok(ByteRangeSpec::Suffix(200))));
+//! assert_eq!(r.next(), None);
+//!
+//! assert_eq!(ByteRangeSpec::Suffix(200).bounds(1000), Some((800, 999)));
+//! ```
+
+use grammar::trim_ows;
+use list::{self, ListValues};
+use {Error, Result};
+
+/// Single range in a byte-range-set [RFC7233§2.1].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ByteRangeSpec {
+    /// Inclusive range of byte positions, such as `0-499`.
+    FromTo(u64, u64),
+    /// Every byte starting at the given position, such as `500-`.
+    From(u64),
+    /// The given number of bytes at the end of the representation, such as `-200`.
+    Suffix(u64),
+}
+
+impl ByteRangeSpec {
+    /// Resolve the range against a representation of the given length, clamping it to
+    /// the last byte.
+    ///
+    /// On success, return `Some((first, last))` with inclusive byte positions. Return
+    /// `None` if the range isn't satisfiable [RFC7233§2.1], as when it starts past the
+    /// end or is a suffix of zero length.
+    pub fn bounds(self, len: u64) -> Option<(u64, u64)> {
+        let (first, last) = match self {
+            ByteRangeSpec::FromTo(first, last) => (first, last),
+            ByteRangeSpec::From(first) => (first, u64::MAX),
+            ByteRangeSpec::Suffix(0) => return None,
+            ByteRangeSpec::Suffix(n) => (len.saturating_sub(n), u64::MAX),
+        };
+
+        if first >= len {
+            None
+        } else {
+            Some((first, last.min(len - 1)))
+        }
+    }
+}
+
+/// Try to parse the `bytes` unit from the given Range header value, with surrounding
+/// whitespace trimmed, and create an iterator over its byte-range-set.
+///
+/// Any other range unit, or a set without at least one range, causes `Error::Syntax`.
+pub fn byte_ranges(val: &[u8]) -> Result<ByteRanges<'_>> {
+    let val = trim_ows(val);
+
+    let (unit, set) = match val.iter().position(|&b| b == b'=') {
+        Some(idx) => (&val[..idx], &val[idx + 1..]),
+        None => return Err(Error::Syntax),
+    };
+
+    if !unit.eq_ignore_ascii_case(b"bytes") {
+        return Err(Error::Syntax);
+    }
+
+    let ranges = list::values(set);
+
+    if ranges.clone().next().is_none() {
+        return Err(Error::Syntax);
+    }
+
+    Ok(ByteRanges(ranges))
+}
+
+/// Iterator over the ranges in a byte-range-set.
+///
+/// Empty list elements are skipped, and a malformed range, including one whose last
+/// position precedes its first, yields `Error::Syntax`.
+#[derive(Clone, Debug)]
+pub struct ByteRanges<'a>(ListValues<'a>);
+
+impl<'a> Iterator for ByteRanges<'a> {
+    type Item = Result<ByteRangeSpec>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_spec))
+    }
+}
+
+/// Parse the given byte-range-spec or suffix-byte-range-spec.
+fn parse_spec(spec: &[u8]) -> Result<ByteRangeSpec> {
+    let dash = spec.iter().position(|&b| b == b'-').ok_or(Error::Syntax)?;
+    let (first, last) = (&spec[..dash], &spec[dash + 1..]);
+
+    if first.is_empty() {
+        return parse_pos(last).map(ByteRangeSpec::Suffix);
+    }
+
+    let first = parse_pos(first)?;
+
+    if last.is_empty() {
+        return Ok(ByteRangeSpec::From(first));
+    }
+
+    match parse_pos(last)? {
+        last if last < first => Err(Error::Syntax),
+        last => Ok(ByteRangeSpec::FromTo(first, last)),
+    }
+}
+
+/// Parse the given byte position, which must be at least one digit and fit in a `u64`.
+fn parse_pos(pos: &[u8]) -> Result<u64> {
+    if pos.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    pos.iter().try_fold(0u64, |n, &b| {
+        if !b.is_ascii_digit() {
+            return Err(Error::Syntax);
+        }
+
+        n.checked_mul(10)
+            .and_then(|n| n.checked_add((b - b'0') as u64))
+            .ok_or(Error::Syntax)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(parse_spec(b"0-0"), Ok(ByteRangeSpec::FromTo(0, 0)));
+        assert_eq!(parse_spec(b"10-20"), Ok(ByteRangeSpec::FromTo(10, 20)));
+        assert_eq!(parse_spec(b"10-"), Ok(ByteRangeSpec::From(10)));
+        assert_eq!(parse_spec(b"-0"), Ok(ByteRangeSpec::Suffix(0)));
+        assert_eq!(parse_spec(b"-18446744073709551615"),
+            Ok(ByteRangeSpec::Suffix(u64::MAX)));
+        assert_eq!(parse_spec(b"-18446744073709551616"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"20-10"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"-"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"10"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"1 - 2"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"+1-2"), Err(Error::Syntax));
+        assert_eq!(parse_spec(b"1-2-3"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_byte_ranges() {
+        let r: Vec<_> = byte_ranges(b" Bytes=0-1,, 5-\t, ").unwrap().collect();
+        assert_eq!(r, [Ok(ByteRangeSpec::FromTo(0, 1)), Ok(ByteRangeSpec::From(5))]);
+
+        let mut r = byte_ranges(b"bytes=1-0, 2-3").unwrap();
+        assert_eq!(r.next(), Some(Err(Error::Syntax)));
+        assert_eq!(r.next(), Some(Ok(ByteRangeSpec::FromTo(2, 3))));
+
+        assert!(byte_ranges(b"bytes=").is_err());
+        assert!(byte_ranges(b"bytes= , ").is_err());
+        assert!(byte_ranges(b"items=0-1").is_err());
+        assert!(byte_ranges(b"bytes 0-1").is_err());
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(ByteRangeSpec::FromTo(0, 499).bounds(1000), Some((0, 499)));
+        assert_eq!(ByteRangeSpec::FromTo(500, 2000).bounds(1000), Some((500, 999)));
+        assert_eq!(ByteRangeSpec::FromTo(1000, 1001).bounds(1000), None);
+        assert_eq!(ByteRangeSpec::From(999).bounds(1000), Some((999, 999)));
+        assert_eq!(ByteRangeSpec::From(0).bounds(0), None);
+        assert_eq!(ByteRangeSpec::Suffix(200).bounds(1000), Some((800, 999)));
+        assert_eq!(ByteRangeSpec::Suffix(2000).bounds(1000), Some((0, 999)));
+        assert_eq!(ByteRangeSpec::Suffix(0).bounds(1000), None);
+        assert_eq!(ByteRangeSpec::Suffix(1).bounds(0), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Request wrapper caching derived values, requiring the `alloc` feature.
+
+use std::cell::OnceCell;
+use std::ops::Deref;
+
+use percent::decoded;
+use {ParserConfig, Request, Result};
+
+/// A parsed `Request` that caches values derived from it, so they're computed at most
+/// once no matter how many layers of a router or middleware stack ask for them.
+#[derive(Clone, Debug)]
+pub struct RequestBuf<'a> {
+    /// Underlying request.
+    pub request: Request<'a>,
+
+    /// Decoded and normalized target path, once computed.
+    target: OnceCell<Vec<u8>>,
+}
+
+impl<'a> RequestBuf<'a> {
+    /// Try to parse the given bytes into a `RequestBuf`.
+    pub fn new(buf: &'a [u8]) -> Result<Self> {
+        Request::new(buf).map(RequestBuf::from)
+    }
+
+    /// Try to parse the given bytes into a `RequestBuf`, applying the limits in the given
+    /// configuration.
+    pub fn with_config(buf: &'a [u8], config: ParserConfig) -> Result<Self> {
+        Request::with_config(buf, config).map(RequestBuf::from)
+    }
+
+    /// Retrieve the path of the request target, with dot-segments removed
+    /// [RFC3986§5.2.4] and then percent-escapes decoded.
+    ///
+    /// Escaped dots count toward dot-segments, but dot-segments are removed before any
+    /// other escapes are decoded, so an encoded `%2F` never splits a segment. The result
+    /// can still contain `/` and `..` from such escapes, so use `path::safe_path` before
+    /// mapping it onto a filesystem. The path is computed on the first call and cached, and
+    /// the query isn't included. The result may not be valid UTF-8, so use
+    /// `percent::check_utf8` to apply a policy.
+    pub fn decoded_target(&self) -> &[u8] {
+        self.target.get_or_init(|| decode_path(self.request.line().target.as_bytes()))
+    }
+}
+
+impl<'a> From<Request<'a>> for RequestBuf<'a> {
+    fn from(request: Request<'a>) -> Self {
+        RequestBuf {
+            request,
+            target: OnceCell::new(),
+        }
+    }
+}
+
+impl<'a> Deref for RequestBuf<'a> {
+    type Target = Request<'a>;
+
+    fn deref(&self) -> &Request<'a> { &self.request }
+}
+
+/// Remove dot-segments from the path of the given target and then decode it.
+fn decode_path(target: &[u8]) -> Vec<u8> {
+    let path = match target.iter().position(|&b| b == b'?') {
+        Some(idx) => &target[..idx],
+        None => target,
+    };
+
+    let absolute = path.first() == Some(&b'/');
+    let rel = if absolute { &path[1..] } else { path };
+
+    let mut segs: Vec<&[u8]> = Vec::new();
+
+    // Whether the output should end with a slash.
+    let mut trailing = false;
+
+    for seg in rel.split(|&b| b == b'/') {
+        // Escaped dots are equivalent to plain ones [RFC3986§2.3].
+        let dots = if decoded(seg).all(|b| b == b'.') { decoded(seg).count() } else { 0 };
+
+        match dots {
+            1 => trailing = true,
+            2 => {
+                segs.pop();
+                trailing = true;
+            },
+            _ => {
+                segs.push(seg);
+                trailing = false;
+            },
+        }
+    }
+
+    let mut out = Vec::with_capacity(path.len());
+
+    for (i, seg) in segs.iter().enumerate() {
+        if absolute || i > 0 {
+            out.push(b'/');
+        }
+
+        out.extend(decoded(seg));
+    }
+
+    if trailing && !segs.is_empty() || absolute && out.is_empty() {
+        out.push(b'/');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(decode_path(b"/"), b"/");
+        assert_eq!(decode_path(b"/a/b%20c?d=%20"), b"/a/b c");
+        assert_eq!(decode_path(b"/a/./b/../c/"), b"/a/c/");
+        assert_eq!(decode_path(b"/a/b/.."), b"/a/");
+        assert_eq!(decode_path(b"/../../a"), b"/a");
+        assert_eq!(decode_path(b"/a/.."), b"/");
+        assert_eq!(decode_path(b"/a%2F..%2Fb/%2e%2e/c"), b"/c");
+        assert_eq!(decode_path(b"/a%2F..%2Fb"), b"/a/../b");
+        assert_eq!(decode_path(b"*"), b"*");
+        assert_eq!(decode_path(b"a/./b"), b"a/b");
+    }
+
+    #[test]
+    fn test_request_buf() {
+        let r = RequestBuf::new(b"GET /x/../caf%C3%A9?q HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+
+        assert_eq!(r.decoded_target(), "/caf\u{e9}".as_bytes());
+        assert_eq!(r.decoded_target().as_ptr(), r.decoded_target().as_ptr());
+        assert_eq!(r.host(), Ok(Some("a")));
+        assert_eq!(r.line().target, "/x/../caf%C3%A9?q");
+    }
+}
@@ -0,0 +1,280 @@
+//! Batteries-included view over a complete request.
+
+use body;
+use grammar::trim_ows;
+#[cfg(feature = "url")]
+use host;
+use idempotency;
+#[cfg(feature = "url")]
+use params::{self, split_unquoted};
+use proxy;
+use {Error, Head, Headers, ParserConfig, RequestLine, Result, Version};
+#[cfg(feature = "alloc")]
+use {DuplicatePolicy, HeaderMap};
+
+/// A parsed request: the head along with the bytes that follow it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Request<'a> {
+    /// Request-Line and header fields.
+    pub head: Head<'a>,
+
+    /// Bytes following the head.
+    ///
+    /// This is everything remaining in the buffer, which may be only part of the body or
+    /// may extend past it into a pipelined request, depending on the message framing.
+    pub body: &'a [u8],
+}
+
+impl<'a> Request<'a> {
+    /// Try to parse the given bytes into a `Request`.
+    pub fn new(buf: &'a [u8]) -> Result<Self> {
+        Request::with_config(buf, ParserConfig::new())
+    }
+
+    /// Try to parse the given bytes into a `Request`, applying the limits in the given
+    /// configuration.
+    pub fn with_config(buf: &'a [u8], config: ParserConfig) -> Result<Self> {
+        let (head, body) = Head::with_config(buf, config)?;
+        Ok(Request { head, body })
+    }
+
+    /// Retrieve the Request-Line.
+    pub fn line(&self) -> RequestLine<'a> { self.head.line }
+
+    /// Create a new iterator over all header fields.
+    pub fn headers(&self) -> Headers<'a> { self.head.headers() }
+
+    /// Retrieve the value, with surrounding whitespace trimmed, of the first header with
+    /// the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers()
+            .filter_map(|h| h.ok())
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| trim_ows(h.val))
+    }
+
+    /// Try to collect the header fields into a `HeaderMap`, applying the given duplicate
+    /// policy, such as `DuplicatePolicy::standard()`.
+    ///
+    /// This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn header_map(&self, policy: DuplicatePolicy) -> Result<HeaderMap<'a>> {
+        self.headers().to_map_with(policy)
+    }
+
+    /// Retrieve the Content-Length of the body, or `None` if the header isn't present.
+    ///
+    /// If the header appears multiple times, all occurrences must agree [RFC7230§3.3.2].
+    pub fn content_length(&self) -> Result<Option<u64>> {
+        body::content_length(self.headers().filter_map(|h| h.ok()))
+    }
+
+    /// Retrieve the raw Content-Type header value, with surrounding whitespace trimmed.
+    pub fn content_type(&self) -> Option<&'a [u8]> { self.header("Content-Type") }
+
+    /// Retrieve the Host header value, with surrounding whitespace trimmed.
+    pub fn host(&self) -> Result<Option<&'a str>> {
+        match self.header("Host") {
+            Some(h) => std::str::from_utf8(h).map(Some).map_err(|_| Error::Syntax),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the key from the Idempotency-Key header, limited to
+    /// `idempotency::DEFAULT_MAX_LEN` bytes, or `None` if the header isn't present.
+    pub fn idempotency_key(&self) -> Result<Option<&'a str>> {
+        match self.header("Idempotency-Key") {
+            Some(h) => idempotency::parse_idempotency_key(h, idempotency::DEFAULT_MAX_LEN)
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Try to form the fully qualified URL of the requested resource, as a `url::Url`.
+    ///
+    /// An absolute-form target is used as given. Otherwise, the URL is formed from the
+    /// Host header and an origin-form or authority-form target [RFC7230§5.5], with the
+    /// given scheme, or, if none is given, the `proto` of the first Forwarded element
+    /// [RFC7239§5.4] and then `http`. Since any client can send a Forwarded header, a
+    /// scheme should be given unless the request passed through a trusted proxy.
+    ///
+    /// A missing or malformed Host header, an asterisk-form target, or any other value
+    /// that doesn't form a valid URL causes `Error::Syntax`.
+    #[cfg(feature = "url")]
+    pub fn url(&self, scheme: Option<&str>) -> Result<::url::Url> {
+        let target = self.line().target;
+
+        if target.contains("://") {
+            return ::url::Url::parse(target).map_err(|_| Error::Syntax);
+        }
+
+        let host = self.host()?.ok_or(Error::Syntax)?;
+        host::parse_host(host.as_bytes())?;
+
+        let path = if target.starts_with('/') {
+            target
+        } else if self.line().method == "CONNECT" {
+            ""
+        } else {
+            return Err(Error::Syntax);
+        };
+
+        let scheme = match scheme {
+            Some(s) => s,
+            None => self.forwarded_proto().unwrap_or("http"),
+        };
+
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+            scheme.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+
+        if !valid_scheme {
+            return Err(Error::Syntax);
+        }
+
+        ::url::Url::parse(&format!("{}://{}{}", scheme, host, path))
+            .map_err(|_| Error::Syntax)
+    }
+
+    /// Retrieve the `proto` parameter of the first element of the first Forwarded
+    /// header, if there is one.
+    #[cfg(feature = "url")]
+    fn forwarded_proto(&self) -> Option<&'a str> {
+        let (elem, _) = split_unquoted(self.header("Forwarded")?, b',').ok()?;
+
+        params::iter(elem)
+            .filter_map(|p| p.ok())
+            .find(|p| p.name.eq_ignore_ascii_case("proto"))
+            .and_then(|p| std::str::from_utf8(params::unquote(p.val?)).ok())
+    }
+
+    /// Check if the connection should persist after this request [RFC7230§6.3].
+    ///
+    /// HTTP/1.1 connections persist unless the `close` option is given, and HTTP/1.0
+    /// connections persist only if the `keep-alive` option is given.
+    pub fn is_keep_alive(&self) -> bool {
+        if self.has_connection_option("close") {
+            false
+        } else if self.head.line.version_parsed() == Ok(Version::HTTP_10) {
+            self.has_connection_option("keep-alive")
+        } else {
+            true
+        }
+    }
+
+    /// Check if the given option is listed in any Connection header.
+    fn has_connection_option(&self, opt: &str) -> bool {
+        proxy::connection_options(self.headers())
+            .any(|o| o.is_ok_and(|o| o.eq_ignore_ascii_case(opt.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let r = Request::new(b"POST /a HTTP/1.1\r\nHost:  example.com \r\n\
+                               content-type: text/plain\r\nContent-Length: 4\r\n\r\nbody")
+            .unwrap();
+
+        assert_eq!(r.line().method, "POST");
+        assert_eq!(r.headers().count(), 3);
+        assert_eq!(r.header("host"), Some(&b"example.com"[..]));
+        assert_eq!(r.header("Accept"), None);
+        assert_eq!(r.host(), Ok(Some("example.com")));
+        assert_eq!(r.content_type(), Some(&b"text/plain"[..]));
+        assert_eq!(r.content_length(), Ok(Some(4)));
+        assert_eq!(r.idempotency_key(), Ok(None));
+        assert!(r.is_keep_alive());
+        assert_eq!(r.body, b"body");
+
+        let r = Request::new(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(r.host(), Ok(None));
+        assert_eq!(r.content_length(), Ok(None));
+        assert!(!r.is_keep_alive());
+        assert_eq!(r.body, b"");
+
+        assert_eq!(Request::new(b"GET / HTTP/1.1\r\n"), Err(Error::Partial));
+
+        let r = Request::new(b"POST / HTTP/1.1\r\nidempotency-key: \"k1\"\r\n\r\n").unwrap();
+        assert_eq!(r.idempotency_key(), Ok(Some("k1")));
+        let r = Request::new(b"POST / HTTP/1.1\r\nIdempotency-Key: \"\"\r\n\r\n").unwrap();
+        assert_eq!(r.idempotency_key(), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_content_length() {
+        let r = Request::new(b"GET / HTTP/1.1\r\nContent-Length: 5\r\ncontent-length: 5\r\n\r\n")
+            .unwrap();
+        assert_eq!(r.content_length(), Ok(Some(5)));
+
+        let r = Request::new(b"GET / HTTP/1.1\r\nContent-Length: 5\r\ncontent-length: 6\r\n\r\n")
+            .unwrap();
+        assert_eq!(r.content_length(), Err(Error::Syntax));
+
+        assert_eq!(body::parse_content_length(b"0"), Ok(0));
+        assert_eq!(body::parse_content_length(b" 18446744073709551615 "), Ok(u64::MAX));
+        assert_eq!(body::parse_content_length(b"18446744073709551616"), Err(Error::Syntax));
+        assert_eq!(body::parse_content_length(b"+5"), Err(Error::Syntax));
+        assert_eq!(body::parse_content_length(b"5 5"), Err(Error::Syntax));
+        assert_eq!(body::parse_content_length(b""), Err(Error::Syntax));
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_url() {
+        let url = |buf: &[u8], scheme| Request::new(buf).unwrap().url(scheme)
+            .map(|u| u.to_string());
+
+        let r = b"GET /a/../b?c=d HTTP/1.1\r\nHost: Example.com:8080\r\n\r\n";
+        assert_eq!(url(r, None), Ok("http://example.com:8080/b?c=d".to_string()));
+        assert_eq!(url(r, Some("https")), Ok("https://example.com:8080/b?c=d".to_string()));
+
+        let r = b"GET /a HTTP/1.1\r\nHost: example.com\r\n\
+                  Forwarded: for=1.2.3.4;Proto=\"https\", proto=http\r\n\r\n";
+        assert_eq!(url(r, None), Ok("https://example.com/a".to_string()));
+        assert_eq!(url(r, Some("http")), Ok("http://example.com/a".to_string()));
+
+        let r = b"GET /a HTTP/1.1\r\nHost: example.com\r\nForwarded: proto=x:y\r\n\r\n";
+        assert_eq!(url(r, None), Err(Error::Syntax));
+
+        let r = b"GET http://other.com/x HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert_eq!(url(r, Some("https")), Ok("http://other.com/x".to_string()));
+
+        let r = b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n";
+        assert_eq!(url(r, Some("https")), Ok("https://example.com/".to_string()));
+
+        let plain = |host: &str, scheme| {
+            url(format!("GET /a HTTP/1.1\r\n{}\r\n", host).as_bytes(), scheme)
+        };
+
+        assert_eq!(plain("", None), Err(Error::Syntax));
+        assert_eq!(plain("Host: a/b\r\n", None), Err(Error::Syntax));
+        assert_eq!(plain("Host: a@b\r\n", None), Err(Error::Syntax));
+        assert_eq!(plain("Host: a\r\n", Some("1x")), Err(Error::Syntax));
+        assert_eq!(url(b"OPTIONS * HTTP/1.1\r\nHost: a\r\n\r\n", None), Err(Error::Syntax));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_header_map() {
+        let r = Request::new(b"GET / HTTP/1.1\r\nHost: a\r\nTE: x\r\nTE: y\r\n\r\n").unwrap();
+        let m = r.header_map(DuplicatePolicy::standard()).unwrap();
+        assert_eq!(m.value("te").as_deref(), Some(&b"x, y"[..]));
+
+        let r = Request::new(b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n").unwrap();
+        assert_eq!(r.header_map(DuplicatePolicy::standard()), Err(Error::Syntax));
+        assert!(r.header_map(DuplicatePolicy::keep_all()).is_ok());
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let ka = |buf: &[u8]| Request::new(buf).unwrap().is_keep_alive();
+
+        assert!(ka(b"GET / HTTP/1.1\r\nConnection: Upgrade\r\n\r\n"));
+        assert!(!ka(b"GET / HTTP/1.1\r\nConnection: Upgrade, Close\r\n\r\n"));
+        assert!(ka(b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n"));
+        assert!(!ka(b"GET / HTTP/1.0\r\nConnection: keep-alive, close\r\n\r\n"));
+    }
+}
@@ -0,0 +1,595 @@
+//! Helpers for HTTP responses.
+
+use memchr::memchr;
+
+use date;
+use grammar::{is_ows, is_token, is_vchar, trim_ows};
+use list::{self, ListValues};
+use params;
+use writer::{write_digits, write_field, Cursor};
+use {next_line, Error, Headers, Result, StatusLine};
+
+/// Retrieve the standard reason phrase for the given status code, if there is one
+/// [RFC7231§6.1].
+pub const fn reason(status: u16) -> Option<&'static str> {
+    Some(match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        103 => "Early Hints",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        203 => "Non-Authoritative Information",
+        204 => "No Content",
+        205 => "Reset Content",
+        206 => "Partial Content",
+        300 => "Multiple Choices",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        305 => "Use Proxy",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        402 => "Payment Required",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        407 => "Proxy Authentication Required",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        411 => "Length Required",
+        412 => "Precondition Failed",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        415 => "Unsupported Media Type",
+        416 => "Range Not Satisfiable",
+        417 => "Expectation Failed",
+        426 => "Upgrade Required",
+        428 => "Precondition Required",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        _ => return None,
+    })
+}
+
+/// Writer for complete responses in servers that need nothing more elaborate.
+pub struct SimpleResponse;
+
+impl SimpleResponse {
+    /// Write a complete HTTP/1.1 response with the given status, headers, and body into
+    /// the given buffer.
+    ///
+    /// Content-Length, Date, and Connection headers are added unless already present in
+    /// `headers`, with the connection marked `close`. The status must be a 3-digit code,
+    /// and the standard reason phrase is used if there is one. Each header name must be a
+    /// token and each value free of control characters other than tab, or
+    /// `Error::Syntax` is returned.
+    ///
+    /// Responses with a 1xx, 204, or 304 status can't have a body [RFC7230§3.3], so
+    /// Content-Length isn't added for them and a non-empty body causes `Error::Syntax`.
+    ///
+    /// On success, return the number of bytes written.
+    pub fn write(status: u16, headers: &[(&str, &[u8])], body: &[u8], buf: &mut [u8])
+        -> Result<usize>
+    {
+        SimpleResponse::write_at(status, headers, body, date::now(), buf)
+    }
+
+    /// Write a complete response like `write`, but with the given time, in seconds since
+    /// the Unix epoch, used for the Date header.
+    pub fn write_at(status: u16, headers: &[(&str, &[u8])], body: &[u8], now: u64,
+                    buf: &mut [u8])
+        -> Result<usize>
+    {
+        if !(100..=999).contains(&status) {
+            return Err(Error::Syntax);
+        }
+
+        let bodiless = status < 200 || status == 204 || status == 304;
+
+        if bodiless && !body.is_empty() {
+            return Err(Error::Syntax);
+        }
+
+        let mut out = Cursor::new(buf);
+        let has = |name: &str| headers.iter().any(|&(n, _)| n.eq_ignore_ascii_case(name));
+
+        // The status is known to fill exactly 3 digits.
+        let mut code = [0; 3];
+        write_digits(status as u64, 10, &mut code);
+
+        out.push(b"HTTP/1.1 ")?;
+        out.push(&code)?;
+        out.push(b" ")?;
+        out.push(reason(status).unwrap_or("").as_bytes())?;
+        out.push(b"\r\n")?;
+
+        for &(name, val) in headers {
+            write_field(&mut out, name, val)?;
+        }
+
+        if !has("Date") {
+            write_field(&mut out, "Date", &date::format(now))?;
+        }
+
+        if !bodiless && !has("Content-Length") {
+            let mut digits = [0; 20];
+            let start = write_digits(body.len() as u64, 10, &mut digits);
+            write_field(&mut out, "Content-Length", &digits[start..])?;
+        }
+
+        if !has("Connection") {
+            write_field(&mut out, "Connection", b"close")?;
+        }
+
+        out.push(b"\r\n")?;
+        out.push(body)?;
+
+        Ok(out.pos())
+    }
+}
+
+/// Leniency flags for parsing the start of a response, since real servers emit
+/// surprising Status-Lines that clients must survive.
+///
+/// Each flag accepts a deviation that `ResponseConfig::strict` rejects with
+/// `Error::Syntax`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ResponseConfig {
+    /// Whether to accept a status code that isn't followed by the space required before
+    /// the reason phrase [RFC7230§3.1.2], as in `HTTP/1.1 204`, which several embedded
+    /// servers emit.
+    pub missing_space: bool,
+
+    /// Whether to accept a reason phrase containing bytes other than HTAB, SP, VCHAR,
+    /// and obs-text, such as control characters.
+    pub invalid_reason: bool,
+
+    /// Whether to skip over lines between the Status-Line and the first header field
+    /// that aren't field lines, such as lines beginning with whitespace or lacking a
+    /// colon.
+    pub skip_junk: bool,
+}
+
+impl ResponseConfig {
+    /// Create a new `ResponseConfig` that rejects every deviation.
+    pub const fn strict() -> Self {
+        ResponseConfig { missing_space: false, invalid_reason: false, skip_junk: false }
+    }
+
+    /// Create a new `ResponseConfig` that accepts every deviation.
+    pub const fn lenient() -> Self {
+        ResponseConfig { missing_space: true, invalid_reason: true, skip_junk: true }
+    }
+}
+
+/// Try to parse the Status-Line at the start of the given bytes, checking it and any
+/// lines before the first header field against the given configuration.
+///
+/// On success, return `Ok((sl, rest))`, where `sl` is the `StatusLine` and `rest` is a
+/// slice that begins at the first header field line, after any skipped junk lines. The
+/// Status-Line itself is parsed with `StatusLine::with_config`.
+pub fn parse_status<'a>(buf: &'a [u8], config: ResponseConfig)
+    -> Result<(StatusLine<'a>, &'a [u8])>
+{
+    let (sl, mut rest) = StatusLine::with_config(buf, config)?;
+
+    // Only complete lines are examined, so junk in an incomplete line is detected once
+    // the caller parses again with more bytes.
+    while let Ok((line, next)) = next_line(rest) {
+        let junk = !line.is_empty() &&
+            (line.first().is_some_and(|&b| is_ows(b)) || memchr(b':', line).is_none());
+
+        if !junk {
+            break;
+        }
+
+        if !config.skip_junk {
+            return Err(Error::Syntax);
+        }
+
+        rest = next;
+    }
+
+    Ok((sl, rest))
+}
+
+/// A complete response head: the Status-Line followed by all header fields.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ResponseHead<'a> {
+    /// Status-Line that begins the head.
+    pub line: StatusLine<'a>,
+
+    /// Header block, including the terminating empty line.
+    pub block: &'a [u8],
+}
+
+impl<'a> ResponseHead<'a> {
+    /// Create a new iterator over the header fields.
+    pub fn headers(&self) -> Headers<'a> { Headers::new(self.block) }
+
+    /// Check if this is an interim response that precedes the final response
+    /// [RFC7231§6.2], which is any 1xx response other than 101 Switching Protocols.
+    pub fn is_interim(&self) -> bool {
+        self.line.code / 100 == 1 && self.line.code != 101
+    }
+}
+
+/// Create an iterator over the response heads at the start of the given bytes: any
+/// number of interim 1xx heads, such as 100 Continue and 103 Early Hints, followed by
+/// the final head.
+///
+/// Each Status-Line is parsed with `parse_status` and the given configuration.
+pub fn heads<'a>(buf: &'a [u8], config: ResponseConfig) -> ResponseHeads<'a> {
+    ResponseHeads { buf, config, done: false }
+}
+
+/// Iterator over interim response heads followed by the final response head.
+///
+/// Iteration ends after the final head, at which point `into_inner` gives the bytes that
+/// begin the response body. If an error is yielded, including `Error::Partial` when a
+/// head is incomplete, iteration stops.
+pub struct ResponseHeads<'a> {
+    buf: &'a [u8],
+    config: ResponseConfig,
+    done: bool,
+}
+
+impl<'a> ResponseHeads<'a> {
+    /// Retrieve the remaining bytes that haven't been processed.
+    ///
+    /// If called after the final head, this slice begins directly after it.
+    pub fn into_inner(self) -> &'a [u8] { self.buf }
+
+    /// Parse the next head.
+    fn parse(&mut self) -> Result<ResponseHead<'a>> {
+        let (line, block) = parse_status(self.buf, self.config)?;
+        let mut headers = Headers::new(block);
+
+        for h in headers.by_ref() {
+            h?;
+        }
+
+        let rest = headers.into_inner();
+        self.buf = rest;
+
+        Ok(ResponseHead { line, block: &block[..block.len() - rest.len()] })
+    }
+}
+
+impl<'a> Iterator for ResponseHeads<'a> {
+    type Item = Result<ResponseHead<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let head = self.parse();
+
+        self.done = match head {
+            Ok(ref h) => !h.is_interim(),
+            Err(_) => true,
+        };
+
+        Some(head)
+    }
+}
+
+/// Parsed Alt-Svc header value [RFC7838§3].
+#[derive(Clone, Debug)]
+pub enum AltSvc<'a> {
+    /// All alternative services for the origin should be invalidated.
+    Clear,
+    /// Iterator over the advertised alternative services.
+    Services(AltServices<'a>),
+}
+
+/// Try to parse the given Alt-Svc header value.
+///
+/// Empty list elements are skipped [RFC7230§7], and entries are only parsed as they're
+/// iterated.
+pub fn alt_svc<'a>(val: &'a [u8]) -> Result<AltSvc<'a>> {
+    if trim_ows(val) == b"clear" {
+        Ok(AltSvc::Clear)
+    } else {
+        Ok(AltSvc::Services(AltServices(list::values(val))))
+    }
+}
+
+/// A single alternative service advertised in an Alt-Svc header value.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct AltService<'a> {
+    /// ALPN protocol identifier, such as `h3`, which is left percent-encoded.
+    pub protocol: &'a str,
+
+    /// Host of the alternative service, which is empty if it's the same as the origin's.
+    ///
+    /// An IPv6 literal keeps its brackets.
+    pub host: &'a str,
+
+    /// Port of the alternative service.
+    pub port: u16,
+
+    /// Number of seconds the service may be considered fresh, which defaults to 24 hours.
+    pub max_age: u64,
+
+    /// Whether the service should persist across network configuration changes.
+    pub persist: bool,
+}
+
+/// Iterator over the alternative services in an Alt-Svc header value.
+#[derive(Clone, Debug)]
+pub struct AltServices<'a>(ListValues<'a>);
+
+impl<'a> Iterator for AltServices<'a> {
+    type Item = Result<AltService<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_alt_service))
+    }
+}
+
+/// Try to parse the given Alt-Svc list element.
+fn parse_alt_service(entry: &[u8]) -> Result<AltService<'_>> {
+    let mut p = params::iter(entry);
+
+    let alt = p.next().ok_or(Error::Syntax)??;
+    let authority = alt.val.ok_or(Error::Syntax)?;
+
+    // Authority must be a quoted-string.
+    if authority.len() < 2 || !authority.starts_with(b"\"") || !authority.ends_with(b"\"") {
+        return Err(Error::Syntax);
+    }
+
+    let authority = std::str::from_utf8(params::unquote(authority))
+        .map_err(|_| Error::Syntax)?;
+
+    let (host, port) = authority.rsplit_once(':').ok_or(Error::Syntax)?;
+
+    if !is_token(alt.name.as_bytes()) || !host.bytes().all(|b| is_vchar(b) && b != b'\\') {
+        return Err(Error::Syntax);
+    }
+
+    let port = match parse_decimal(port.as_bytes())? {
+        p if p <= u16::MAX as u64 => p as u16,
+        _ => return Err(Error::Syntax),
+    };
+
+    let mut svc = AltService {
+        protocol: alt.name,
+        host,
+        port,
+        max_age: 86400,
+        persist: false,
+    };
+
+    for param in p {
+        let param = param?;
+        let val = params::unquote(param.val.unwrap_or_default());
+
+        if param.name.eq_ignore_ascii_case("ma") {
+            svc.max_age = parse_decimal(val)?;
+        } else if param.name.eq_ignore_ascii_case("persist") {
+            // Values other than 1 are ignored [RFC7838§3.1].
+            svc.persist |= val == b"1";
+        }
+    }
+
+    Ok(svc)
+}
+
+/// Parse the given bytes as a nonempty decimal integer.
+fn parse_decimal(val: &[u8]) -> Result<u64> {
+    if val.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    val.iter().try_fold(0u64, |n, &b| {
+        if !b.is_ascii_digit() {
+            return Err(Error::Syntax);
+        }
+
+        n.checked_mul(10)
+            .and_then(|n| n.checked_add((b - b'0') as u64))
+            .ok_or(Error::Syntax)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reason() {
+        assert_eq!(reason(200), Some("OK"));
+        assert_eq!(reason(404), Some("Not Found"));
+        assert_eq!(reason(299), None);
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let strict = ResponseConfig::strict();
+        let lenient = ResponseConfig::lenient();
+
+        let buf = b"HTTP/1.1 200 OK\r\nA: b\r\n\r\n";
+        let (sl, rest) = parse_status(buf, strict).unwrap();
+        assert_eq!(sl.reason, b"OK");
+        assert_eq!(rest, b"A: b\r\n\r\n");
+
+        let (sl, rest) = parse_status(b"HTTP/1.1 200 \r\n\r\n", strict).unwrap();
+        assert_eq!(sl.reason, b"");
+        assert_eq!(rest, b"\r\n");
+
+        let (sl, _) = parse_status(b"HTTP/1.1 200 \xe9t\xe9\t!\r\n\r\n", strict).unwrap();
+        assert_eq!(sl.reason, b"\xe9t\xe9\t!");
+
+        let buf = b"HTTP/1.1 204\r\n\r\n";
+        assert_eq!(parse_status(buf, strict), Err(Error::Syntax));
+        assert!(parse_status(buf, ResponseConfig { missing_space: true, ..strict }).is_ok());
+
+        let buf = b"HTTP/1.1 200 O\x01K\r\n\r\n";
+        assert_eq!(parse_status(buf, strict), Err(Error::Syntax));
+        assert!(parse_status(buf, ResponseConfig { invalid_reason: true, ..strict }).is_ok());
+
+        let buf = b"HTTP/1.1 200 OK\r\n  junk\r\n<html>\r\nA: b\r\n\r\n";
+        assert_eq!(parse_status(buf, strict), Err(Error::Syntax));
+        let (_, rest) = parse_status(buf, lenient).unwrap();
+        assert_eq!(rest, b"A: b\r\n\r\n");
+
+        let (_, rest) = parse_status(b"HTTP/1.1 200 OK\r\n junk", strict).unwrap();
+        assert_eq!(rest, b" junk");
+
+        assert_eq!(parse_status(b"HTTP/1.1 200 OK", lenient), Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_heads() {
+        let buf = b"HTTP/1.1 100 Continue\r\n\r\n\
+                    HTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\n\
+                    HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi";
+
+        let strict = ResponseConfig::strict();
+        let mut h = heads(buf, strict);
+
+        let x = h.next().unwrap().unwrap();
+        assert_eq!(x.line.code, 100);
+        assert_eq!(x.block, b"\r\n");
+        assert!(x.is_interim());
+
+        let x = h.next().unwrap().unwrap();
+        assert_eq!(x.line.code, 103);
+        assert_eq!(x.headers().next().unwrap().unwrap().name, "Link");
+        assert!(x.is_interim());
+
+        let x = h.next().unwrap().unwrap();
+        assert_eq!(x.line.code, 200);
+        assert_eq!(x.block, b"Content-Length: 2\r\n\r\n");
+        assert!(!x.is_interim());
+
+        assert_eq!(h.next(), None);
+        assert_eq!(h.into_inner(), b"hi");
+
+        let mut h = heads(b"HTTP/1.1 101 Switching Protocols\r\n\r\nrest", strict);
+        assert!(!h.next().unwrap().unwrap().is_interim());
+        assert_eq!(h.next(), None);
+        assert_eq!(h.into_inner(), b"rest");
+
+        let mut h = heads(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 2", strict);
+        assert!(h.next().unwrap().is_ok());
+        assert_eq!(h.next(), Some(Err(Error::Partial)));
+        assert_eq!(h.next(), None);
+
+        let mut h = heads(b"HTTP/1.1 100 Continue\r\nA\xff: b\r\n\r\n", strict);
+        assert_eq!(h.next(), Some(Err(Error::Syntax)));
+    }
+
+    #[test]
+    fn test_alt_svc() {
+        let val = b"h3=\":443\"; ma=3600; persist=1, h2=\"alt.example.com:8000\", \
+                    h3-29=\"[::1]:443\";persist=0;ma=\"60\";x=y";
+
+        let mut s = match alt_svc(val).unwrap() {
+            AltSvc::Services(s) => s,
+            AltSvc::Clear => panic!(),
+        };
+
+        assert_eq!(s.next(), Some(Ok(AltService {
+            protocol: "h3",
+            host: "",
+            port: 443,
+            max_age: 3600,
+            persist: true,
+        })));
+        assert_eq!(s.next(), Some(Ok(AltService {
+            protocol: "h2",
+            host: "alt.example.com",
+            port: 8000,
+            max_age: 86400,
+            persist: false,
+        })));
+        assert_eq!(s.next(), Some(Ok(AltService {
+            protocol: "h3-29",
+            host: "[::1]",
+            port: 443,
+            max_age: 60,
+            persist: false,
+        })));
+        assert_eq!(s.next(), None);
+
+        assert!(matches!(alt_svc(b" clear "), Ok(AltSvc::Clear)));
+
+        let first = |val| match alt_svc(val).unwrap() {
+            AltSvc::Services(mut s) => s.next(),
+            AltSvc::Clear => None,
+        };
+
+        assert_eq!(first(b"h3=:443"), Some(Err(Error::Syntax)));
+        assert_eq!(first(b"h3=\"443\""), Some(Err(Error::Syntax)));
+        assert_eq!(first(b"h3=\":65536\""), Some(Err(Error::Syntax)));
+        assert_eq!(first(b"h3=\":443\"; ma=x"), Some(Err(Error::Syntax)));
+        assert_eq!(first(b"h3"), Some(Err(Error::Syntax)));
+        assert_eq!(first(b"h3=\":443"), Some(Err(Error::Syntax)));
+        assert_eq!(first(b" , "), None);
+    }
+
+    #[test]
+    fn test_simple_response() {
+        let mut buf = [0; 256];
+
+        let len = SimpleResponse::write_at(200, &[("Content-Type", b"text/plain")],
+            b"hello", 784111777, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\
+            Date: Sun, 06 Nov 1994 08:49:37 GMT\r\nContent-Length: 5\r\n\
+            Connection: close\r\n\r\nhello"[..]);
+
+        let len = SimpleResponse::write_at(599, &[
+            ("content-length", b"0"),
+            ("DATE", b"x"),
+            ("Connection", b"keep-alive"),
+        ], b"", 0, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"HTTP/1.1 599 \r\ncontent-length: 0\r\nDATE: x\r\n\
+            Connection: keep-alive\r\n\r\n"[..]);
+
+        for &status in &[100, 101, 204, 304] {
+            let len = SimpleResponse::write_at(status, &[], b"", 0, &mut buf).unwrap();
+            assert!(buf[..len].ends_with(b"GMT\r\nConnection: close\r\n\r\n"));
+            assert!(!buf[..len].windows(15).any(|w| w.eq_ignore_ascii_case(b"Content-Length:")));
+
+            assert_eq!(SimpleResponse::write_at(status, &[], b"x", 0, &mut buf),
+                Err(Error::Syntax));
+        }
+
+        let len = SimpleResponse::write_at(205, &[], b"", 0, &mut buf).unwrap();
+        assert!(buf[..len].ends_with(b"Content-Length: 0\r\nConnection: close\r\n\r\n"));
+
+        assert!(SimpleResponse::write(404, &[], b"", &mut buf).is_ok());
+        assert_eq!(SimpleResponse::write_at(99, &[], b"", 0, &mut buf), Err(Error::Syntax));
+        assert_eq!(SimpleResponse::write_at(200, &[], b"", 0, &mut buf[..20]),
+            Err(Error::Capacity));
+
+        assert_eq!(SimpleResponse::write_at(200, &[("X-A", b"a\r\nSet-Cookie: x")], b"", 0,
+            &mut buf), Err(Error::Syntax));
+        assert_eq!(SimpleResponse::write_at(200, &[("X-A", b"a\nb")], b"", 0, &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(SimpleResponse::write_at(200, &[("X-A\r\nB", b"c")], b"", 0, &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(SimpleResponse::write_at(200, &[("X A", b"c")], b"", 0, &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(SimpleResponse::write_at(200, &[("", b"c")], b"", 0, &mut buf),
+            Err(Error::Syntax));
+        assert!(SimpleResponse::write_at(200, &[("X-A", b"a\tb \xff")], b"", 0, &mut buf)
+            .is_ok());
+    }
+}
@@ -0,0 +1,332 @@
+//! Canonical request construction for verifying AWS Signature Version 4 style request
+//! signatures.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::Head;
+//! use uhttp_request::sigv4::canonical_request;
+//!
+//! let (head, _) = Head::new(
+//!     b"GET /a/./b%7e?z=1&a=%2f HTTP/1.1\r\nHost: example.com\r\nX-Amz-Date:  2017  01 \r\n\r\n"
+//! ).unwrap();
+//!
+//! let mut buf = [0; 256];
+//! let len = canonical_request(&head, &["x-amz-date", "host"], "UNSIGNED-PAYLOAD",
+//!     &mut buf).unwrap();
+//!
+//! assert_eq!(&buf[..len], &b"GET\n/a/b~\na=%2F&z=1\n\
+//!     host:example.com\nx-amz-date:2017 01\n\n\
+//!     host;x-amz-date\nUNSIGNED-PAYLOAD"[..]);
+//! ```
+
+use grammar::{is_ows, trim_ows};
+use percent::{self, decoded, EncodeSet};
+use writer::Cursor;
+use {Error, Head, Result};
+
+/// Maximum number of query parameters in a canonical request.
+pub const MAX_QUERY_PARAMS: usize = 64;
+
+/// Maximum number of signed header names in a canonical request.
+pub const MAX_SIGNED: usize = 32;
+
+/// Write the canonical request for the given request head into the given buffer.
+///
+/// The canonical request consists of the method, the URI-encoded path with dot-segments
+/// removed [RFC3986§5.2.4], the URI-encoded query parameters sorted by name and value,
+/// the lowercased signed header fields sorted by name with whitespace-normalized values,
+/// the signed header list, and the given hex-encoded payload hash, each separated by a
+/// newline. Existing percent-escapes in the target are decoded before re-encoding, so
+/// every escape in the output uses uppercase hex digits.
+///
+/// The `signed` names are matched case-insensitively and may be given in any order.
+/// More than `MAX_SIGNED` names or more than `MAX_QUERY_PARAMS` query parameters cause
+/// `Error::Capacity`.
+///
+/// On success, return the number of bytes written.
+pub fn canonical_request(head: &Head, signed: &[&str], payload_hash: &str,
+                         buf: &mut [u8])
+    -> Result<usize>
+{
+    let mut names = [""; MAX_SIGNED];
+    let signed = sorted(signed, &mut names)?;
+
+    let mut out = Cursor::new(buf);
+
+    let (path, query) = match head.line.target.find('?') {
+        Some(idx) => head.line.target.split_at(idx),
+        None => (head.line.target, "?"),
+    };
+
+    out.push(head.line.method.as_bytes())?;
+    out.push(b"\n")?;
+
+    write_path(path.as_bytes(), &mut out)?;
+    out.push(b"\n")?;
+
+    write_query(&query.as_bytes()[1..], &mut out)?;
+    out.push(b"\n")?;
+
+    for &name in signed {
+        write_lower(name, &mut out)?;
+        out.push(b":")?;
+
+        let vals = head.headers()
+            .filter_map(|h| h.ok())
+            .filter(|h| h.name.eq_ignore_ascii_case(name));
+
+        for (i, h) in vals.enumerate() {
+            if i > 0 {
+                out.push(b",")?;
+            }
+
+            write_collapsed(trim_ows(h.val), &mut out)?;
+        }
+
+        out.push(b"\n")?;
+    }
+
+    out.push(b"\n")?;
+
+    for (i, &name) in signed.iter().enumerate() {
+        if i > 0 {
+            out.push(b";")?;
+        }
+
+        write_lower(name, &mut out)?;
+    }
+
+    out.push(b"\n")?;
+    out.push(payload_hash.as_bytes())?;
+
+    Ok(out.pos())
+}
+
+/// Write the encoded form of the given path with dot-segments removed.
+fn write_path(path: &[u8], out: &mut Cursor) -> Result<()> {
+    let path = if path.first() == Some(&b'/') { &path[1..] } else { path };
+    let base = out.pos();
+
+    // Whether the output should end with a slash.
+    let mut trailing = false;
+
+    for seg in path.split(|&b| b == b'/') {
+        match seg {
+            b"." => trailing = true,
+            b".." => {
+                let end = out.written()[base..].iter().rposition(|&b| b == b'/')
+                    .unwrap_or(0);
+                out.truncate(base + end);
+                trailing = true;
+            },
+            _ => {
+                out.push(b"/")?;
+                write_encoded(seg, out)?;
+                trailing = false;
+            },
+        }
+    }
+
+    if trailing || out.pos() == base {
+        out.push(b"/")?;
+    }
+
+    Ok(())
+}
+
+/// Write the encoded query parameters of the given query string, sorted by encoded name
+/// then encoded value.
+///
+/// More than `MAX_QUERY_PARAMS` parameters cause `Error::Capacity`.
+fn write_query(query: &[u8], out: &mut Cursor) -> Result<()> {
+    let mut params = [(&b""[..], &b""[..]); MAX_QUERY_PARAMS];
+    let mut len = 0;
+
+    for p in query.split(|&b| b == b'&').filter(|p| !p.is_empty()) {
+        let param = match p.iter().position(|&b| b == b'=') {
+            Some(idx) => (&p[..idx], &p[idx + 1..]),
+            None => (p, &b""[..]),
+        };
+
+        *params.get_mut(len).ok_or(Error::Capacity)? = param;
+        len += 1;
+    }
+
+    let params = params.get_mut(..len).unwrap_or_default();
+
+    // Parameters that compare equal encode identically, so their order doesn't matter.
+    params.sort_unstable_by(|a, b| {
+        encoded(a.0).cmp(encoded(b.0)).then_with(|| encoded(a.1).cmp(encoded(b.1)))
+    });
+
+    for (i, &(name, val)) in params.iter().enumerate() {
+        if i > 0 {
+            out.push(b"&")?;
+        }
+
+        write_encoded(name, out)?;
+        out.push(b"=")?;
+        write_encoded(val, out)?;
+    }
+
+    Ok(())
+}
+
+/// Sort the given header names by lowercase form into the given buffer, removing
+/// duplicates, and return the sorted names.
+///
+/// More than `MAX_SIGNED` names cause `Error::Capacity`.
+fn sorted<'a, 'b>(names: &[&'a str], buf: &'b mut [&'a str; MAX_SIGNED])
+    -> Result<&'b [&'a str]>
+{
+    let buf = buf.get_mut(..names.len()).ok_or(Error::Capacity)?;
+    buf.copy_from_slice(names);
+    buf.sort_unstable_by(|a, b| lower(a).cmp(lower(b)));
+
+    let mut len = 0;
+
+    for i in 0..buf.len() {
+        if len == 0 || !buf[len - 1].eq_ignore_ascii_case(buf[i]) {
+            buf[len] = buf[i];
+            len += 1;
+        }
+    }
+
+    Ok(&buf[..len])
+}
+
+/// Create an iterator over the ASCII-lowercased bytes of the given string.
+fn lower(s: &str) -> impl Iterator<Item = u8> + '_ {
+    s.bytes().map(|b| b.to_ascii_lowercase())
+}
+
+/// Write the ASCII-lowercased form of the given string.
+fn write_lower(s: &str, out: &mut Cursor) -> Result<()> {
+    for b in lower(s) {
+        out.push(&[b])?;
+    }
+
+    Ok(())
+}
+
+/// Write the given bytes with every run of whitespace collapsed into a single space.
+fn write_collapsed(val: &[u8], out: &mut Cursor) -> Result<()> {
+    let mut space = false;
+
+    for &b in val {
+        if is_ows(b) {
+            space = true;
+            continue;
+        }
+
+        if space {
+            out.push(b" ")?;
+            space = false;
+        }
+
+        out.push(&[b])?;
+    }
+
+    Ok(())
+}
+
+/// Write the canonical encoded form of the given raw component.
+fn write_encoded(raw: &[u8], out: &mut Cursor) -> Result<()> {
+    for b in encoded(raw) {
+        out.push(&[b])?;
+    }
+
+    Ok(())
+}
+
+/// Create an iterator over the canonical encoded form of the given raw component.
+///
+/// Valid percent-escapes are decoded first, and then every byte outside the unreserved
+/// set [RFC3986§2.3] is percent-encoded with uppercase hex digits.
+fn encoded(raw: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    percent::encoded(decoded(raw), EncodeSet::Unreserved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+
+    fn path(p: &str) -> String {
+        let mut buf = [0; 64];
+        let mut out = Cursor::new(&mut buf);
+        out.push(b"GET\n").unwrap();
+        write_path(p.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out.written()[4..].to_vec()).unwrap()
+    }
+
+    fn query(q: &str) -> String {
+        let mut buf = [0; 64];
+        let mut out = Cursor::new(&mut buf);
+        write_query(q.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out.written().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(path(""), "/");
+        assert_eq!(path("/"), "/");
+        assert_eq!(path("/a/b"), "/a/b");
+        assert_eq!(path("/a/b/"), "/a/b/");
+        assert_eq!(path("/a/./b"), "/a/b");
+        assert_eq!(path("/a/b/.."), "/a/");
+        assert_eq!(path("/a/b/../c"), "/a/c");
+        assert_eq!(path("/../../a"), "/a");
+        assert_eq!(path("/a/."), "/a/");
+        assert_eq!(path("/a b/%7e%2F"), "/a%20b/~%2F");
+        assert_eq!(path("/%zz%4"), "/%25zz%254");
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(query(""), "");
+        assert_eq!(query("b=2&a=1"), "a=1&b=2");
+        assert_eq!(query("a=2&a=1&a=1"), "a=1&a=1&a=2");
+        assert_eq!(query("x&&y="), "x=&y=");
+        assert_eq!(query("k=a+b&k=%41"), "k=A&k=a%2Bb");
+        assert_eq!(query("%62=1&a=1"), "a=1&b=1");
+
+        let mut q = "a&".repeat(MAX_QUERY_PARAMS);
+        let mut buf = [0; 256];
+        assert!(write_query(q.as_bytes(), &mut Cursor::new(&mut buf)).is_ok());
+        q.push('b');
+        assert_eq!(write_query(q.as_bytes(), &mut Cursor::new(&mut buf)),
+            Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_sorted() {
+        let mut buf = [""; MAX_SIGNED];
+        assert_eq!(sorted(&["b", "a", "C", "a"], &mut buf), Ok(&["a", "b", "C"][..]));
+        assert_eq!(sorted(&["X", "x"], &mut buf).map(|s| s.len()), Ok(1));
+        assert_eq!(sorted(&[], &mut buf), Ok(&[][..]));
+        assert!(sorted(&["a"; MAX_SIGNED], &mut buf).is_ok());
+        assert_eq!(sorted(&["a"; MAX_SIGNED + 1], &mut buf), Err(Error::Capacity));
+    }
+
+    #[test]
+    fn test_canonical_request() {
+        let (head, _) = Head::new(
+            b"PUT /x?b=2&a HTTP/1.1\r\nhost: example.com\r\nX-A: 1\r\nx-a:  2 \r\n\r\n"
+        ).unwrap();
+
+        let mut buf = [0; 256];
+
+        let len = canonical_request(&head, &["X-A", "Host", "x-a"], "abc", &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], &b"PUT\n/x\na=&b=2\nhost:example.com\nx-a:1,2\n\n\
+                                   host;x-a\nabc"[..]);
+
+        let len = canonical_request(&head, &[], "", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"PUT\n/x\na=&b=2\n\n\n"[..]);
+
+        assert_eq!(canonical_request(&head, &["host"], "abc", &mut buf[..16]),
+            Err(Error::Capacity));
+    }
+}
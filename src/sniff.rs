@@ -0,0 +1,146 @@
+//! Classification of new connections by their first bytes, so a single listener can
+//! route each connection to the right protocol handler.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::sniff::{classify, Protocol};
+//!
+//! assert_eq!(classify(b"GET / HTTP/1.1\r\n"), Protocol::Http1Request);
+//! assert_eq!(classify(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n"), Protocol::Http2Preface);
+//! assert_eq!(classify(b"PRI * HT"), Protocol::NeedMoreData);
+//! assert_eq!(classify(b"\x16\x03\x01\x02\x00\x01"), Protocol::TlsClientHello);
+//! ```
+
+use grammar::is_tchar;
+
+/// HTTP/2 connection preface [RFC7540§3.5].
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Signature beginning a PROXY protocol version 2 header [PROXY§2.2].
+pub const PROXY_V2_SIGNATURE: &[u8] = b"\r\n\r\n\x00\r\nQUIT\n";
+
+/// Maximum method length considered when recognizing an HTTP/1.x request.
+const MAX_METHOD_LEN: usize = 32;
+
+/// Protocol detected at the start of a connection.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Protocol {
+    /// An HTTP/1.x Request-Line, possibly preceded by empty lines.
+    Http1Request,
+    /// The HTTP/2 connection preface.
+    Http2Preface,
+    /// A TLS handshake record containing a ClientHello [RFC8446§5.1].
+    TlsClientHello,
+    /// A PROXY protocol version 1 header [PROXY§2.1].
+    ProxyProtocolV1,
+    /// A PROXY protocol version 2 header [PROXY§2.2].
+    ProxyProtocolV2,
+    /// More bytes are needed to decide.
+    NeedMoreData,
+    /// None of the recognized protocols.
+    Unknown,
+}
+
+/// Classify a connection from the bytes received on it so far.
+///
+/// Only as many bytes as needed to distinguish the protocols are examined, which is at
+/// most the length of the HTTP/2 preface or a short method, so the result is only a
+/// routing decision and the chosen handler must still fully validate the stream.
+pub fn classify(buf: &[u8]) -> Protocol {
+    if buf.is_empty() {
+        return Protocol::NeedMoreData;
+    }
+
+    let sigs = [
+        (HTTP2_PREFACE, Protocol::Http2Preface),
+        (PROXY_V2_SIGNATURE, Protocol::ProxyProtocolV2),
+        (&b"PROXY "[..], Protocol::ProxyProtocolV1),
+    ];
+
+    for &(sig, proto) in &sigs {
+        if buf.starts_with(sig) {
+            return proto;
+        }
+
+        if sig.starts_with(buf) {
+            return Protocol::NeedMoreData;
+        }
+    }
+
+    if buf[0] == 0x16 {
+        // ContentType handshake, ProtocolVersion 3.x, 2-byte length, HandshakeType
+        // client_hello.
+        return match buf.get(..6) {
+            Some(rec) if rec[1] == 3 && rec[2] <= 4 && rec[5] == 1 =>
+                Protocol::TlsClientHello,
+            Some(_) => Protocol::Unknown,
+            None if buf.get(1).is_none_or(|&b| b == 3) => Protocol::NeedMoreData,
+            None => Protocol::Unknown,
+        };
+    }
+
+    classify_http1(buf)
+}
+
+/// Classify the given bytes as the start of an HTTP/1.x request or not.
+fn classify_http1(mut buf: &[u8]) -> Protocol {
+    // Skip leading empty lines [RFC7230§3.5].
+    while buf.starts_with(b"\r\n") {
+        buf = &buf[2..];
+    }
+
+    if buf.is_empty() || buf == b"\r" {
+        return Protocol::NeedMoreData;
+    }
+
+    let len = buf.iter().take(MAX_METHOD_LEN + 1).take_while(|&&b| is_tchar(b)).count();
+
+    match buf.get(len) {
+        Some(&b' ') if len > 0 => Protocol::Http1Request,
+        None if len <= MAX_METHOD_LEN => Protocol::NeedMoreData,
+        _ => Protocol::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        use self::Protocol::*;
+
+        assert_eq!(classify(b""), NeedMoreData);
+
+        assert_eq!(classify(b"GET / HTTP/1.1\r\n"), Http1Request);
+        assert_eq!(classify(b"\r\n\r\nPOST "), Http1Request);
+        assert_eq!(classify(b"PRI / HTTP/1.1\r\n"), Http1Request);
+        assert_eq!(classify(b"PROXYX / HTTP/1.1\r\n"), Http1Request);
+        assert_eq!(classify(b"P"), NeedMoreData);
+        assert_eq!(classify(b"GE"), NeedMoreData);
+        assert_eq!(classify(b"\r\n\r"), NeedMoreData);
+        assert_eq!(classify(&[b'A'; 32]), NeedMoreData);
+        assert_eq!(classify(&[b'A'; 33]), Unknown);
+        assert_eq!(classify(b" GET /"), Unknown);
+        assert_eq!(classify(b"GET\t/"), Unknown);
+
+        assert_eq!(classify(HTTP2_PREFACE), Http2Preface);
+        assert_eq!(classify(&HTTP2_PREFACE[..23]), NeedMoreData);
+        // A corrupted preface still has the shape of a Request-Line.
+        assert_eq!(classify(b"PRI * HTTP/2.0\r\n\r\nXX"), Http1Request);
+
+        assert_eq!(classify(b"PROXY TCP4 1.2.3.4 5.6.7.8 1 2\r\n"), ProxyProtocolV1);
+        assert_eq!(classify(b"PROX"), NeedMoreData);
+        assert_eq!(classify(b"\r\n\r\n\x00\r\nQUIT\n\x21\x11"), ProxyProtocolV2);
+        assert_eq!(classify(b"\r\n\r\n\x00"), NeedMoreData);
+        assert_eq!(classify(b"\r\n\r\n"), NeedMoreData);
+
+        assert_eq!(classify(b"\x16\x03\x03\x00\x10\x01"), TlsClientHello);
+        assert_eq!(classify(b"\x16\x03\x01"), NeedMoreData);
+        assert_eq!(classify(b"\x16"), NeedMoreData);
+        assert_eq!(classify(b"\x16\x02"), Unknown);
+        assert_eq!(classify(b"\x16\x03\x03\x00\x10\x02"), Unknown);
+        assert_eq!(classify(b"\x00\x01"), Unknown);
+    }
+}
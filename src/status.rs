@@ -0,0 +1,136 @@
+//! Parsing of the Status-Line that begins an HTTP response.
+
+use grammar::{is_obs_text, is_ows, is_vchar};
+use response::ResponseConfig;
+use {next_line, Error, Result};
+
+/// A "Status-Line" [RFC7230§3.1.2] that begins an HTTP response.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct StatusLine<'a> {
+    /// HTTP protocol version of response.
+    ///
+    /// This is guaranteed to be free of spaces but is not guaranteed to be otherwise
+    /// syntactically correct.
+    pub version: &'a str,
+
+    /// 3-digit status code.
+    pub code: u16,
+
+    /// Raw reason phrase, which may be empty and isn't guaranteed to be valid UTF-8.
+    pub reason: &'a [u8],
+}
+
+impl<'a> StatusLine<'a> {
+    /// Try to parse the given bytes into `StatusLine` components.
+    ///
+    /// On success, return `Ok((sl, rest))`, where `sl` is the `StatusLine` and `rest` is
+    /// a slice that begins directly after the Status-Line terminating CRLF, which can be
+    /// passed to `Headers::new`. This is as lenient as `ResponseConfig::lenient`, so the
+    /// space following the status code may be omitted when the reason phrase is empty,
+    /// as some servers do.
+    pub fn new(buf: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        StatusLine::with_config(buf, ResponseConfig::lenient())
+    }
+
+    /// Try to parse the given bytes into `StatusLine` components, rejecting the
+    /// deviations that the given configuration doesn't accept.
+    ///
+    /// An empty reason phrase and trailing whitespace, which is kept as part of the
+    /// reason phrase, are always accepted.
+    pub fn with_config(buf: &'a [u8], config: ResponseConfig) -> Result<(Self, &'a [u8])> {
+        let (line, rest) = next_line(buf)?;
+
+        let sp = line.iter().position(|&b| b == b' ').ok_or(Error::Syntax)?;
+        let version = std::str::from_utf8(&line[..sp]).map_err(|_| Error::Syntax)?;
+
+        if version.is_empty() {
+            return Err(Error::Syntax);
+        }
+
+        let after = &line[sp + 1..];
+
+        let code = match after.get(..3) {
+            Some(d) if d.iter().all(u8::is_ascii_digit) => d.iter()
+                .fold(0, |n, &b| n * 10 + (b - b'0') as u16),
+            _ => return Err(Error::Syntax),
+        };
+
+        let reason = match after.get(3) {
+            None if config.missing_space => &after[3..],
+            Some(&b' ') => &after[4..],
+            _ => return Err(Error::Syntax),
+        };
+
+        let valid = |b: u8| is_vchar(b) || is_obs_text(b) || is_ows(b);
+
+        if !config.invalid_reason && !reason.iter().all(|&b| valid(b)) {
+            return Err(Error::Syntax);
+        }
+
+        Ok((StatusLine { version, code, reason }, rest))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Headers;
+
+    #[test]
+    fn test_status_line() {
+        let (sl, rest) = StatusLine::new(b"HTTP/1.1 404 Not Found\r\nA: b\r\n\r\n").unwrap();
+        assert_eq!(sl, StatusLine { version: "HTTP/1.1", code: 404, reason: b"Not Found" });
+
+        let mut h = Headers::new(rest);
+        assert_eq!(h.next().unwrap().unwrap().name, "A");
+        assert!(h.next().is_none());
+
+        let (sl, rest) = StatusLine::new(b"HTTP/1.0 200 \r\n").unwrap();
+        assert_eq!(sl.code, 200);
+        assert_eq!(sl.reason, b"");
+        assert_eq!(rest, b"");
+
+        let (sl, _) = StatusLine::new(b"HTTP/1.1 204\r\n").unwrap();
+        assert_eq!(sl.reason, b"");
+
+        let (sl, _) = StatusLine::new(b"HTTP/1.1 500 a  b\xff\r\n").unwrap();
+        assert_eq!(sl.reason, b"a  b\xff");
+
+        assert_eq!(StatusLine::new(b"HTTP/1.1 200 OK"), Err(Error::Partial));
+        assert_eq!(StatusLine::new(b"HTTP/1.1 200 OK\r"), Err(Error::Partial));
+        assert_eq!(StatusLine::new(b"HTTP/1.1 20 OK\r\n"), Err(Error::Syntax));
+        assert_eq!(StatusLine::new(b"HTTP/1.1 2000 OK\r\n"), Err(Error::Syntax));
+        assert_eq!(StatusLine::new(b"HTTP/1.1 abc\r\n"), Err(Error::Syntax));
+        assert_eq!(StatusLine::new(b"HTTP/1.1\r\n"), Err(Error::Syntax));
+        assert_eq!(StatusLine::new(b" 200 OK\r\n"), Err(Error::Syntax));
+        assert_eq!(StatusLine::new(b"\r\nHTTP/1.1 200 OK\r\n"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_with_config() {
+        let strict = ResponseConfig::strict();
+
+        for &config in &[strict, ResponseConfig::lenient()] {
+            let (sl, _) = StatusLine::with_config(b"HTTP/1.1 200  \r\n", config).unwrap();
+            assert_eq!(sl.reason, b" ");
+
+            let (sl, _) = StatusLine::with_config(b"HTTP/1.1 200 OK \t\r\n", config).unwrap();
+            assert_eq!(sl.reason, b"OK \t");
+
+            let (sl, _) = StatusLine::with_config(b"HTTP/1.1 200 \r\n", config).unwrap();
+            assert_eq!(sl.reason, b"");
+        }
+
+        let buf = b"HTTP/1.1 200\r\n";
+        assert_eq!(StatusLine::with_config(buf, strict), Err(Error::Syntax));
+        let config = ResponseConfig { missing_space: true, ..strict };
+        let (sl, rest) = StatusLine::with_config(buf, config).unwrap();
+        assert_eq!(sl, StatusLine { version: "HTTP/1.1", code: 200, reason: b"" });
+        assert_eq!(rest, b"");
+
+        let buf = b"HTTP/1.1 200 \x7f\r\n";
+        assert_eq!(StatusLine::with_config(buf, strict), Err(Error::Syntax));
+        let config = ResponseConfig { invalid_reason: true, ..strict };
+        assert_eq!(StatusLine::with_config(buf, config).unwrap().0.reason, b"\x7f");
+    }
+}
@@ -0,0 +1,137 @@
+//! Owned storage for parsed request heads, requiring the `alloc` feature.
+
+use writer::Span;
+use {Head, HeadStats, Header, RequestLine};
+
+/// Owned copy of a parsed `Head` that no longer borrows from the connection buffer.
+///
+/// Every component of the head is copied into a single contiguous allocation, with the
+/// spans of the header fields kept in a second one, so a request can be handed off to
+/// another thread while the original buffer is reused, without an allocation per
+/// header. All returned references borrow from the store and stay valid as long as it
+/// does.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct HeadStore {
+    /// Concatenated Request-Line components, header names, and header values.
+    buf: Box<[u8]>,
+    /// Spans of the method, target, and version within `buf`.
+    line: [Span; 3],
+    /// Spans of each header name and value within `buf`.
+    fields: Box<[(Span, Span)]>,
+    /// Size accounting of the original head.
+    stats: HeadStats,
+}
+
+impl HeadStore {
+    /// Copy the components of the given head into a new `HeadStore`.
+    ///
+    /// Only the fields yielded by the head's `headers` iterator are kept, so any fields
+    /// dropped or renamed by the parser configuration are stored that way.
+    pub fn new(head: &Head) -> Self {
+        let fields = || head.headers().filter_map(|h| h.ok());
+
+        let len = head.line.method.len() + head.line.target.len() + head.line.version.len()
+            + fields().map(|h| h.name.len() + h.val.len()).sum::<usize>();
+
+        let mut buf = Vec::with_capacity(len);
+
+        let mut push = |bytes: &[u8]| {
+            let start = buf.len();
+            buf.extend_from_slice(bytes);
+            Span { start, end: buf.len() }
+        };
+
+        let line = [
+            push(head.line.method.as_bytes()),
+            push(head.line.target.as_bytes()),
+            push(head.line.version.as_bytes()),
+        ];
+
+        let fields = fields()
+            .map(|h| (push(h.name.as_bytes()), push(h.val)))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        HeadStore {
+            buf: buf.into_boxed_slice(),
+            line,
+            fields,
+            stats: head.stats,
+        }
+    }
+
+    /// Retrieve the stored Request-Line.
+    pub fn line(&self) -> RequestLine<'_> {
+        RequestLine {
+            method: self.text(self.line[0]),
+            target: self.text(self.line[1]),
+            version: self.text(self.line[2]),
+        }
+    }
+
+    /// Create an iterator over the stored header fields, in their original order.
+    pub fn headers(&self) -> impl Iterator<Item = Header<'_>> + '_ {
+        self.fields.iter().map(move |&(name, val)| Header {
+            name: self.text(name),
+            val: &self.buf[val.start..val.end],
+        })
+    }
+
+    /// Retrieve the raw value of the first stored header with the given name, compared
+    /// case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers().find(|h| h.name.eq_ignore_ascii_case(name)).map(|h| h.val)
+    }
+
+    /// Retrieve the size accounting of the original head.
+    pub fn stats(&self) -> HeadStats { self.stats }
+
+    /// Retrieve the string at the given span.
+    fn text(&self, span: Span) -> &str {
+        // Span was copied from a string, so this can't fail.
+        std::str::from_utf8(&self.buf[span.start..span.end]).unwrap_or("")
+    }
+}
+
+impl<'a> From<Head<'a>> for HeadStore {
+    fn from(head: Head<'a>) -> Self { HeadStore::new(&head) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_head_store() {
+        let mut buf = b"POST /x HTTP/1.1\r\nHost: example.com\r\nA:  b \r\n\r\nbody".to_vec();
+
+        let store = {
+            let (head, _) = Head::new(&buf).unwrap();
+            HeadStore::new(&head)
+        };
+
+        buf.clear();
+
+        assert_eq!(store.line(), RequestLine {
+            method: "POST",
+            target: "/x",
+            version: "HTTP/1.1",
+        });
+
+        let h = store.headers().collect::<Vec<_>>();
+        assert_eq!(h, vec![
+            Header { name: "Host", val: b" example.com" },
+            Header { name: "A", val: b"  b " },
+        ]);
+
+        assert_eq!(store.header("host"), Some(&b" example.com"[..]));
+        assert_eq!(store.header("B"), None);
+        assert_eq!(store.stats().headers, 2);
+        assert_eq!(store.buf.len(), 35);
+
+        let (head, _) = Head::new(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let store = HeadStore::from(head);
+        assert_eq!(store.line().target, "/");
+        assert_eq!(store.headers().count(), 0);
+    }
+}
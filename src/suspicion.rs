@@ -0,0 +1,198 @@
+//! Heuristic flagging of request heads matching common attack patterns.
+//!
+//! These checks are cheap signals for coarse allow/deny decisions, not a substitute for
+//! validating input where it's used.
+
+use percent::{decoded, hex};
+use {name_hash64, Head};
+
+/// Number of times a header name may repeat before it's considered suspicious.
+pub const MAX_REPEAT: usize = 16;
+
+/// Number of header fields that can be checked for repetition.
+pub const MAX_COUNTED: usize = 256;
+
+/// Suspicious patterns found in a request head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Suspicion {
+    /// The decoded target contains an overlong UTF-8 encoding, such as `%C0%AF` for `/`,
+    /// which is commonly used to sneak characters past filters.
+    pub overlong_utf8: bool,
+
+    /// The decoded target contains a NUL byte, such as from `%00`, which can truncate
+    /// paths in C-based backends.
+    pub nul: bool,
+
+    /// The target contains an escaped percent sign followed by hex digits, such as
+    /// `%252e`, which decodes into another escape.
+    pub double_encoding: bool,
+
+    /// The target only contains a `..` traversal after escapes are decoded, such as
+    /// `..%2f` or `%2e%2e/`.
+    pub encoded_traversal: bool,
+
+    /// Some header name appears more than `MAX_REPEAT` times, or the head has more than
+    /// `MAX_COUNTED` fields, which is unusual enough in itself to flag.
+    pub header_repetition: bool,
+}
+
+impl Suspicion {
+    /// Analyze the given request head for suspicious patterns.
+    pub fn new(head: &Head) -> Self {
+        let target = head.line.target.as_bytes();
+
+        Suspicion {
+            overlong_utf8: has_overlong(decoded(target)),
+            nul: decoded(target).any(|b| b == 0),
+            double_encoding: target.windows(5).any(|w| {
+                w[..3].eq_ignore_ascii_case(b"%25") && hex(w[3]).is_some() &&
+                    hex(w[4]).is_some()
+            }),
+            encoded_traversal: !has_traversal(target.iter().cloned()) &&
+                has_traversal(decoded(target)),
+            header_repetition: has_repetition(head),
+        }
+    }
+
+    /// Compute a rough score from 0 to 100 of how likely the request is malicious.
+    pub fn score(&self) -> u32 {
+        [
+            (self.overlong_utf8, 40),
+            (self.nul, 30),
+            (self.double_encoding, 20),
+            (self.encoded_traversal, 40),
+            (self.header_repetition, 10),
+        ].iter().filter(|f| f.0).map(|f| f.1).sum::<u32>().min(100)
+    }
+
+    /// Check if no suspicious patterns were found.
+    pub fn is_clean(&self) -> bool { *self == Suspicion::default() }
+}
+
+/// Check if some header name in the given head repeats more than `MAX_REPEAT` times.
+///
+/// Names are counted in a single pass by sorting their case-insensitive hashes, so a
+/// head with more fields than fit in the buffer counts as repetitive.
+fn has_repetition(head: &Head) -> bool {
+    let mut hashes = [0; MAX_COUNTED];
+    let mut len = 0;
+
+    for h in head.headers().filter_map(|h| h.ok()) {
+        match hashes.get_mut(len) {
+            Some(slot) => *slot = name_hash64(h.name),
+            None => return true,
+        }
+
+        len += 1;
+    }
+
+    let hashes = hashes.get_mut(..len).unwrap_or_default();
+    hashes.sort_unstable();
+
+    hashes.chunk_by(|a, b| a == b).any(|run| run.len() > MAX_REPEAT)
+}
+
+/// Check if the given bytes contain an overlong UTF-8 sequence [RFC3629§3].
+fn has_overlong<I: Iterator<Item = u8>>(mut bytes: I) -> bool {
+    while let Some(b) = bytes.next() {
+        let overlong = match b {
+            0xC0 | 0xC1 => true,
+            0xE0 => bytes.next().is_some_and(|c| (0x80..0xA0).contains(&c)),
+            0xF0 => bytes.next().is_some_and(|c| (0x80..0x90).contains(&c)),
+            _ => false,
+        };
+
+        if overlong {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check if the given bytes contain a `..` path segment delimited by `/` or `\`.
+fn has_traversal<I: Iterator<Item = u8>>(bytes: I) -> bool {
+    // Number of dots since the last delimiter, or `None` if other bytes were seen.
+    let mut dots = Some(0);
+
+    for b in bytes {
+        dots = match (b, dots) {
+            (b'/', Some(2)) | (b'\\', Some(2)) => return true,
+            (b'/', _) | (b'\\', _) => Some(0),
+            (b'.', Some(n)) => Some(n + 1),
+            _ => None,
+        };
+    }
+
+    dots == Some(2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn analyze(target: &str) -> Suspicion {
+        let req = format!("GET {} HTTP/1.1\r\n\r\n", target);
+        Suspicion::new(&Head::new(req.as_bytes()).unwrap().0)
+    }
+
+    #[test]
+    fn test_suspicion() {
+        assert!(analyze("/a/b.html?x=%20").is_clean());
+        assert!(analyze("/a/../b").is_clean());
+        assert!(analyze("/caf%C3%A9").is_clean());
+
+        let s = analyze("/%C0%AFetc");
+        assert_eq!(s, Suspicion { overlong_utf8: true, ..Suspicion::default() });
+        assert_eq!(s.score(), 40);
+
+        assert!(analyze("/%e0%80%af").overlong_utf8);
+        assert!(analyze("/%f0%80%80%af").overlong_utf8);
+        assert!(!analyze("/%e0%a0%80").overlong_utf8);
+
+        assert!(analyze("/a.php%00.png").nul);
+        assert!(analyze("/%252e%252e/").double_encoding);
+        assert!(!analyze("/100%25").double_encoding);
+        assert!(analyze("/a/..%2fetc").encoded_traversal);
+        assert!(analyze("/a/%2e%2e/etc").encoded_traversal);
+        assert!(analyze("/a/..%5Cetc").encoded_traversal);
+        assert!(analyze("/a/%2E%2E").encoded_traversal);
+        assert!(!analyze("/a/..%2fb/../c").encoded_traversal);
+        assert!(!analyze("/a/...%2f").encoded_traversal);
+
+        let s = analyze("/..%2f%00%252e%C0%80");
+        assert_eq!(s.score(), 100);
+        assert!(!s.header_repetition);
+    }
+
+    #[test]
+    fn test_header_repetition() {
+        let mut req = b"GET / HTTP/1.1\r\n".to_vec();
+
+        for _ in 0..MAX_REPEAT {
+            req.extend_from_slice(b"X-A: 1\r\n");
+        }
+
+        let head = |req: &[u8]| {
+            let mut req = req.to_vec();
+            req.extend_from_slice(b"\r\n");
+            Suspicion::new(&Head::new(&req).unwrap().0)
+        };
+
+        assert!(head(&req).is_clean());
+
+        req.extend_from_slice(b"x-a: 1\r\n");
+        assert_eq!(head(&req), Suspicion { header_repetition: true, ..Suspicion::default() });
+
+        let mut req = b"GET / HTTP/1.1\r\n".to_vec();
+
+        for i in 0..MAX_COUNTED {
+            req.extend_from_slice(format!("X-{}: 1\r\n", i % (MAX_COUNTED / 2)).as_bytes());
+        }
+
+        assert!(head(&req).is_clean());
+
+        req.extend_from_slice(b"X-Extra: 1\r\n");
+        assert!(head(&req).header_repetition);
+    }
+}
@@ -0,0 +1,223 @@
+//! Composable transforms of a request head, for building forward and reverse proxies.
+//!
+//! Each pass appends edits over the original head to a shared list, and the combined
+//! list is applied by `writer::write_edited` in a single pass over the head, so no
+//! intermediate copy of the head is ever made.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::transform::{write_transformed, AddVia, ForwardedFor, RewriteHost,
+//!                                StripHopByHop, Transform};
+//! use uhttp_request::writer::Edit;
+//!
+//! let head = b"GET / HTTP/1.1\r\nHost: a.com\r\nConnection: close\r\n\r\n";
+//!
+//! let passes: [&dyn Transform; 4] = [
+//!     &StripHopByHop,
+//!     &RewriteHost("backend"),
+//!     &AddVia(b"1.1 proxy"),
+//!     &ForwardedFor(b"192.0.2.1"),
+//! ];
+//!
+//! let mut edits = [Edit::Target(""); 16];
+//! let mut buf = [0; 128];
+//! let len = write_transformed(head, &passes, &mut edits, &mut buf).unwrap();
+//!
+//! assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nHost: backend\r\nVia: 1.1 proxy\r\n\
+//!     X-Forwarded-For: 192.0.2.1\r\n\r\n"[..]);
+//! ```
+
+use host::parse_host;
+use proxy::strip_hop_by_hop;
+use writer::{is_field_value, write_edited, Edit, Spans};
+use {Error, Result};
+
+/// List of edits stored in a caller-provided buffer.
+pub struct Edits<'a, 'b> {
+    buf: &'b mut [Edit<'a>],
+    len: usize,
+}
+
+impl<'a, 'b> Edits<'a, 'b> {
+    /// Create a new, empty `Edits` list over the given buffer, whose length bounds the
+    /// number of edits and whose initial contents are ignored.
+    pub fn new(buf: &'b mut [Edit<'a>]) -> Self {
+        Edits { buf, len: 0 }
+    }
+
+    /// Try to append the given edit, returning `Error::Capacity` if the buffer is full.
+    pub fn push(&mut self, edit: Edit<'a>) -> Result<()> {
+        let slot = self.buf.get_mut(self.len).ok_or(Error::Capacity)?;
+        *slot = edit;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Retrieve the edits appended so far.
+    pub fn as_slice(&self) -> &[Edit<'a>] { &self.buf[..self.len] }
+}
+
+/// A single pass over a request head.
+pub trait Transform {
+    /// Try to append the edits made by this pass over the given head to the given list.
+    fn apply<'a, 'b>(&'a self, head: &'a [u8], edits: &mut Edits<'a, 'b>) -> Result<()>;
+}
+
+/// Pass that removes every hop-by-hop header field, as with `proxy::strip_hop_by_hop`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct StripHopByHop;
+
+impl Transform for StripHopByHop {
+    fn apply<'a, 'b>(&'a self, head: &'a [u8], edits: &mut Edits<'a, 'b>) -> Result<()> {
+        for edit in strip_hop_by_hop(head)? {
+            edits.push(edit)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Pass that adds a Via header field with the given value, such as `1.1 proxy`
+/// [RFC7230§5.7.1].
+///
+/// The field is written as a separate line after any existing Via fields, which is
+/// equivalent to appending to their list. A value containing control characters other
+/// than tab causes `Error::Syntax`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct AddVia<'v>(pub &'v [u8]);
+
+impl<'v> Transform for AddVia<'v> {
+    fn apply<'a, 'b>(&'a self, _: &'a [u8], edits: &mut Edits<'a, 'b>) -> Result<()> {
+        if !is_field_value(self.0) {
+            return Err(Error::Syntax);
+        }
+
+        edits.push(Edit::Insert("Via", self.0))
+    }
+}
+
+/// Pass that replaces every Host header field with one of the given value.
+///
+/// The value must be a valid `host[:port]` authority, as checked by `host::parse_host`,
+/// or `Error::Syntax` is returned.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct RewriteHost<'v>(pub &'v str);
+
+impl<'v> Transform for RewriteHost<'v> {
+    fn apply<'a, 'b>(&'a self, head: &'a [u8], edits: &mut Edits<'a, 'b>) -> Result<()> {
+        parse_host(self.0.as_bytes())?;
+
+        for next in Spans::new(head)? {
+            let (h, span) = next?;
+
+            if h.name.eq_ignore_ascii_case("Host") {
+                edits.push(Edit::Drop(span))?;
+            }
+        }
+
+        edits.push(Edit::Insert("Host", self.0.as_bytes()))
+    }
+}
+
+/// Pass that appends the given client address to the X-Forwarded-For list.
+///
+/// The address is written as a separate line after any existing X-Forwarded-For
+/// fields, which is equivalent to appending to their list [RFC7230§3.2.2]. An address
+/// containing control characters other than tab causes `Error::Syntax`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ForwardedFor<'v>(pub &'v [u8]);
+
+impl<'v> Transform for ForwardedFor<'v> {
+    fn apply<'a, 'b>(&'a self, _: &'a [u8], edits: &mut Edits<'a, 'b>) -> Result<()> {
+        if !is_field_value(self.0) {
+            return Err(Error::Syntax);
+        }
+
+        edits.push(Edit::Insert("X-Forwarded-For", self.0))
+    }
+}
+
+/// Write the given request head into the given buffer with the given passes applied in
+/// order, using the given buffer to hold the combined edits.
+///
+/// On success, return the number of bytes written.
+pub fn write_transformed<'a>(head: &'a [u8], passes: &[&'a dyn Transform],
+                             edits: &mut [Edit<'a>], buf: &mut [u8])
+    -> Result<usize>
+{
+    let mut edits = Edits::new(edits);
+
+    for pass in passes {
+        pass.apply(head, &mut edits)?;
+    }
+
+    write_edited(head, edits.as_slice(), buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_edits() {
+        let mut buf = [Edit::Target(""); 2];
+        let mut e = Edits::new(&mut buf);
+        assert_eq!(e.as_slice(), &[]);
+        assert_eq!(e.push(Edit::Target("/a")), Ok(()));
+        assert_eq!(e.push(Edit::Insert("A", b"b")), Ok(()));
+        assert_eq!(e.push(Edit::Target("/b")), Err(Error::Capacity));
+        assert_eq!(e.as_slice(), &[Edit::Target("/a"), Edit::Insert("A", b"b")]);
+    }
+
+    #[test]
+    fn test_write_transformed() {
+        let head = b"GET / HTTP/1.1\r\nhost: a\r\nX-Forwarded-For: 1.1.1.1\r\n\
+                     Via: 1.0 x\r\nKeep-Alive: 1\r\nHost: b\r\n\r\nbody";
+        let mut edits = [Edit::Target(""); 8];
+        let mut buf = [0; 256];
+
+        let len = write_transformed(head, &[
+            &RewriteHost("c"),
+            &ForwardedFor(b"2.2.2.2"),
+            &StripHopByHop,
+            &AddVia(b"1.1 p"),
+        ], &mut edits, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\nX-Forwarded-For: 1.1.1.1\r\n\
+            Via: 1.0 x\r\nHost: c\r\nX-Forwarded-For: 2.2.2.2\r\nVia: 1.1 p\r\n\r\n"[..]);
+
+        let len = write_transformed(head, &[], &mut edits, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &head[..head.len() - 4]);
+
+        assert_eq!(write_transformed(head, &[&RewriteHost("c")], &mut edits[..2], &mut buf),
+            Err(Error::Capacity));
+        assert_eq!(write_transformed(b"GET / HTTP/1.1\r\nA\r\n\r\n", &[&StripHopByHop],
+            &mut edits, &mut buf), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_injection() {
+        let head = b"GET / HTTP/1.1\r\nHost: a\r\n\r\n";
+        let mut buf = [Edit::Target(""); 4];
+        let apply = |pass: &dyn Transform| pass.apply(head, &mut Edits::new(&mut [
+            Edit::Target(""); 4
+        ]));
+
+        assert_eq!(apply(&RewriteHost("b\r\nX-Injected: 1")), Err(Error::Syntax));
+        assert_eq!(apply(&RewriteHost("a@evil.com")), Err(Error::Syntax));
+        assert_eq!(apply(&RewriteHost("")), Err(Error::Syntax));
+        assert_eq!(apply(&ForwardedFor(b"1.2.3.4\r\nEvil: 1")), Err(Error::Syntax));
+        assert_eq!(apply(&ForwardedFor(b"1.2.3.4\n")), Err(Error::Syntax));
+        assert_eq!(apply(&AddVia(b"1.1 p\r\nEvil: 1")), Err(Error::Syntax));
+        assert_eq!(apply(&AddVia(b"1.1 p\0")), Err(Error::Syntax));
+
+        assert_eq!(apply(&RewriteHost("[::1]:8080")), Ok(()));
+        assert_eq!(apply(&ForwardedFor(b"1.2.3.4, [::1]")), Ok(()));
+        assert_eq!(apply(&AddVia(b"1.1 p (comment)")), Ok(()));
+
+        let mut e = Edits::new(&mut buf);
+        assert_eq!(RewriteHost("b\r\nX: y").apply(head, &mut e), Err(Error::Syntax));
+        assert_eq!(e.as_slice(), &[]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Recognition of protocol upgrades [RFC7230§6.7], including the HTTP/1.1 form of
+//! extended CONNECT used by MASQUE tunnels such as CONNECT-UDP [RFC9298§3.2].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::Head;
+//! use uhttp_request::upgrade::{masque_udp_target, requested_protocol};
+//!
+//! let (head, _) = Head::new(b"GET /.well-known/masque/udp/192.0.2.6/443/ HTTP/1.1\r\n\
+//!                             Host: proxy\r\nConnection: Upgrade\r\n\
+//!                             Upgrade: connect-udp\r\nCapsule-Protocol: ?1\r\n\r\n")
+//!     .unwrap();
+//!
+//! assert_eq!(requested_protocol(&head), Ok(Some("connect-udp")));
+//! assert_eq!(masque_udp_target(head.line.target), Some(("192.0.2.6", 443)));
+//! ```
+
+use grammar::is_token;
+use list::{self, ListValues};
+use proxy;
+use {Error, Head, Result};
+
+/// Create an iterator over the protocols, such as `websocket` or `HTTP/2.0`, in the given
+/// Upgrade header value, in the client's order of preference.
+///
+/// Empty list elements are skipped, and each protocol must be a token optionally
+/// followed by `/` and a version token.
+pub fn protocols<'a>(val: &'a [u8]) -> Protocols<'a> {
+    Protocols(list::values(val))
+}
+
+/// Iterator over the protocols in an Upgrade header value.
+#[derive(Clone, Debug)]
+pub struct Protocols<'a>(ListValues<'a>);
+
+impl<'a> Iterator for Protocols<'a> {
+    type Item = Result<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(|p| if p.splitn(2, |&b| b == b'/').all(is_token) {
+            // Protocol is ASCII, so this can't fail.
+            std::str::from_utf8(p).map_err(|_| Error::Syntax)
+        } else {
+            Err(Error::Syntax)
+        }))
+    }
+}
+
+/// Retrieve the most preferred protocol the given request asks to upgrade to, if any.
+///
+/// An upgrade is only recognized when the Connection header lists the `upgrade` option,
+/// as required for the Upgrade header to take effect [RFC7230§6.7]. The protocol is
+/// returned as sent, so it should be compared case-insensitively.
+pub fn requested_protocol<'a>(head: &Head<'a>) -> Result<Option<&'a str>> {
+    let upgrade = proxy::connection_options(head.headers())
+        .any(|o| o.is_ok_and(|o| o.eq_ignore_ascii_case(b"upgrade")));
+
+    if !upgrade {
+        return Ok(None);
+    }
+
+    let val = match head.headers().filter_map(|h| h.ok())
+        .find(|h| h.name.eq_ignore_ascii_case("Upgrade"))
+    {
+        Some(h) => h.val,
+        None => return Ok(None),
+    };
+
+    protocols(val).next().map_or(Ok(None), |p| p.map(Some))
+}
+
+/// Extract the target host and port from the given request target if it follows the
+/// default CONNECT-UDP URI template, `/.well-known/masque/udp/{host}/{port}/`
+/// [RFC9298§3].
+///
+/// IPv6 hosts are given in their percent-encoded form, such as `2001%3Adb8%3A%3A42`.
+pub fn masque_udp_target(target: &str) -> Option<(&str, u16)> {
+    let rest = target.strip_prefix("/.well-known/masque/udp/")?;
+    let rest = rest.strip_suffix('/')?;
+
+    let (host, port) = rest.split_at(rest.find('/')?);
+    let port = &port[1..];
+
+    if host.is_empty() || port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((host, port.parse().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_protocols() {
+        let mut p = protocols(b"HTTP/2.0, , connect-udp,websocket/13");
+        assert_eq!(p.next(), Some(Ok("HTTP/2.0")));
+        assert_eq!(p.next(), Some(Ok("connect-udp")));
+        assert_eq!(p.next(), Some(Ok("websocket/13")));
+        assert_eq!(p.next(), None);
+
+        let mut p = protocols(b"\"a, b\", c");
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), Some(Ok("c")));
+        assert_eq!(p.next(), None);
+
+        let mut p = protocols(b"a/, /1, a b, a/b/c");
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), Some(Err(Error::Syntax)));
+        assert_eq!(p.next(), None);
+    }
+
+    #[test]
+    fn test_requested_protocol() {
+        fn req(buf: &[u8]) -> Result<Option<&str>> {
+            requested_protocol(&Head::new(buf).unwrap().0)
+        }
+
+        assert_eq!(req(b"GET / HTTP/1.1\r\nConnection: keep-alive, Upgrade\r\n\
+                         Upgrade: connect-ip, connect-udp\r\n\r\n"),
+            Ok(Some("connect-ip")));
+        assert_eq!(req(b"GET / HTTP/1.1\r\nUpgrade: websocket\r\n\r\n"), Ok(None));
+        assert_eq!(req(b"GET / HTTP/1.1\r\nConnection: upgrade\r\n\r\n"), Ok(None));
+        assert_eq!(req(b"GET / HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: \r\n\r\n"),
+            Ok(None));
+        assert_eq!(req(b"GET / HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: a b\r\n\r\n"),
+            Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_masque_udp_target() {
+        assert_eq!(masque_udp_target("/.well-known/masque/udp/example.com/53/"),
+            Some(("example.com", 53)));
+        assert_eq!(masque_udp_target("/.well-known/masque/udp/2001%3Adb8%3A%3A42/443/"),
+            Some(("2001%3Adb8%3A%3A42", 443)));
+        assert_eq!(masque_udp_target("/.well-known/masque/udp/a/53"), None);
+        assert_eq!(masque_udp_target("/.well-known/masque/udp//53/"), None);
+        assert_eq!(masque_udp_target("/.well-known/masque/udp/a/b/53/"), None);
+        assert_eq!(masque_udp_target("/.well-known/masque/udp/a/65536/"), None);
+        assert_eq!(masque_udp_target("/.well-known/masque/ip/a/1/"), None);
+    }
+}
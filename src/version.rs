@@ -0,0 +1,59 @@
+//! Typed HTTP protocol versions.
+
+use {Error, Result};
+
+/// An HTTP protocol version [RFC7230§2.6].
+///
+/// Versions are ordered by major then minor number.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct Version {
+    /// Major version number.
+    pub major: u8,
+    /// Minor version number.
+    pub minor: u8,
+}
+
+impl Version {
+    /// HTTP/1.0.
+    pub const HTTP_10: Version = Version { major: 1, minor: 0 };
+
+    /// HTTP/1.1.
+    pub const HTTP_11: Version = Version { major: 1, minor: 1 };
+
+    /// Try to parse the given `HTTP/<major>.<minor>` version token.
+    ///
+    /// The `HTTP` name is case-sensitive, and each number must be a single digit.
+    pub fn parse(s: &str) -> Result<Self> {
+        match *s.as_bytes() {
+            [b'H', b'T', b'T', b'P', b'/', major, b'.', minor]
+                if major.is_ascii_digit() && minor.is_ascii_digit() =>
+                Ok(Version { major: major - b'0', minor: minor - b'0' }),
+            _ => Err(Error::Syntax),
+        }
+    }
+
+    /// Check if connections with this version persist by default [RFC7230§6.3].
+    pub fn is_persistent_default(&self) -> bool { *self >= Version::HTTP_11 }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version() {
+        assert_eq!(Version::parse("HTTP/1.1"), Ok(Version::HTTP_11));
+        assert_eq!(Version::parse("HTTP/1.0"), Ok(Version::HTTP_10));
+        assert_eq!(Version::parse("HTTP/2.0"), Ok(Version { major: 2, minor: 0 }));
+        assert_eq!(Version::parse("http/1.1"), Err(Error::Syntax));
+        assert_eq!(Version::parse("HTTP/1"), Err(Error::Syntax));
+        assert_eq!(Version::parse("HTTP/1.10"), Err(Error::Syntax));
+        assert_eq!(Version::parse("HTTP/a.1"), Err(Error::Syntax));
+        assert_eq!(Version::parse(""), Err(Error::Syntax));
+
+        assert!(Version::HTTP_10 < Version::HTTP_11);
+        assert!(Version::parse("HTTP/0.9").unwrap() < Version::HTTP_10);
+        assert!(!Version::HTTP_10.is_persistent_default());
+        assert!(Version::HTTP_11.is_persistent_default());
+    }
+}
@@ -0,0 +1,168 @@
+//! Push-style parsing of request heads into caller-defined output.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::visit::{visit, Visitor};
+//! use uhttp_request::{RequestLine, Result};
+//!
+//! #[derive(Default)]
+//! struct Req<'a> {
+//!     path: &'a str,
+//!     host: Option<&'a [u8]>,
+//! }
+//!
+//! impl<'a> Visitor<'a> for Req<'a> {
+//!     type Output = Self;
+//!
+//!     fn request_line(&mut self, line: RequestLine<'a>) -> Result<()> {
+//!         self.path = line.target;
+//!         Ok(())
+//!     }
+//!
+//!     fn header(&mut self, name: &'a str, val: &'a [u8]) -> Result<()> {
+//!         if name.eq_ignore_ascii_case("host") {
+//!             self.host = Some(val);
+//!         }
+//!
+//!         Ok(())
+//!     }
+//!
+//!     fn finish(self) -> Result<Self> { Ok(self) }
+//! }
+//!
+//! let (req, rest) = visit(b"GET /a HTTP/1.1\r\nHost: h\r\n\r\nbody", Req::default())
+//!     .unwrap();
+//!
+//! assert_eq!(req.path, "/a");
+//! assert_eq!(req.host, Some(&b" h"[..]));
+//! assert_eq!(rest, b"body");
+//! ```
+
+use {Error, Headers, ParserConfig, RequestLine, Result};
+
+/// Receiver of the components of a request head as they're parsed.
+///
+/// Components borrow from the parsed buffer, so they can be stored directly into the
+/// output without copying or collecting intermediate header fields.
+pub trait Visitor<'a> {
+    /// Value produced once the whole head has been visited.
+    type Output;
+
+    /// Receive the Request-Line.
+    ///
+    /// Returning an error stops parsing and returns that error.
+    fn request_line(&mut self, line: RequestLine<'a>) -> Result<()> {
+        let _ = line;
+        Ok(())
+    }
+
+    /// Receive a header field with the given trimmed name and raw value, in the order
+    /// they appear in the head.
+    ///
+    /// Returning an error stops parsing and returns that error.
+    fn header(&mut self, name: &'a str, val: &'a [u8]) -> Result<()>;
+
+    /// Produce the output after the terminating empty line has been parsed.
+    fn finish(self) -> Result<Self::Output>;
+}
+
+/// Try to parse the request head at the start of the given buffer, passing each
+/// component to the given visitor.
+///
+/// On success, return `Ok((output, rest))`, where `output` is produced by the visitor and
+/// `rest` is a slice that begins directly after the terminating empty line. The visitor
+/// may have already received some components when an error is returned, including
+/// `Error::Partial`, so parsing should be retried with a fresh visitor once more bytes
+/// arrive.
+pub fn visit<'a, V: Visitor<'a>>(buf: &'a [u8], visitor: V)
+    -> Result<(V::Output, &'a [u8])>
+{
+    visit_with_config(buf, visitor, ParserConfig::new())
+}
+
+/// Try to parse the request head at the start of the given buffer like `visit`, applying
+/// the limits in the given configuration.
+pub fn visit_with_config<'a, V: Visitor<'a>>(buf: &'a [u8], visitor: V,
+                                            config: ParserConfig)
+    -> Result<(V::Output, &'a [u8])>
+{
+    let max = match config.max_scan_len {
+        Some(max) if max < buf.len() => max,
+        _ => return parse(buf, visitor, config),
+    };
+
+    match parse(&buf[..max], visitor, config) {
+        Ok((out, rest)) => Ok((out, &buf[max - rest.len()..])),
+        Err(Error::Partial) => Err(Error::Budget),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse the head in the given buffer, scanning as far as needed.
+fn parse<'a, V: Visitor<'a>>(buf: &'a [u8], mut visitor: V, config: ParserConfig)
+    -> Result<(V::Output, &'a [u8])>
+{
+    let (line, block) = RequestLine::with_config(buf, config)?;
+    visitor.request_line(line)?;
+
+    let mut headers = Headers::with_config(block, config);
+
+    for h in headers.by_ref() {
+        let h = h?;
+        visitor.header(h.name, h.val)?;
+    }
+
+    Ok((visitor.finish()?, headers.into_inner()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ProxyConnection;
+
+    /// Visitor counting fields and recording the last name.
+    struct Count<'a>(usize, &'a str);
+
+    impl<'a> Visitor<'a> for Count<'a> {
+        type Output = (usize, &'a str);
+
+        fn header(&mut self, name: &'a str, _: &'a [u8]) -> Result<()> {
+            if name == "Bad" {
+                return Err(Error::Syntax);
+            }
+
+            self.0 += 1;
+            self.1 = name;
+
+            Ok(())
+        }
+
+        fn finish(self) -> Result<Self::Output> { Ok((self.0, self.1)) }
+    }
+
+    #[test]
+    fn test_visit() {
+        let req = b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\nrest";
+
+        assert_eq!(visit(req, Count(0, "")), Ok(((2, "B"), &b"rest"[..])));
+        assert_eq!(visit(&req[..20], Count(0, "")), Err(Error::Partial));
+        assert_eq!(visit(b"GET / HTTP/1.1\r\nBad: 1\r\n\r\n", Count(0, "")),
+            Err(Error::Syntax));
+        assert_eq!(visit(b"GET / HTTP/1.1\r\nA\r\n\r\n", Count(0, "")),
+            Err(Error::Syntax));
+
+        let config = ParserConfig {
+            proxy_connection: ProxyConnection::Strip,
+            max_scan_len: Some(49),
+            ..ParserConfig::new()
+        };
+
+        let req = b"GET / HTTP/1.1\r\nA: 1\r\nProxy-Connection: close\r\n\r\nrest";
+        assert_eq!(visit_with_config(req, Count(0, ""), config),
+            Ok(((1, "A"), &b"rest"[..])));
+
+        let config = ParserConfig { max_scan_len: Some(20), ..config };
+        assert_eq!(visit_with_config(req, Count(0, ""), config), Err(Error::Budget));
+    }
+}
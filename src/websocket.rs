@@ -0,0 +1,126 @@
+//! Helpers for the WebSocket opening handshake [RFC6455§4.2].
+
+use list::{self, ListValues};
+use {Error, Headers, Result};
+
+/// The only protocol version defined by [RFC6455].
+pub const VERSION: u8 = 13;
+
+/// Sec-WebSocket-Version value listing the supported versions, to send in a 426 Upgrade
+/// Required response [RFC6455§4.4].
+pub const SUPPORTED_VERSIONS: &str = "13";
+
+/// Outcome of checking the versions requested by the client.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum VersionNegotiation {
+    /// The client requested version 13, so the handshake can proceed.
+    Accepted,
+    /// The client didn't request a supported version, so the server should send a 426
+    /// Upgrade Required response with a Sec-WebSocket-Version header containing the
+    /// given value.
+    UpgradeRequired(&'static str),
+}
+
+/// Create an iterator over the versions in the given Sec-WebSocket-Version header
+/// value [RFC6455§11.3.5].
+///
+/// Empty list elements are skipped, and each version must be a decimal number from 0 to
+/// 255 without leading zeros.
+pub fn versions<'a>(val: &'a [u8]) -> Versions<'a> {
+    Versions(list::values(val))
+}
+
+/// Iterator over the versions in a Sec-WebSocket-Version header value.
+#[derive(Clone, Debug)]
+pub struct Versions<'a>(ListValues<'a>);
+
+impl<'a> Iterator for Versions<'a> {
+    type Item = Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.and_then(parse_version))
+    }
+}
+
+/// Parse the given version number.
+fn parse_version(v: &[u8]) -> Result<u8> {
+    if v.len() > 3 || !v.iter().all(u8::is_ascii_digit) || (v.len() > 1 && v[0] == b'0') {
+        return Err(Error::Syntax);
+    }
+
+    let n = v.iter().fold(0u16, |n, &b| n * 10 + (b - b'0') as u16);
+
+    if n > 255 { Err(Error::Syntax) } else { Ok(n as u8) }
+}
+
+/// Check the versions in every Sec-WebSocket-Version header in the given headers.
+///
+/// Return `Error::Syntax` if the header is missing or malformed, in which case the server
+/// should send a 400 Bad Request response [RFC6455§4.2.1].
+pub fn negotiate_version(headers: Headers) -> Result<VersionNegotiation> {
+    let mut found = false;
+    let mut accepted = false;
+
+    for h in headers {
+        let h = h?;
+
+        if !h.name.eq_ignore_ascii_case("Sec-WebSocket-Version") {
+            continue;
+        }
+
+        for v in versions(h.val) {
+            found = true;
+            accepted |= v? == VERSION;
+        }
+    }
+
+    if !found {
+        Err(Error::Syntax)
+    } else if accepted {
+        Ok(VersionNegotiation::Accepted)
+    } else {
+        Ok(VersionNegotiation::UpgradeRequired(SUPPORTED_VERSIONS))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_versions() {
+        let mut v = versions(b" 13, ,8,255");
+        assert_eq!(v.next(), Some(Ok(13)));
+        assert_eq!(v.next(), Some(Ok(8)));
+        assert_eq!(v.next(), Some(Ok(255)));
+        assert_eq!(v.next(), None);
+
+        let mut v = versions(b"13, \"8");
+        assert_eq!(v.next(), Some(Ok(13)));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), None);
+
+        let mut v = versions(b"0, 07, 256, x, 1 3");
+        assert_eq!(v.next(), Some(Ok(0)));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), Some(Err(Error::Syntax)));
+        assert_eq!(v.next(), None);
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        let neg = |h: &[u8]| negotiate_version(Headers::new(h));
+
+        assert_eq!(neg(b"Sec-WebSocket-Version: 13\r\n\r\n"),
+            Ok(VersionNegotiation::Accepted));
+        assert_eq!(neg(b"sec-websocket-version: 8\r\nSec-WebSocket-Version: 7, 13\r\n\r\n"),
+            Ok(VersionNegotiation::Accepted));
+        assert_eq!(neg(b"Sec-WebSocket-Version: 8\r\n\r\n"),
+            Ok(VersionNegotiation::UpgradeRequired("13")));
+        assert_eq!(neg(b"Host: a\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(neg(b"Sec-WebSocket-Version: \r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(neg(b"Sec-WebSocket-Version: 13, x\r\n\r\n"), Err(Error::Syntax));
+    }
+}
@@ -0,0 +1,779 @@
+//! Writers for emitting request heads and bodies into caller-provided buffers.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use uhttp_request::writer::{write_edited, Edit, Spans};
+//!
+//! let head = b"GET /a HTTP/1.1\r\nHost: example.com\r\nCookie: x\r\n\r\nbody";
+//!
+//! let (_, cookie) = Spans::new(head).unwrap()
+//!     .map(|r| r.unwrap())
+//!     .find(|&(h, _)| h.name == "Cookie")
+//!     .unwrap();
+//!
+//! let edits = [
+//!     Edit::Target("/b"),
+//!     Edit::Drop(cookie),
+//!     Edit::Insert("Via", b"1.1 proxy"),
+//! ];
+//!
+//! let mut buf = [0; 64];
+//! let len = write_edited(head, &edits, &mut buf).unwrap();
+//!
+//! assert_eq!(&buf[..len],
+//!     &b"GET /b HTTP/1.1\r\nHost: example.com\r\nVia: 1.1 proxy\r\n\r\n"[..]);
+//! ```
+
+use std::fmt;
+
+use grammar::{is_obs_text, is_ows, is_token, is_vchar, trim_ows};
+use {Error, Head, Header, Headers, ParserConfig, RequestLine, Result};
+
+/// Byte range within a request head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Span {
+    /// Offset of the first byte in the range.
+    pub start: usize,
+    /// Offset one past the last byte in the range.
+    pub end: usize,
+}
+
+/// Exact spans of the components of a Request-Line, relative to the start of the head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct LineSpans {
+    /// Span of any empty lines preceding the Request-Line.
+    pub leading: Span,
+    /// Span of the method.
+    pub method: Span,
+    /// Span of the request target.
+    pub target: Span,
+    /// Span of the HTTP version.
+    pub version: Span,
+    /// Span of the whole Request-Line, including its CRLF.
+    pub line: Span,
+}
+
+impl LineSpans {
+    /// Try to compute the spans of the Request-Line components in the given head, which
+    /// must begin with the Request-Line or the empty lines preceding it.
+    pub fn new(head: &[u8]) -> Result<Self> {
+        let (line, rest) = RequestLine::new(head)?;
+
+        let span = |s: &str| {
+            let start = offset(head, s.as_bytes()).ok_or(Error::Syntax)?;
+            Ok(Span { start, end: start + s.len() })
+        };
+
+        let method = span(line.method)?;
+
+        Ok(LineSpans {
+            leading: Span { start: 0, end: method.start },
+            method,
+            target: span(line.target)?,
+            version: span(line.version)?,
+            line: Span { start: method.start, end: head.len() - rest.len() },
+        })
+    }
+}
+
+/// Exact spans of the components of a header field line, relative to the start of the
+/// head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct FieldSpans {
+    /// Span of the name, including any surrounding whitespace, up to the colon.
+    pub name: Span,
+    /// Span of the raw value following the colon, including any surrounding whitespace.
+    pub val: Span,
+    /// Span of the whole field line, including its CRLF.
+    pub line: Span,
+}
+
+impl FieldSpans {
+    /// Try to compute the spans of the components of the given header field and field
+    /// line span, as yielded by `Spans` over the given head.
+    ///
+    /// Return `Error::Syntax` if the field doesn't lie within the head after the start of
+    /// the line.
+    pub fn new(head: &[u8], field: Header, line: Span) -> Result<Self> {
+        let start = offset(head, field.val).ok_or(Error::Syntax)?;
+
+        let end = match start.checked_sub(1) {
+            Some(end) if end >= line.start => end,
+            _ => return Err(Error::Syntax),
+        };
+
+        Ok(FieldSpans {
+            name: Span { start: line.start, end },
+            val: Span { start, end: start + field.val.len() },
+            line,
+        })
+    }
+}
+
+/// An edit to apply to a request head when passing it through.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Edit<'a> {
+    /// Drop the header field line at the given span, as yielded by `Spans`.
+    Drop(Span),
+    /// Insert a header field with the given name and value before the terminating empty
+    /// line.
+    Insert(&'a str, &'a [u8]),
+    /// Replace the request target.
+    Target(&'a str),
+}
+
+/// Iterator over the header fields in a request head, along with the span of each field
+/// line (including its CRLF) relative to the start of the head.
+pub struct Spans<'a> {
+    head: &'a [u8],
+    headers: Headers<'a>,
+}
+
+impl<'a> Spans<'a> {
+    /// Create a new `Spans` iterator over the given head, which must begin with the
+    /// Request-Line.
+    pub fn new(head: &'a [u8]) -> Result<Self> {
+        let (_, rest) = RequestLine::new(head)?;
+
+        Ok(Spans {
+            head,
+            headers: Headers::new(rest),
+        })
+    }
+
+    /// Offset of the next unprocessed byte relative to the start of the head.
+    pub(crate) fn pos(&self) -> usize { self.head.len() - self.headers.buf.len() }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Result<(Header<'a>, Span)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos();
+
+        let h = match self.headers.next()? {
+            Ok(h) => h,
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(Ok((h, Span { start, end: self.pos() })))
+    }
+}
+
+/// Write the given request head into the given buffer with the given edits applied.
+///
+/// Runs of the original head that aren't affected by any edit are copied as-is. If
+/// multiple `Target` edits are given, the last one wins. Inserted fields are written as
+/// `name: val` directly before the terminating empty line, in the order given. Any bytes
+/// following the head aren't written.
+///
+/// A replacement target must be nonempty and contain only visible ASCII, and each
+/// inserted field must have a token name and a value free of control characters other
+/// than tab, or `Error::Syntax` is returned, so untrusted edits can't inject lines.
+///
+/// On success, return the number of bytes written.
+pub fn write_edited(head: &[u8], edits: &[Edit], buf: &mut [u8]) -> Result<usize> {
+    let (line, _) = RequestLine::new(head)?;
+    let mut out = Cursor::new(buf);
+
+    let replaced = edits.iter().rev().filter_map(|e| match *e {
+        Edit::Target(t) => Some(t),
+        _ => None,
+    }).next();
+
+    let target = match replaced {
+        Some(t) if t.is_empty() || !t.bytes().all(is_vchar) => return Err(Error::Syntax),
+        Some(t) => t,
+        None => line.target,
+    };
+
+    let start = offset(head, line.target.as_bytes()).ok_or(Error::Syntax)?;
+
+    out.push(&head[..start])?;
+    out.push(target.as_bytes())?;
+
+    let mut spans = Spans::new(head)?;
+
+    // Start of the current run of unmodified bytes.
+    let mut run = start + line.target.len();
+
+    for next in spans.by_ref() {
+        let (_, span) = next?;
+
+        if edits.contains(&Edit::Drop(span)) {
+            out.push(&head[run..span.start])?;
+            run = span.end;
+        }
+    }
+
+    // Spans now points directly after the terminating empty line.
+    let end = spans.pos() - 2;
+    out.push(&head[run..end])?;
+
+    for edit in edits {
+        if let Edit::Insert(name, val) = *edit {
+            write_field(&mut out, name, val)?;
+        }
+    }
+
+    out.push(b"\r\n")?;
+
+    Ok(out.pos())
+}
+
+/// Write the given request head into the given buffer exactly as it appeared on the
+/// wire, including any leading empty lines and the original whitespace and case of every
+/// component.
+///
+/// The head is fully validated first. Any bytes following the head aren't written.
+///
+/// On success, return the number of bytes written.
+pub fn write_original(head: &[u8], buf: &mut [u8]) -> Result<usize> {
+    let mut spans = Spans::new(head)?;
+
+    for next in spans.by_ref() {
+        next?;
+    }
+
+    let mut out = Cursor::new(buf);
+    out.push(&head[..spans.pos()])?;
+
+    Ok(out.pos())
+}
+
+/// Check that the given bytes are exactly one request head that a strict peer would
+/// accept, such as one emitted by the other writers.
+///
+/// The head must parse under `ParserConfig::strict_rfc9112`, its Request-Line must pass
+/// `RequestLine::new_strict` with an HTTP/1.x version, every field name must be a token,
+/// and field values must not contain control characters other than tab [RFC7230§3].
+/// Empty lines preceding the Request-Line are allowed, but no bytes may follow the head.
+///
+/// Return `Error::Syntax` if any check fails.
+pub fn validate(head: &[u8]) -> Result<()> {
+    let (_, rest) = Head::with_config(head, ParserConfig::strict_rfc9112())?;
+
+    if !rest.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    let (line, _) = RequestLine::new_strict(head)?;
+
+    if line.version_parsed()?.major != 1 {
+        return Err(Error::Syntax);
+    }
+
+    for next in Spans::new(head)? {
+        let (field, span) = next?;
+        let spans = FieldSpans::new(head, field, span)?;
+
+        if !is_token(&head[spans.name.start..spans.name.end]) {
+            return Err(Error::Syntax);
+        }
+
+        if !is_field_value(trim_ows(field.val)) {
+            return Err(Error::Syntax);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the given bytes are a field value free of control characters other than tab
+/// [RFC7230§3.2].
+pub(crate) fn is_field_value(val: &[u8]) -> bool {
+    val.iter().all(|&b| is_vchar(b) || is_obs_text(b) || is_ows(b))
+}
+
+/// How the body of a written request is framed [RFC7230§3.3.3].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Framing {
+    /// Request has no body, so no framing header is written.
+    Empty,
+    /// Body has the given length, written as a Content-Length header.
+    Length(u64),
+    /// Body is sent with the chunked transfer coding, written as a Transfer-Encoding
+    /// header, with each chunk then written by `write_chunk` and the end of the body by
+    /// `write_last_chunk`.
+    Chunked,
+}
+
+/// Write a request head with the given method, target, headers, and body framing into
+/// the given buffer.
+///
+/// The framing header is written after the given headers, which shouldn't themselves
+/// include Content-Length or Transfer-Encoding. If `expect_continue` is set and the
+/// request has a body, an `Expect: 100-continue` header is also written
+/// [RFC7231§5.1.1], and a `BodyState` can be used to track when the body may be sent.
+///
+/// The method and each header name must be a token, the target must be nonempty and
+/// contain only visible ASCII, and header values must be free of control characters
+/// other than tab, or `Error::Syntax` is returned.
+///
+/// On success, return the number of bytes written.
+pub fn write_request(method: &str, target: &str, headers: &[(&str, &[u8])],
+                     framing: Framing, expect_continue: bool, buf: &mut [u8])
+    -> Result<usize>
+{
+    if !is_token(method.as_bytes()) || target.is_empty() || !target.bytes().all(is_vchar) {
+        return Err(Error::Syntax);
+    }
+
+    let mut out = Cursor::new(buf);
+
+    out.push(method.as_bytes())?;
+    out.push(b" ")?;
+    out.push(target.as_bytes())?;
+    out.push(b" HTTP/1.1\r\n")?;
+
+    for &(name, val) in headers {
+        write_field(&mut out, name, val)?;
+    }
+
+    match framing {
+        Framing::Empty => {},
+        Framing::Length(len) => {
+            let mut digits = [0; 20];
+            let start = write_digits(len, 10, &mut digits);
+            write_field(&mut out, "Content-Length", &digits[start..])?;
+        },
+        Framing::Chunked => write_field(&mut out, "Transfer-Encoding", b"chunked")?,
+    }
+
+    if expect_continue && framing != Framing::Empty {
+        write_field(&mut out, "Expect", b"100-continue")?;
+    }
+
+    out.push(b"\r\n")?;
+
+    Ok(out.pos())
+}
+
+/// Write the given data as a single chunk of a chunked body [RFC7230§4.1] into the given
+/// buffer.
+///
+/// The data must not be empty, since an empty chunk marks the end of the body.
+///
+/// On success, return the number of bytes written.
+pub fn write_chunk(data: &[u8], buf: &mut [u8]) -> Result<usize> {
+    if data.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    let mut digits = [0; 16];
+    let start = write_digits(data.len() as u64, 16, &mut digits);
+
+    let mut out = Cursor::new(buf);
+    out.push(&digits[start..])?;
+    out.push(b"\r\n")?;
+    out.push(data)?;
+    out.push(b"\r\n")?;
+
+    Ok(out.pos())
+}
+
+/// Write the last chunk and empty trailer that end a chunked body into the given
+/// buffer.
+///
+/// On success, return the number of bytes written.
+pub fn write_last_chunk(buf: &mut [u8]) -> Result<usize> {
+    let mut out = Cursor::new(buf);
+    out.push(b"0\r\n\r\n")?;
+
+    Ok(out.pos())
+}
+
+/// Whether a client may send the body of a request it has written.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum BodyState {
+    /// Request was sent with `Expect: 100-continue`, and the body should be held back
+    /// until a 100 Continue response arrives or a timeout elapses.
+    AwaitContinue,
+    /// Body may be sent.
+    Send,
+    /// Server sent a final response before the body was needed, so the body shouldn't be
+    /// sent, and the connection should be closed if any of the body was already sent or
+    /// was promised by the framing.
+    Abandon,
+}
+
+impl BodyState {
+    /// Create the initial state for a request, depending on whether it was written with
+    /// `Expect: 100-continue`.
+    pub const fn new(expect_continue: bool) -> Self {
+        if expect_continue { BodyState::AwaitContinue } else { BodyState::Send }
+    }
+
+    /// Advance the state given the status code of a response received while the body is
+    /// pending or being sent.
+    ///
+    /// A 100 Continue allows the body to be sent, and other interim responses are
+    /// ignored. A final error response (300 or above) means the server won't use the
+    /// body, while a final successful response only abandons a body that hasn't been
+    /// started yet [RFC7231§5.1.1].
+    pub fn on_status(self, status: u16) -> Self {
+        match (self, status) {
+            (BodyState::Abandon, _) => BodyState::Abandon,
+            (BodyState::AwaitContinue, 100) => BodyState::Send,
+            (s, 100..=199) => s,
+            (BodyState::AwaitContinue, _) => BodyState::Abandon,
+            (BodyState::Send, 200..=299) => BodyState::Send,
+            (BodyState::Send, _) => BodyState::Abandon,
+        }
+    }
+
+    /// Advance the state after waiting for a 100 Continue response for too long, since a
+    /// client shouldn't wait indefinitely before sending the body [RFC7231§5.1.1].
+    pub fn on_timeout(self) -> Self {
+        match self {
+            BodyState::AwaitContinue => BodyState::Send,
+            s => s,
+        }
+    }
+
+    /// Check if the body may currently be sent.
+    pub fn may_send(self) -> bool { self == BodyState::Send }
+}
+
+/// Write a single `name: val` header line, failing with `Error::Syntax` if the name
+/// isn't a token or the value contains control characters.
+pub(crate) fn write_field(out: &mut Cursor, name: &str, val: &[u8]) -> Result<()> {
+    if !is_token(name.as_bytes()) || !is_field_value(val) {
+        return Err(Error::Syntax);
+    }
+
+    out.push(name.as_bytes())?;
+    out.push(b": ")?;
+    out.push(val)?;
+    out.push(b"\r\n")
+}
+
+/// Write the digits of the given number in the given radix into the end of the given
+/// buffer, using uppercase hex digits, and return the offset of the first digit.
+pub(crate) fn write_digits(mut n: u64, radix: u64, buf: &mut [u8]) -> usize {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut pos = buf.len();
+
+    loop {
+        pos -= 1;
+        buf[pos] = DIGITS[(n % radix) as usize];
+        n /= radix;
+
+        if n == 0 {
+            return pos;
+        }
+    }
+}
+
+/// Appends bytes into a caller-provided buffer.
+pub(crate) struct Cursor<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Create a new `Cursor` that begins writing at the start of the given buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Cursor { buf, pos: 0 }
+    }
+
+    /// Append the given bytes, failing if they don't fit in the remaining space.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        let end = self.pos + bytes.len();
+
+        if end > self.buf.len() {
+            return Err(Error::Capacity);
+        }
+
+        self.buf[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+
+        Ok(())
+    }
+
+    /// Retrieve the number of bytes written so far.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// Retrieve the bytes written so far.
+    pub fn written(&self) -> &[u8] { &self.buf[..self.pos] }
+
+    /// Discard everything written after the given position.
+    pub fn truncate(&mut self, pos: usize) {
+        self.pos = std::cmp::min(self.pos, pos);
+    }
+}
+
+/// Adapter for formatting into a `Cursor`.
+pub(crate) struct FmtCursor<'a, 'b: 'a>(pub &'a mut Cursor<'b>);
+
+impl<'a, 'b> fmt::Write for FmtCursor<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.push(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Compute the offset of the given subslice from the start of the given slice, or
+/// `None` if it doesn't lie within the slice.
+pub(crate) fn offset(outer: &[u8], inner: &[u8]) -> Option<usize> {
+    let off = (inner.as_ptr() as usize).checked_sub(outer.as_ptr() as usize)?;
+
+    if off.checked_add(inner.len())? <= outer.len() { Some(off) } else { None }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spans() {
+        let head = b"GET / HTTP/1.1\r\nA: b\r\n  C :d\r\n\r\nbody";
+        let mut s = Spans::new(head).unwrap();
+
+        let (h, span) = s.next().unwrap().unwrap();
+        assert_eq!(h.name, "A");
+        assert_eq!(span, Span { start: 16, end: 22 });
+        assert_eq!(&head[span.start..span.end], b"A: b\r\n");
+
+        let (h, span) = s.next().unwrap().unwrap();
+        assert_eq!(h.name, "C");
+        assert_eq!(&head[span.start..span.end], b"  C :d\r\n");
+
+        assert!(s.next().is_none());
+
+        let mut s = Spans::new(b"GET / HTTP/1.1\r\nA\r\n\r\n").unwrap();
+        assert_eq!(s.next().unwrap(), Err(Error::Syntax));
+
+        assert!(Spans::new(b"GET / HTTP/1.1").is_err());
+    }
+
+    #[test]
+    fn test_component_spans() {
+        let head = b"\r\nGET /abc HTTP/1.1\r\n  Ab \t:\t x \r\nC:\r\n\r\n";
+        let at = |s: Span| &head[s.start..s.end];
+
+        let l = LineSpans::new(head).unwrap();
+        assert_eq!(at(l.leading), b"\r\n");
+        assert_eq!(at(l.method), b"GET");
+        assert_eq!(at(l.target), b"/abc");
+        assert_eq!(at(l.version), b"HTTP/1.1");
+        assert_eq!(at(l.line), b"GET /abc HTTP/1.1\r\n");
+
+        let mut s = Spans::new(head).unwrap();
+
+        let (h, line) = s.next().unwrap().unwrap();
+        let f = FieldSpans::new(head, h, line).unwrap();
+        assert_eq!(at(f.name), b"  Ab \t");
+        assert_eq!(at(f.val), b"\t x ");
+        assert_eq!(at(f.line), b"  Ab \t:\t x \r\n");
+
+        let (h, line) = s.next().unwrap().unwrap();
+        let f = FieldSpans::new(head, h, line).unwrap();
+        assert_eq!(at(f.name), b"C");
+        assert_eq!(at(f.val), b"");
+
+        assert_eq!(FieldSpans::new(b"A: b", h, line), Err(Error::Syntax));
+        assert_eq!(FieldSpans::new(&head[..4], h, line), Err(Error::Syntax));
+        assert_eq!(FieldSpans::new(head, h, Span { start: head.len(), end: head.len() }),
+            Err(Error::Syntax));
+
+        assert_eq!(LineSpans::new(b"GET / HTTP/1.1"), Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_write_original() {
+        let head = b"\r\nget /a HTTP/1.1\r\nhOsT :  x\t\r\nA:b\r\n\r\nbody";
+        let mut buf = [0; 64];
+
+        let len = write_original(head, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &head[..head.len() - 4]);
+
+        assert_eq!(write_original(head, &mut buf[..len - 1]), Err(Error::Capacity));
+        assert_eq!(write_original(b"GET / HTTP/1.1\r\nA\r\n\r\n", &mut buf),
+            Err(Error::Syntax));
+        assert_eq!(write_original(b"GET / HTTP/1.1\r\nA: b\r\n", &mut buf),
+            Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_write_edited() {
+        let head = b"\r\nGET /abc HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\nbody";
+        let mut buf = [0; 128];
+
+        let len = write_edited(head, &[], &mut buf).unwrap();
+        assert_eq!(&buf[..len], &head[..head.len() - 4]);
+
+        let spans = Spans::new(head).unwrap().map(|r| r.unwrap().1).collect::<Vec<_>>();
+
+        let len = write_edited(head, &[
+            Edit::Drop(spans[0]),
+            Edit::Drop(spans[2]),
+            Edit::Insert("D", b"4"),
+            Edit::Insert("E", b""),
+        ], &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"\r\nGET /abc HTTP/1.1\r\nB: 2\r\nD: 4\r\nE: \r\n\r\n"[..]);
+
+        let len = write_edited(head, &[
+            Edit::Target("/x"),
+            Edit::Drop(spans[1]),
+            Edit::Target("/yz"),
+        ], &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"\r\nGET /yz HTTP/1.1\r\nA: 1\r\nC: 3\r\n\r\n"[..]);
+
+        // Spans that don't match a field line are ignored.
+        let len = write_edited(head, &[
+            Edit::Drop(Span { start: 0, end: 2 }),
+        ], &mut buf).unwrap();
+        assert_eq!(&buf[..len], &head[..head.len() - 4]);
+
+        let e = |edits: &[Edit]| write_edited(head, edits, &mut [0; 128]);
+
+        assert_eq!(e(&[Edit::Target("/x HTTP/1.1\r\nA: b\r\n\r\nGET /y")]),
+            Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Target("/a b")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Target("")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Target("/x"), Edit::Target("/\n")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Insert("D", b"4\r\nX-Injected: 1")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Insert("D", b"4\n")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Insert("D: x\r\nE", b"4")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Insert("D E", b"4")]), Err(Error::Syntax));
+        assert_eq!(e(&[Edit::Insert("", b"4")]), Err(Error::Syntax));
+        assert!(e(&[Edit::Target("/%20?a=b"), Edit::Insert("D", b"\tx y")]).is_ok());
+
+        assert_eq!(write_edited(head, &[], &mut buf[..10]), Err(Error::Capacity));
+        assert_eq!(write_edited(b"GET / HTTP/1.1\r\nA: 1\r\n", &[], &mut buf),
+            Err(Error::Partial));
+    }
+
+    #[test]
+    fn test_write_request() {
+        let mut buf = [0; 128];
+
+        let len = write_request("PUT", "/up", &[("Host", b"h")], Framing::Chunked, true,
+            &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"PUT /up HTTP/1.1\r\nHost: h\r\n\
+            Transfer-Encoding: chunked\r\nExpect: 100-continue\r\n\r\n"[..]);
+
+        let len = write_request("POST", "/", &[], Framing::Length(1024), false, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], &b"POST / HTTP/1.1\r\nContent-Length: 1024\r\n\r\n"[..]);
+
+        let len = write_request("GET", "/", &[], Framing::Empty, true, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"GET / HTTP/1.1\r\n\r\n"[..]);
+
+        assert_eq!(write_request("GET", "/", &[], Framing::Empty, false, &mut buf[..5]),
+            Err(Error::Capacity));
+
+        let w = |method, target, headers: &[(&str, &[u8])]| {
+            write_request(method, target, headers, Framing::Empty, false, &mut [0; 128])
+        };
+
+        assert_eq!(w("GET / HTTP/1.1\r\nX:", "/", &[]), Err(Error::Syntax));
+        assert_eq!(w("", "/", &[]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/ HTTP/1.1\r\nX: y", &[]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/a b", &[]), Err(Error::Syntax));
+        assert_eq!(w("GET", "", &[]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/", &[("A\r\nB", b"c")]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/", &[("A:", b"c")]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/", &[("A", b"c\r\nB: d")]), Err(Error::Syntax));
+        assert_eq!(w("GET", "/", &[("A", b"c\0")]), Err(Error::Syntax));
+        assert!(w("GET", "http://a/b?c", &[("A", b"\tc d\xff")]).is_ok());
+    }
+
+    #[test]
+    fn test_write_chunk() {
+        let mut buf = [0; 64];
+
+        let len = write_chunk(b"hello", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"5\r\nhello\r\n"[..]);
+
+        let len = write_chunk(&[b'x'; 26], &mut buf).unwrap();
+        assert_eq!(&buf[..4], &b"1A\r\n"[..]);
+        assert_eq!(len, 32);
+
+        assert_eq!(write_chunk(b"", &mut buf), Err(Error::Syntax));
+        assert_eq!(write_chunk(b"hello", &mut buf[..8]), Err(Error::Capacity));
+
+        let len = write_last_chunk(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &b"0\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nHost: a\r\nB:\tc d\xff\r\nC:\r\n\r\n"),
+            Ok(()));
+        assert_eq!(validate(b"\r\nGET / HTTP/1.0\r\n\r\n"), Ok(()));
+
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nHost: a\r\n"), Err(Error::Partial));
+        assert_eq!(validate(b"GET / HTTP/1.1\r\n\r\nbody"), Err(Error::Syntax));
+        assert_eq!(validate(b"G\x7fET / HTTP/1.1\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET /\x01 HTTP/1.1\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / HTTP/2.0\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / http/1.1\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nHost : a\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nA\x01: a\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nA: b\x00\r\n\r\n"), Err(Error::Syntax));
+        assert_eq!(validate(b"GET / HTTP/1.1\r\nProxy-Connection: x\r\n\r\n"),
+            Err(Error::Syntax));
+    }
+
+    #[test]
+    fn test_snapshots() {
+        // Check the exact output of a writer, which must also pass validation.
+        fn snapshot(out: &[u8], expected: &[u8]) {
+            assert_eq!(out, expected);
+            assert_eq!(validate(out), Ok(()));
+        }
+
+        let mut buf = [0; 256];
+
+        let len = write_request("GET", "/", &[("Host", b"h")], Framing::Empty, false,
+            &mut buf).unwrap();
+        snapshot(&buf[..len], b"GET / HTTP/1.1\r\nHost: h\r\n\r\n");
+
+        let len = write_request("POST", "/up", &[("Host", b"h"), ("X", b"")],
+            Framing::Length(0), true, &mut buf).unwrap();
+        snapshot(&buf[..len], b"POST /up HTTP/1.1\r\nHost: h\r\nX: \r\n\
+            Content-Length: 0\r\nExpect: 100-continue\r\n\r\n");
+
+        let len = write_request("PUT", "*", &[], Framing::Chunked, false, &mut buf).unwrap();
+        snapshot(&buf[..len], b"PUT * HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n");
+
+        let head = b"\r\nPOST /a?b HTTP/1.1\r\nHost: h\r\nCookie: c\r\nA:b\r\n\r\nbody";
+
+        let len = write_original(head, &mut buf).unwrap();
+        snapshot(&buf[..len], b"\r\nPOST /a?b HTTP/1.1\r\nHost: h\r\n\
+            Cookie: c\r\nA:b\r\n\r\n");
+
+        let cookie = Spans::new(head).unwrap().nth(1).unwrap().unwrap().1;
+        let len = write_edited(head, &[
+            Edit::Target("/c"),
+            Edit::Drop(cookie),
+            Edit::Insert("Via", b"1.1 p"),
+        ], &mut buf).unwrap();
+        snapshot(&buf[..len], b"\r\nPOST /c HTTP/1.1\r\nHost: h\r\nA:b\r\n\
+            Via: 1.1 p\r\n\r\n");
+    }
+
+    #[test]
+    fn test_body_state() {
+        let s = BodyState::new(true);
+        assert!(!s.may_send());
+        assert_eq!(s.on_status(100), BodyState::Send);
+        assert_eq!(s.on_status(102), BodyState::AwaitContinue);
+        assert_eq!(s.on_status(200), BodyState::Abandon);
+        assert_eq!(s.on_status(417), BodyState::Abandon);
+        assert_eq!(s.on_timeout(), BodyState::Send);
+
+        let s = BodyState::new(false);
+        assert!(s.may_send());
+        assert_eq!(s.on_status(100), BodyState::Send);
+        assert_eq!(s.on_status(200), BodyState::Send);
+        assert_eq!(s.on_status(413), BodyState::Abandon);
+        assert_eq!(s.on_timeout(), BodyState::Send);
+
+        assert_eq!(BodyState::Abandon.on_status(100), BodyState::Abandon);
+        assert_eq!(BodyState::Abandon.on_timeout(), BodyState::Abandon);
+    }
+}